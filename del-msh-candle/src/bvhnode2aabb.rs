@@ -144,7 +144,7 @@ impl BvhForTriMesh {
             .inplace_op3(tri2vtx, vtx2xyz, &crate::elem2center::Layer {})?;
         self.sorted_morton_code.inplace_op2(
             &self.tri2center,
-            &crate::bvhnodes_morton::SortedMortonCode {},
+            &crate::bvhnodes_morton::SortedMortonCode { is_u64: false },
         )?;
         self.bvhnodes.inplace_op2(
             &self.sorted_morton_code,
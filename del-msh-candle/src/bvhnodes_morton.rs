@@ -1,7 +1,22 @@
 #[allow(unused_imports)]
 use candle_core::{CpuStorage, CudaStorage, Device, Layout, Tensor};
 
-pub struct SortedMortonCode {}
+/// sorted Morton codes stored in a `u32` tensor as `[idx2vtx, idx2morton, vtx2morton]`.
+/// * `is_u64` - use the 64-bit Morton code (21 bits for each axis).
+///   The size of the tensor is `num_vtx * 5` as each code is stored as two `u32` (lower bits first).
+///   Otherwise, the 32-bit code is used and the size of the tensor is `num_vtx * 3`
+pub struct SortedMortonCode {
+    pub is_u64: bool,
+}
+
+/// size of the tensor storing the sorted Morton codes
+pub fn num_sorted_morton_code(num_vtx: usize, is_u64: bool) -> usize {
+    if is_u64 {
+        num_vtx * 5
+    } else {
+        num_vtx * 3
+    }
+}
 
 impl candle_core::InplaceOp2 for SortedMortonCode {
     fn name(&self) -> &'static str {
@@ -21,12 +36,34 @@ impl candle_core::InplaceOp2 for SortedMortonCode {
             CpuStorage::U32(morton_data) => morton_data,
             _ => panic!(),
         };
-        assert_eq!(morton_data.len(), num_vtx * 3);
-        let (idx2vtx, idx2morton) = morton_data.split_at_mut(num_vtx);
-        let (idx2morton, vtx2morton) = idx2morton.split_at_mut(num_vtx);
-        del_msh_cpu::bvhnodes_morton::update_sorted_morton_code(
-            idx2vtx, idx2morton, vtx2morton, vtx2pos, num_dim,
+        assert_eq!(
+            morton_data.len(),
+            num_sorted_morton_code(num_vtx, self.is_u64)
         );
+        let (idx2vtx, idx2morton) = morton_data.split_at_mut(num_vtx);
+        if self.is_u64 {
+            let mut idx2morton64 = vec![0u64; num_vtx];
+            let mut vtx2morton64 = vec![0u64; num_vtx];
+            del_msh_cpu::bvhnodes_morton::update_sorted_morton_code(
+                idx2vtx,
+                &mut idx2morton64,
+                &mut vtx2morton64,
+                vtx2pos,
+                num_dim,
+            );
+            idx2morton
+                .chunks_mut(2)
+                .zip(idx2morton64.iter().chain(vtx2morton64.iter()))
+                .for_each(|(m32, &m64)| {
+                    m32[0] = m64 as u32;
+                    m32[1] = (m64 >> 32) as u32;
+                });
+        } else {
+            let (idx2morton, vtx2morton) = idx2morton.split_at_mut(num_vtx);
+            del_msh_cpu::bvhnodes_morton::update_sorted_morton_code(
+                idx2vtx, idx2morton, vtx2morton, vtx2pos, num_dim,
+            );
+        }
         Ok(())
     }
 
@@ -41,6 +78,9 @@ impl candle_core::InplaceOp2 for SortedMortonCode {
         use candle_core::backend::BackendDevice;
         use candle_core::cuda_backend::CudaStorageSlice;
         use candle_core::cuda_backend::WrapErr;
+        if self.is_u64 {
+            candle_core::bail!("64-bit Morton code is not supported on CUDA");
+        }
         get_cuda_slice_device_from_storage_u32!(
             sorted_morton_code,
             dev_sorted_morton_code,
@@ -98,8 +138,8 @@ impl candle_core::InplaceOp2 for BvhNodesFromSortedMortonCode {
         morton_data: &CpuStorage,
         l_morton_data: &Layout,
     ) -> candle_core::Result<()> {
-        let num_vtx = l_morton_data.dims()[0] / 3;
         assert_eq!(l_bvhnodes.dims()[1], 3);
+        let num_vtx = l_bvhnodes.dims()[0].div_ceil(2);
         assert_eq!(l_bvhnodes.dims()[0], num_vtx * 2 - 1);
         let bvhnodes = match bvhnodes {
             CpuStorage::U32(bvhnodes) => bvhnodes,
@@ -107,8 +147,20 @@ impl candle_core::InplaceOp2 for BvhNodesFromSortedMortonCode {
         };
         let morton_data = morton_data.as_slice::<u32>()?;
         let (idx2vtx, idx2morton) = morton_data.split_at(num_vtx);
-        let (idx2morton, _vtx2morton) = idx2morton.split_at(num_vtx);
-        del_msh_cpu::bvhnodes_morton::update_bvhnodes(bvhnodes, idx2vtx, idx2morton);
+        if l_morton_data.dims()[0] == num_sorted_morton_code(num_vtx, true) {
+            let idx2morton: Vec<u64> = idx2morton[..num_vtx * 2]
+                .chunks(2)
+                .map(|m32| (m32[0] as u64) | ((m32[1] as u64) << 32))
+                .collect();
+            del_msh_cpu::bvhnodes_morton::update_bvhnodes(bvhnodes, idx2vtx, &idx2morton);
+        } else {
+            assert_eq!(
+                l_morton_data.dims()[0],
+                num_sorted_morton_code(num_vtx, false)
+            );
+            let (idx2morton, _vtx2morton) = idx2morton.split_at(num_vtx);
+            del_msh_cpu::bvhnodes_morton::update_bvhnodes(bvhnodes, idx2vtx, idx2morton);
+        }
         Ok(())
     }
 
@@ -124,8 +176,11 @@ impl candle_core::InplaceOp2 for BvhNodesFromSortedMortonCode {
         use candle_core::cuda_backend::CudaStorageSlice;
         use candle_core::cuda_backend::WrapErr;
         get_cuda_slice_device_from_storage_u32!(bvhnodes, device_bvhnodes, bvhnodes);
-        let num_vtx = l_morton_data.dim(0)? / 3;
+        let num_vtx = l_bvhnodes.dim(0)?.div_ceil(2);
         assert_eq!(l_bvhnodes.dims(), &[num_vtx * 2 - 1, 3]);
+        if l_morton_data.dim(0)? != num_sorted_morton_code(num_vtx, false) {
+            candle_core::bail!("64-bit Morton code is not supported on CUDA");
+        }
         get_cuda_slice_device_from_storage_u32!(
             sorted_morton_code,
             device_sorted_morton_code,
@@ -166,7 +221,7 @@ pub fn from_trimesh(tri2vtx: &Tensor, vtx2xyz: &Tensor) -> anyhow::Result<Tensor
     )?;
     // -----------------
     tri2center.inplace_op3(tri2vtx, vtx2xyz, &crate::elem2center::Layer {})?;
-    sorted_morton_code.inplace_op2(&tri2center, &SortedMortonCode {})?;
+    sorted_morton_code.inplace_op2(&tri2center, &SortedMortonCode { is_u64: false })?;
     bvhnodes.inplace_op2(&sorted_morton_code, &BvhNodesFromSortedMortonCode {})?;
     Ok(bvhnodes)
 }
@@ -192,7 +247,7 @@ fn test_from_vtx2xyz() -> anyhow::Result<()> {
         candle_core::DType::U32,
         &candle_core::Device::Cpu,
     )?;
-    sorted_morton_code.inplace_op2(&vtx2xyz, &SortedMortonCode {})?;
+    sorted_morton_code.inplace_op2(&vtx2xyz, &SortedMortonCode { is_u64: false })?;
     bvhnodes.inplace_op2(&sorted_morton_code, &BvhNodesFromSortedMortonCode {})?;
     {
         let bvhnodes = bvhnodes.flatten_all()?.to_vec1::<u32>()?;
@@ -201,6 +256,36 @@ fn test_from_vtx2xyz() -> anyhow::Result<()> {
     Ok(())
 }
 
+#[test]
+fn test_from_vtx2xyz_u64() -> anyhow::Result<()> {
+    let (tri2vtx, vtx2xyz) =
+        del_msh_cpu::trimesh3_primitive::torus_zup::<u32, f32>(1.0, 0.3, 32, 32);
+    let num_tri = tri2vtx.len() / 3;
+    let tri2center =
+        del_msh_cpu::elem2center::from_uniform_mesh_as_points::<u32, f32>(&tri2vtx, 3, &vtx2xyz, 3);
+    let tri2center =
+        candle_core::Tensor::from_vec(tri2center, (num_tri, 3), &candle_core::Device::Cpu)?;
+    let sorted_morton_code = candle_core::Tensor::zeros(
+        num_sorted_morton_code(num_tri, true),
+        candle_core::DType::U32,
+        &candle_core::Device::Cpu,
+    )?;
+    let bvhnodes = candle_core::Tensor::zeros(
+        (num_tri * 2 - 1, 3),
+        candle_core::DType::U32,
+        &candle_core::Device::Cpu,
+    )?;
+    sorted_morton_code.inplace_op2(&tri2center, &SortedMortonCode { is_u64: true })?;
+    bvhnodes.inplace_op2(&sorted_morton_code, &BvhNodesFromSortedMortonCode {})?;
+    let bvhnodes = bvhnodes.flatten_all()?.to_vec1::<u32>()?;
+    del_msh_cpu::bvhnodes::check_bvh_topology(&bvhnodes, num_tri);
+    let bvhnodes_cpu = del_msh_cpu::bvhnodes_morton::from_triangle_mesh_with_morton_code::<u32, u64>(
+        &tri2vtx, &vtx2xyz, 3,
+    );
+    assert_eq!(bvhnodes, bvhnodes_cpu);
+    Ok(())
+}
+
 #[test]
 #[allow(unused_variables)]
 fn test_from_trimesh3() -> anyhow::Result<()> {
@@ -234,7 +319,7 @@ fn test_from_trimesh3() -> anyhow::Result<()> {
     // -----------------
     tri2center.inplace_op3(&tri2vtx, &vtx2xyz, &crate::elem2center::Layer {})?;
     let tri2center_cpu = tri2center.flatten_all()?.to_vec1::<f32>()?;
-    sorted_morton_code.inplace_op2(&tri2center, &SortedMortonCode {})?;
+    sorted_morton_code.inplace_op2(&tri2center, &SortedMortonCode { is_u64: false })?;
     let sorted_morton_code_cpu = sorted_morton_code.flatten_all()?.to_vec1::<u32>()?;
     bvhnodes.inplace_op2(&sorted_morton_code, &BvhNodesFromSortedMortonCode {})?;
     let bvhnodes_cpu = bvhnodes.flatten_all()?.to_vec1::<u32>()?;
@@ -253,7 +338,7 @@ fn test_from_trimesh3() -> anyhow::Result<()> {
                 assert_eq!(a, b);
             });
         let sorted_morton_code = sorted_morton_code.zeros_like()?.to_device(&device)?;
-        sorted_morton_code.inplace_op2(&tri2center, &SortedMortonCode {})?;
+        sorted_morton_code.inplace_op2(&tri2center, &SortedMortonCode { is_u64: false })?;
         let sorted_morton_code_gpu = sorted_morton_code.flatten_all()?.to_vec1::<u32>()?;
         sorted_morton_code_cpu
             .iter()
@@ -75,6 +75,7 @@ fn main() -> anyhow::Result<()> {
                 dist0: 0.01,
                 alpha: 0.01,
                 num_iter: 20,
+                tolerance: 0.0,
             },
         );
     del_msh_cpu::io_obj::save_tri2vtx_vtx2xyz(
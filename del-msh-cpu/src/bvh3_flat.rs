@@ -0,0 +1,592 @@
+//! 3D BVH layouts for fast traversal converted from the binary `bvhnodes`
+//! * `BvhFlat3` - depth-first flattened binary tree with skip pointers for the stackless traversal
+//! * `BvhWide3` - 4-ary tree with the AABBs of the children stored side by side
+//!
+//! In both layouts, a leaf holds a range of the elements `idx2elem[begin..end]`
+
+use num_traits::AsPrimitive;
+
+/// the number of the elements under each node of the binary `bvhnodes`
+fn subtree_size<Index>(bvhnodes: &[Index], i_bvhnode: usize, node2cnt: &mut [usize]) -> usize
+where
+    Index: num_traits::PrimInt + AsPrimitive<usize>,
+{
+    let cnt = if bvhnodes[i_bvhnode * 3 + 2] == Index::max_value() {
+        1
+    } else {
+        subtree_size(bvhnodes, bvhnodes[i_bvhnode * 3 + 1].as_(), node2cnt)
+            + subtree_size(bvhnodes, bvhnodes[i_bvhnode * 3 + 2].as_(), node2cnt)
+    };
+    node2cnt[i_bvhnode] = cnt;
+    cnt
+}
+
+/// append the elements under the node of the binary `bvhnodes`
+fn collect_elements<Index>(bvhnodes: &[Index], i_bvhnode: usize, idx2elem: &mut Vec<usize>)
+where
+    Index: num_traits::PrimInt + AsPrimitive<usize>,
+{
+    if bvhnodes[i_bvhnode * 3 + 2] == Index::max_value() {
+        idx2elem.push(bvhnodes[i_bvhnode * 3 + 1].as_());
+        return;
+    }
+    collect_elements(bvhnodes, bvhnodes[i_bvhnode * 3 + 1].as_(), idx2elem);
+    collect_elements(bvhnodes, bvhnodes[i_bvhnode * 3 + 2].as_(), idx2elem);
+}
+
+/// range of the parameter where the line `org + t * dir` is inside the AABB.
+/// The range is empty (`t0 > t1`) if they do not intersect
+fn range_line_aabb(
+    aabb_min: [f32; 3],
+    aabb_max: [f32; 3],
+    org: &[f32; 3],
+    dir_inv: &[f32; 3],
+) -> (f32, f32) {
+    let mut t0 = f32::NEG_INFINITY;
+    let mut t1 = f32::INFINITY;
+    for i_dim in 0..3 {
+        let s0 = (aabb_min[i_dim] - org[i_dim]) * dir_inv[i_dim];
+        let s1 = (aabb_max[i_dim] - org[i_dim]) * dir_inv[i_dim];
+        t0 = t0.max(s0.min(s1));
+        t1 = t1.min(s0.max(s1));
+    }
+    (t0, t1)
+}
+
+fn inverse_dir(dir: &[f32; 3]) -> [f32; 3] {
+    [1f32 / dir[0], 1f32 / dir[1], 1f32 / dir[2]]
+}
+
+/// depth-first flattened BVH. The first child of an internal node `i` is `i+1`
+/// and `node2skip[i]` is the node visited next when the subtree of `i` is skipped.
+/// A node `i` is a leaf if `node2skip[i] == i + 1`
+pub struct BvhFlat3 {
+    pub node2aabb: Vec<f32>,
+    pub node2skip: Vec<usize>,
+    /// the elements under the node `i` are `idx2elem[node2range[i*2]..node2range[i*2+1]]`
+    pub node2range: Vec<usize>,
+    pub idx2elem: Vec<usize>,
+}
+
+impl BvhFlat3 {
+    /// * `aabbs` - AABBs of the nodes of `bvhnodes` (e.g., from `bvhnode2aabb3`)
+    /// * `max_leaf_size` - the subtrees with this number of elements or fewer become leaves
+    pub fn from_bvhnodes<Index>(
+        bvhnodes: &[Index],
+        aabbs: &[f32],
+        i_bvhnode_root: usize,
+        max_leaf_size: usize,
+    ) -> Self
+    where
+        Index: num_traits::PrimInt + AsPrimitive<usize>,
+    {
+        assert_eq!(bvhnodes.len() / 3, aabbs.len() / 6);
+        assert!(max_leaf_size > 0);
+        let mut node2cnt = vec![0usize; bvhnodes.len() / 3];
+        subtree_size(bvhnodes, i_bvhnode_root, &mut node2cnt);
+        let mut bvh = BvhFlat3 {
+            node2aabb: vec![],
+            node2skip: vec![],
+            node2range: vec![],
+            idx2elem: vec![],
+        };
+        bvh.build(bvhnodes, aabbs, i_bvhnode_root, &node2cnt, max_leaf_size);
+        bvh
+    }
+
+    fn build<Index>(
+        &mut self,
+        bvhnodes: &[Index],
+        aabbs: &[f32],
+        i_bvhnode: usize,
+        node2cnt: &[usize],
+        max_leaf_size: usize,
+    ) where
+        Index: num_traits::PrimInt + AsPrimitive<usize>,
+    {
+        let i_node = self.num_node();
+        self.node2aabb
+            .extend_from_slice(&aabbs[i_bvhnode * 6..(i_bvhnode + 1) * 6]);
+        self.node2skip.push(usize::MAX);
+        self.node2range.extend([self.idx2elem.len(), usize::MAX]);
+        if node2cnt[i_bvhnode] <= max_leaf_size {
+            collect_elements(bvhnodes, i_bvhnode, &mut self.idx2elem);
+        } else {
+            for i_child in 1..3 {
+                let j_bvhnode: usize = bvhnodes[i_bvhnode * 3 + i_child].as_();
+                self.build(bvhnodes, aabbs, j_bvhnode, node2cnt, max_leaf_size);
+            }
+        }
+        self.node2range[i_node * 2 + 1] = self.idx2elem.len();
+        self.node2skip[i_node] = self.num_node();
+    }
+
+    pub fn num_node(&self) -> usize {
+        self.node2skip.len()
+    }
+
+    pub fn is_leaf(&self, i_node: usize) -> bool {
+        self.node2skip[i_node] == i_node + 1
+    }
+
+    fn aabb(&self, i_node: usize) -> ([f32; 3], [f32; 3]) {
+        let a = &self.node2aabb[i_node * 6..(i_node + 1) * 6];
+        ([a[0], a[1], a[2]], [a[3], a[4], a[5]])
+    }
+
+    /// stackless traversal. The subtree of a node is visited if `is_visit(aabb_min, aabb_max)`
+    /// and `leaf(idx2elem_of_leaf)` is called for the visited leaves
+    pub fn traverse<F, G>(&self, mut is_visit: F, mut leaf: G)
+    where
+        F: FnMut([f32; 3], [f32; 3]) -> bool,
+        G: FnMut(&[usize]),
+    {
+        let mut i_node = 0;
+        while i_node < self.num_node() {
+            let (aabb_min, aabb_max) = self.aabb(i_node);
+            if !is_visit(aabb_min, aabb_max) {
+                i_node = self.node2skip[i_node];
+                continue;
+            }
+            if self.is_leaf(i_node) {
+                let (i0, i1) = (self.node2range[i_node * 2], self.node2range[i_node * 2 + 1]);
+                leaf(&self.idx2elem[i0..i1]);
+            }
+            i_node += 1;
+        }
+    }
+
+    /// same as `search_bvh3::intersections_ray`
+    pub fn intersections_ray<Index>(
+        &self,
+        hits: &mut Vec<(f32, usize)>,
+        ray_org: &[f32; 3],
+        ray_dir: &[f32; 3],
+        tri2vtx: &[Index],
+        vtx2xyz: &[f32],
+    ) where
+        Index: AsPrimitive<usize>,
+    {
+        let dir_inv = inverse_dir(ray_dir);
+        self.traverse(
+            |aabb_min, aabb_max| {
+                let (t0, t1) = range_line_aabb(aabb_min, aabb_max, ray_org, &dir_inv);
+                t0 <= t1 && t1 >= 0f32
+            },
+            |idx2tri| {
+                for &i_tri in idx2tri {
+                    if let Some(t) = crate::trimesh3::to_tri3(tri2vtx, vtx2xyz, i_tri)
+                        .intersection_against_ray(ray_org, ray_dir)
+                    {
+                        hits.push((t, i_tri));
+                    }
+                }
+            },
+        );
+    }
+
+    /// same as `search_bvh3::intersections_line`
+    pub fn intersections_line<Index>(
+        &self,
+        hits: &mut Vec<(f32, usize)>,
+        line_org: &[f32; 3],
+        line_dir: &[f32; 3],
+        tri2vtx: &[Index],
+        vtx2xyz: &[f32],
+    ) where
+        Index: AsPrimitive<usize>,
+    {
+        let dir_inv = inverse_dir(line_dir);
+        self.traverse(
+            |aabb_min, aabb_max| {
+                let (t0, t1) = range_line_aabb(aabb_min, aabb_max, line_org, &dir_inv);
+                t0 <= t1
+            },
+            |idx2tri| {
+                for &i_tri in idx2tri {
+                    if let Some(t) = crate::trimesh3::to_tri3(tri2vtx, vtx2xyz, i_tri)
+                        .intersection_against_line(line_org, line_dir)
+                    {
+                        hits.push((t, i_tri));
+                    }
+                }
+            },
+        );
+    }
+
+    /// same as `search_bvh3::first_intersection_ray`.
+    /// The nodes farther than the closest hit found so far are skipped
+    pub fn first_intersection_ray<Index>(
+        &self,
+        ray_org: &[f32; 3],
+        ray_dir: &[f32; 3],
+        tri2vtx: &[Index],
+        vtx2xyz: &[f32],
+        dis: f32,
+    ) -> Option<(f32, usize)>
+    where
+        Index: AsPrimitive<usize>,
+    {
+        let dir_inv = inverse_dir(ray_dir);
+        let mut res: Option<(f32, usize)> = None;
+        let mut t_max = dis;
+        let mut i_node = 0;
+        while i_node < self.num_node() {
+            let (aabb_min, aabb_max) = self.aabb(i_node);
+            let (t0, t1) = range_line_aabb(aabb_min, aabb_max, ray_org, &dir_inv);
+            if t0 > t1 || t1 < 0f32 || t0 >= t_max {
+                i_node = self.node2skip[i_node];
+                continue;
+            }
+            if self.is_leaf(i_node) {
+                let (i0, i1) = (self.node2range[i_node * 2], self.node2range[i_node * 2 + 1]);
+                for &i_tri in &self.idx2elem[i0..i1] {
+                    let Some(t) = crate::trimesh3::to_tri3(tri2vtx, vtx2xyz, i_tri)
+                        .intersection_against_ray(ray_org, ray_dir)
+                    else {
+                        continue;
+                    };
+                    if t < t_max {
+                        t_max = t;
+                        res = Some((t, i_tri));
+                    }
+                }
+            }
+            i_node += 1;
+        }
+        res
+    }
+}
+
+/// 4-ary BVH. The AABBs of the four children of a node are stored side by side
+/// (structure of arrays) so that they can be tested together.
+/// A node has fewer than four children if the subtree is small. The unused slots come last
+/// and have the inverted AABB (`min = +inf`, `max = -inf`), which the slab test does not reject,
+/// so the traversal skips them using `node2num_child`
+pub struct BvhWide3 {
+    /// `node2aabbs[i_node][i_coord][i_child]` where `i_coord` is `min_x, min_y, min_z, max_x, max_y, max_z`
+    pub node2aabbs: Vec<[[f32; 4]; 6]>,
+    /// the index of the child node or `usize::MAX` if the child is a leaf (or unused)
+    pub node2child: Vec<[usize; 4]>,
+    /// number of the used child slots
+    pub node2num_child: Vec<usize>,
+    /// the elements of the leaf child are `idx2elem[node2range[i][i_child][0]..node2range[i][i_child][1]]`
+    pub node2range: Vec<[[usize; 2]; 4]>,
+    pub idx2elem: Vec<usize>,
+}
+
+impl BvhWide3 {
+    /// * `aabbs` - AABBs of the nodes of `bvhnodes` (e.g., from `bvhnode2aabb3`)
+    /// * `max_leaf_size` - the subtrees with this number of elements or fewer become leaves
+    pub fn from_bvhnodes<Index>(
+        bvhnodes: &[Index],
+        aabbs: &[f32],
+        i_bvhnode_root: usize,
+        max_leaf_size: usize,
+    ) -> Self
+    where
+        Index: num_traits::PrimInt + AsPrimitive<usize>,
+    {
+        assert_eq!(bvhnodes.len() / 3, aabbs.len() / 6);
+        assert!(max_leaf_size > 0);
+        let mut node2cnt = vec![0usize; bvhnodes.len() / 3];
+        subtree_size(bvhnodes, i_bvhnode_root, &mut node2cnt);
+        let mut bvh = BvhWide3 {
+            node2aabbs: vec![],
+            node2child: vec![],
+            node2num_child: vec![],
+            node2range: vec![],
+            idx2elem: vec![],
+        };
+        bvh.build(bvhnodes, aabbs, i_bvhnode_root, &node2cnt, max_leaf_size);
+        bvh
+    }
+
+    fn build<Index>(
+        &mut self,
+        bvhnodes: &[Index],
+        aabbs: &[f32],
+        i_bvhnode: usize,
+        node2cnt: &[usize],
+        max_leaf_size: usize,
+    ) -> usize
+    where
+        Index: num_traits::PrimInt + AsPrimitive<usize>,
+    {
+        let is_leaf = |j_bvhnode: usize| node2cnt[j_bvhnode] <= max_leaf_size;
+        let half_area = |j_bvhnode: usize| {
+            let a = &aabbs[j_bvhnode * 6..(j_bvhnode + 1) * 6];
+            let (lx, ly, lz) = (a[3] - a[0], a[4] - a[1], a[5] - a[2]);
+            lx * ly + ly * lz + lz * lx
+        };
+        // open the largest internal node until there are four children
+        let mut children = vec![i_bvhnode];
+        while children.len() < 4 {
+            let Some(i_open) = (0..children.len())
+                .filter(|&i| !is_leaf(children[i]))
+                .max_by(|&i, &j| half_area(children[i]).total_cmp(&half_area(children[j])))
+            else {
+                break;
+            };
+            let j_bvhnode = children.remove(i_open);
+            children.push(bvhnodes[j_bvhnode * 3 + 1].as_());
+            children.push(bvhnodes[j_bvhnode * 3 + 2].as_());
+        }
+        let i_node = self.node2child.len();
+        let mut aabbs4 = [[f32::INFINITY; 4]; 6];
+        aabbs4[3..].fill([f32::NEG_INFINITY; 4]);
+        self.node2aabbs.push(aabbs4);
+        self.node2child.push([usize::MAX; 4]);
+        self.node2num_child.push(children.len());
+        self.node2range.push([[0; 2]; 4]);
+        for (i_child, &j_bvhnode) in children.iter().enumerate() {
+            for i_coord in 0..6 {
+                self.node2aabbs[i_node][i_coord][i_child] = aabbs[j_bvhnode * 6 + i_coord];
+            }
+            if is_leaf(j_bvhnode) {
+                let i0 = self.idx2elem.len();
+                collect_elements(bvhnodes, j_bvhnode, &mut self.idx2elem);
+                self.node2range[i_node][i_child] = [i0, self.idx2elem.len()];
+            } else {
+                let j_node = self.build(bvhnodes, aabbs, j_bvhnode, node2cnt, max_leaf_size);
+                self.node2child[i_node][i_child] = j_node;
+            }
+        }
+        i_node
+    }
+
+    pub fn num_node(&self) -> usize {
+        self.node2child.len()
+    }
+
+    /// the ranges of the parameter `t` where the line `org + t * dir` is inside the four AABBs
+    fn ranges_line_aabbs4(
+        &self,
+        i_node: usize,
+        org: &[f32; 3],
+        dir_inv: &[f32; 3],
+    ) -> ([f32; 4], [f32; 4]) {
+        let aabbs4 = &self.node2aabbs[i_node];
+        let mut t0 = [f32::NEG_INFINITY; 4];
+        let mut t1 = [f32::INFINITY; 4];
+        for i_dim in 0..3 {
+            for i_child in 0..4 {
+                let s0 = (aabbs4[i_dim][i_child] - org[i_dim]) * dir_inv[i_dim];
+                let s1 = (aabbs4[i_dim + 3][i_child] - org[i_dim]) * dir_inv[i_dim];
+                t0[i_child] = t0[i_child].max(s0.min(s1));
+                t1[i_child] = t1[i_child].min(s0.max(s1));
+            }
+        }
+        (t0, t1)
+    }
+
+    /// the hits of the line `org + t * dir` with `t_min <= t`
+    fn intersections<Index>(
+        &self,
+        hits: &mut Vec<(f32, usize)>,
+        org: &[f32; 3],
+        dir: &[f32; 3],
+        t_min: f32,
+        tri2vtx: &[Index],
+        vtx2xyz: &[f32],
+    ) where
+        Index: AsPrimitive<usize>,
+    {
+        let dir_inv = inverse_dir(dir);
+        let mut stack = vec![0];
+        while let Some(i_node) = stack.pop() {
+            let (t0, t1) = self.ranges_line_aabbs4(i_node, org, &dir_inv);
+            for i_child in 0..self.node2num_child[i_node] {
+                if t0[i_child] > t1[i_child] || t1[i_child] < t_min {
+                    continue;
+                }
+                let j_node = self.node2child[i_node][i_child];
+                if j_node != usize::MAX {
+                    stack.push(j_node);
+                    continue;
+                }
+                let [i0, i1] = self.node2range[i_node][i_child];
+                for &i_tri in &self.idx2elem[i0..i1] {
+                    if let Some(t) = crate::trimesh3::to_tri3(tri2vtx, vtx2xyz, i_tri)
+                        .intersection_against_line(org, dir)
+                        .filter(|&t| t >= t_min)
+                    {
+                        hits.push((t, i_tri));
+                    }
+                }
+            }
+        }
+    }
+
+    /// same as `search_bvh3::intersections_ray`
+    pub fn intersections_ray<Index>(
+        &self,
+        hits: &mut Vec<(f32, usize)>,
+        ray_org: &[f32; 3],
+        ray_dir: &[f32; 3],
+        tri2vtx: &[Index],
+        vtx2xyz: &[f32],
+    ) where
+        Index: AsPrimitive<usize>,
+    {
+        self.intersections(hits, ray_org, ray_dir, 0f32, tri2vtx, vtx2xyz);
+    }
+
+    /// same as `search_bvh3::intersections_line`
+    pub fn intersections_line<Index>(
+        &self,
+        hits: &mut Vec<(f32, usize)>,
+        line_org: &[f32; 3],
+        line_dir: &[f32; 3],
+        tri2vtx: &[Index],
+        vtx2xyz: &[f32],
+    ) where
+        Index: AsPrimitive<usize>,
+    {
+        self.intersections(
+            hits,
+            line_org,
+            line_dir,
+            f32::NEG_INFINITY,
+            tri2vtx,
+            vtx2xyz,
+        );
+    }
+
+    /// same as `search_bvh3::first_intersection_ray`.
+    /// The children are visited from the nearest one
+    pub fn first_intersection_ray<Index>(
+        &self,
+        ray_org: &[f32; 3],
+        ray_dir: &[f32; 3],
+        tri2vtx: &[Index],
+        vtx2xyz: &[f32],
+        dis: f32,
+    ) -> Option<(f32, usize)>
+    where
+        Index: AsPrimitive<usize>,
+    {
+        let dir_inv = inverse_dir(ray_dir);
+        let mut res: Option<(f32, usize)> = None;
+        let mut t_max = dis;
+        // (node, entry parameter of the node)
+        let mut stack = vec![(0, 0f32)];
+        while let Some((i_node, t_entry)) = stack.pop() {
+            if t_entry >= t_max {
+                continue;
+            }
+            let (t0, t1) = self.ranges_line_aabbs4(i_node, ray_org, &dir_inv);
+            let mut children: Vec<(usize, f32)> = (0..self.node2num_child[i_node])
+                .filter(|&i_child| {
+                    t0[i_child] <= t1[i_child] && t1[i_child] >= 0f32 && t0[i_child] < t_max
+                })
+                .map(|i_child| (i_child, t0[i_child].max(0f32)))
+                .collect();
+            // the farthest child is pushed first so that the nearest one is popped first
+            children.sort_by(|a, b| b.1.total_cmp(&a.1));
+            for (i_child, t_child) in children {
+                let j_node = self.node2child[i_node][i_child];
+                if j_node != usize::MAX {
+                    stack.push((j_node, t_child));
+                    continue;
+                }
+                let [i0, i1] = self.node2range[i_node][i_child];
+                for &i_tri in &self.idx2elem[i0..i1] {
+                    let Some(t) = crate::trimesh3::to_tri3(tri2vtx, vtx2xyz, i_tri)
+                        .intersection_against_ray(ray_org, ray_dir)
+                    else {
+                        continue;
+                    };
+                    if t < t_max {
+                        t_max = t;
+                        res = Some((t, i_tri));
+                    }
+                }
+            }
+        }
+        res
+    }
+}
+
+#[test]
+fn test_flat_and_wide() {
+    let (tri2vtx, vtx2xyz) = crate::trimesh3_primitive::sphere_yup::<usize, f32>(1.0, 32, 32);
+    let num_tri = tri2vtx.len() / 3;
+    let bvhnodes = crate::bvhnodes_morton::from_triangle_mesh(&tri2vtx, &vtx2xyz, 3);
+    let aabbs = crate::bvhnode2aabb3::from_uniform_mesh_with_bvh(
+        0,
+        &bvhnodes,
+        Some((&tri2vtx, 3)),
+        &vtx2xyz,
+        None,
+    );
+    let trimesh3 = crate::search_bvh3::TriMeshWithBvh {
+        tri2vtx: &tri2vtx,
+        vtx2xyz: &vtx2xyz,
+        bvhnodes: &bvhnodes,
+        bvhnode2aabb: &aabbs,
+    };
+    use rand::Rng;
+    use rand::SeedableRng;
+    let mut reng = rand_chacha::ChaChaRng::seed_from_u64(0u64);
+    for max_leaf_size in [1, 4, 8] {
+        let flat = BvhFlat3::from_bvhnodes(&bvhnodes, &aabbs, 0, max_leaf_size);
+        let wide = BvhWide3::from_bvhnodes(&bvhnodes, &aabbs, 0, max_leaf_size);
+        for idx2elem in [&flat.idx2elem, &wide.idx2elem] {
+            let mut idx2elem = idx2elem.clone();
+            idx2elem.sort();
+            assert_eq!(idx2elem, (0..num_tri).collect::<Vec<_>>());
+        }
+        assert!(wide.num_node() < flat.num_node());
+        for i_node in 0..wide.num_node() {
+            let num_child = wide.node2num_child[i_node];
+            assert!((2..=4).contains(&num_child));
+            for i_child in num_child..4 {
+                assert_eq!(wide.node2child[i_node][i_child], usize::MAX);
+                assert_eq!(wide.node2range[i_node][i_child], [0, 0]);
+            }
+        }
+        for _iter in 0..300 {
+            let ray_org: [f32; 3] = std::array::from_fn(|_| reng.random::<f32>() * 3.0 - 1.5);
+            let ray_dir: [f32; 3] = std::array::from_fn(|_| reng.random::<f32>() * 2.0 - 1.0);
+            let sort = |mut hits: Vec<(f32, usize)>| {
+                hits.sort_by_key(|a| a.1);
+                hits
+            };
+            // ray
+            let mut hits0 = vec![];
+            crate::search_bvh3::intersections_ray(&mut hits0, &ray_org, &ray_dir, &trimesh3, 0);
+            let mut hits1 = vec![];
+            flat.intersections_ray(&mut hits1, &ray_org, &ray_dir, &tri2vtx, &vtx2xyz);
+            let mut hits2 = vec![];
+            wide.intersections_ray(&mut hits2, &ray_org, &ray_dir, &tri2vtx, &vtx2xyz);
+            let hits0 = sort(hits0);
+            assert_eq!(hits0, sort(hits1));
+            assert_eq!(hits0, sort(hits2));
+            // line
+            let mut hits0 = vec![];
+            crate::search_bvh3::intersections_line(&mut hits0, &ray_org, &ray_dir, &trimesh3, 0);
+            let mut hits1 = vec![];
+            flat.intersections_line(&mut hits1, &ray_org, &ray_dir, &tri2vtx, &vtx2xyz);
+            let mut hits2 = vec![];
+            wide.intersections_line(&mut hits2, &ray_org, &ray_dir, &tri2vtx, &vtx2xyz);
+            let hits0 = sort(hits0);
+            assert_eq!(hits0, sort(hits1));
+            assert_eq!(hits0, sort(hits2));
+            // first hit
+            let first0 = hits0
+                .iter()
+                .filter(|a| a.0 >= 0f32)
+                .min_by(|a, b| a.0.total_cmp(&b.0))
+                .map(|a| a.0);
+            let first1 = flat
+                .first_intersection_ray(&ray_org, &ray_dir, &tri2vtx, &vtx2xyz, f32::INFINITY)
+                .map(|a| a.0);
+            let first2 = wide
+                .first_intersection_ray(&ray_org, &ray_dir, &tri2vtx, &vtx2xyz, f32::INFINITY)
+                .map(|a| a.0);
+            assert_eq!(first0, first1);
+            assert_eq!(first0, first2);
+        }
+    }
+}
@@ -107,3 +107,90 @@ where
     );
     bvhnode2aabb
 }
+
+/// build aabb for uniform mesh whose vertices have radius (e.g., capsules or particles)
+/// if 'elem2vtx' is None, bvh stores the vertex index directly
+/// * `vtx2rad` - radius of each vertex
+pub fn update_for_uniform_mesh_with_bvh_and_radius<Index, Real>(
+    bvhnode2aabb: &mut [Real],
+    i_bvhnode: usize,
+    bvhnodes: &[Index],
+    elem2vtx: Option<(&[Index], usize)>,
+    vtx2xyz: &[Real],
+    vtx2rad: &[Real],
+) where
+    Real: num_traits::Float,
+    Index: PrimInt + AsPrimitive<usize>,
+{
+    assert_eq!(bvhnode2aabb.len() / 6, bvhnodes.len() / 3);
+    assert_eq!(vtx2xyz.len() / 3, vtx2rad.len());
+    if bvhnodes[i_bvhnode * 3 + 2] == Index::max_value() {
+        // leaf node
+        let i_elem: usize = bvhnodes[i_bvhnode * 3 + 1].as_();
+        let mut aabb = [
+            Real::infinity(),
+            Real::infinity(),
+            Real::infinity(),
+            Real::neg_infinity(),
+            Real::neg_infinity(),
+            Real::neg_infinity(),
+        ];
+        let mut add_vtx = |i_vtx: usize| {
+            for i_dim in 0..3 {
+                let x = vtx2xyz[i_vtx * 3 + i_dim];
+                aabb[i_dim] = aabb[i_dim].min(x - vtx2rad[i_vtx]);
+                aabb[i_dim + 3] = aabb[i_dim + 3].max(x + vtx2rad[i_vtx]);
+            }
+        };
+        if let Some((elem2vtx, num_noel)) = elem2vtx {
+            for i_vtx in &elem2vtx[i_elem * num_noel..(i_elem + 1) * num_noel] {
+                add_vtx(i_vtx.as_());
+            }
+        } else {
+            add_vtx(i_elem);
+        }
+        bvhnode2aabb[i_bvhnode * 6..i_bvhnode * 6 + 6].copy_from_slice(&aabb);
+    } else {
+        let i_bvhnode_child0: usize = bvhnodes[i_bvhnode * 3 + 1].as_();
+        let i_bvhnode_child1: usize = bvhnodes[i_bvhnode * 3 + 2].as_();
+        for i_bvhnode_child in [i_bvhnode_child0, i_bvhnode_child1] {
+            update_for_uniform_mesh_with_bvh_and_radius(
+                bvhnode2aabb,
+                i_bvhnode_child,
+                bvhnodes,
+                elem2vtx,
+                vtx2xyz,
+                vtx2rad,
+            );
+        }
+        let aabb = del_geo_core::aabb3::from_two_aabbs(
+            arrayref::array_ref!(bvhnode2aabb, i_bvhnode_child0 * 6, 6),
+            arrayref::array_ref!(bvhnode2aabb, i_bvhnode_child1 * 6, 6),
+        );
+        bvhnode2aabb[i_bvhnode * 6..(i_bvhnode + 1) * 6].copy_from_slice(&aabb);
+    }
+}
+
+pub fn from_uniform_mesh_with_bvh_and_radius<Index, Real>(
+    i_bvhnode: usize,
+    bvhnodes: &[Index],
+    elem2vtx: Option<(&[Index], usize)>,
+    vtx2xyz: &[Real],
+    vtx2rad: &[Real],
+) -> Vec<Real>
+where
+    Real: num_traits::Float,
+    Index: PrimInt + AsPrimitive<usize>,
+{
+    let num_bvhnode = bvhnodes.len() / 3;
+    let mut bvhnode2aabb = vec![Real::zero(); num_bvhnode * 6];
+    update_for_uniform_mesh_with_bvh_and_radius(
+        &mut bvhnode2aabb,
+        i_bvhnode,
+        bvhnodes,
+        elem2vtx,
+        vtx2xyz,
+        vtx2rad,
+    );
+    bvhnode2aabb
+}
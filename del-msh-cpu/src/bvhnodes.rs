@@ -34,3 +34,114 @@ where
         }
     }
 }
+
+/// concatenate several BVHs into one array (e.g., BVHs of vertices, edges and triangles).
+/// The node indices are shifted while the element indices at the leaves are kept.
+/// Returns the concatenated `bvhnodes` and the root node index of each BVH
+pub fn concatenate<INDEX>(list_bvhnodes: &[&[INDEX]]) -> (Vec<INDEX>, Vec<usize>)
+where
+    INDEX: num_traits::PrimInt + num_traits::AsPrimitive<usize>,
+    usize: num_traits::AsPrimitive<INDEX>,
+{
+    use num_traits::AsPrimitive;
+    let mut bvhnodes: Vec<INDEX> = vec![];
+    let mut roots = vec![];
+    for bvhnodes0 in list_bvhnodes {
+        assert_eq!(bvhnodes0.len() % 3, 0);
+        let offset: usize = bvhnodes.len() / 3;
+        roots.push(offset);
+        let shift = |i: INDEX| -> INDEX {
+            if i == INDEX::max_value() {
+                i
+            } else {
+                (i.as_() + offset).as_()
+            }
+        };
+        for node in bvhnodes0.chunks(3) {
+            let is_leaf = node[2] == INDEX::max_value();
+            bvhnodes.push(shift(node[0]));
+            bvhnodes.push(if is_leaf { node[1] } else { shift(node[1]) });
+            bvhnodes.push(shift(node[2]));
+        }
+    }
+    (bvhnodes, roots)
+}
+
+/// statistics of a BVH to compare the builders
+#[derive(Debug, Clone, Copy)]
+pub struct Quality<Real> {
+    /// SAH cost normalized by the surface area of the root
+    pub sah_cost: Real,
+    pub num_internal: usize,
+    /// the number of the leaves, which is the number of the elements as each leaf has one element
+    pub num_leaf: usize,
+    pub min_leaf_depth: usize,
+    pub max_leaf_depth: usize,
+    pub average_leaf_depth: Real,
+}
+
+/// evaluate the quality of the BVH with the AABBs of the nodes (e.g., from `bvhnode2aabb3`).
+/// * `num_dim` - 2 or 3. The perimeter is used instead of the surface area in 2D
+/// * `cost_traversal` - the cost to test a node
+/// * `cost_intersection` - the cost to test an element
+pub fn quality<INDEX, Real>(
+    bvhnodes: &[INDEX],
+    aabbs: &[Real],
+    num_dim: usize,
+    i_bvhnode_root: usize,
+    cost_traversal: Real,
+    cost_intersection: Real,
+) -> Quality<Real>
+where
+    INDEX: num_traits::PrimInt + num_traits::AsPrimitive<usize>,
+    Real: num_traits::Float + 'static,
+    usize: num_traits::AsPrimitive<Real>,
+{
+    use num_traits::AsPrimitive;
+    assert!(num_dim == 2 || num_dim == 3);
+    assert_eq!(bvhnodes.len() / 3, aabbs.len() / (num_dim * 2));
+    let area = |i_bvhnode: usize| -> Real {
+        let aabb = &aabbs[i_bvhnode * num_dim * 2..(i_bvhnode + 1) * num_dim * 2];
+        let s: Vec<Real> = (0..num_dim)
+            .map(|i_dim| (aabb[num_dim + i_dim] - aabb[i_dim]).max(Real::zero()))
+            .collect();
+        if num_dim == 2 {
+            s[0] + s[1]
+        } else {
+            s[0] * s[1] + s[1] * s[2] + s[2] * s[0]
+        }
+    };
+    let mut q = Quality {
+        sah_cost: Real::zero(),
+        num_internal: 0,
+        num_leaf: 0,
+        min_leaf_depth: usize::MAX,
+        max_leaf_depth: 0,
+        average_leaf_depth: Real::zero(),
+    };
+    let mut sum_depth = 0usize;
+    let mut stack = vec![(i_bvhnode_root, 0usize)];
+    while let Some((i_bvhnode, depth)) = stack.pop() {
+        if bvhnodes[i_bvhnode * 3 + 2] == INDEX::max_value() {
+            // leaf
+            q.num_leaf += 1;
+            q.min_leaf_depth = q.min_leaf_depth.min(depth);
+            q.max_leaf_depth = q.max_leaf_depth.max(depth);
+            sum_depth += depth;
+            q.sah_cost = q.sah_cost + cost_intersection * area(i_bvhnode);
+            continue;
+        }
+        q.num_internal += 1;
+        q.sah_cost = q.sah_cost + cost_traversal * area(i_bvhnode);
+        stack.push((bvhnodes[i_bvhnode * 3 + 1].as_(), depth + 1));
+        stack.push((bvhnodes[i_bvhnode * 3 + 2].as_(), depth + 1));
+    }
+    let area_root = area(i_bvhnode_root);
+    if area_root > Real::zero() {
+        q.sah_cost = q.sah_cost / area_root;
+    }
+    let num_leaf: Real = q.num_leaf.as_();
+    let sum_depth: Real = sum_depth.as_();
+    q.average_leaf_depth = sum_depth / num_leaf;
+    q
+}
@@ -4,9 +4,43 @@
 //!  when the number of query object is N, `bvhnodes` is sized as (2N-1)*3
 //!  bvhnodes store the `parent node`, `left node` and `right node` index
 //!  if `right node` index is the maximum numbe, the left node stores the index of an object
+//!
+//!  The Morton code is either `u32` (10 bits for each axis in 3D) or `u64` (21 bits for each axis in 3D).
+//!  The 64-bit code is useful for large or highly non-uniform meshes where many objects share the same 32-bit code.
+//!  Duplicated codes are handled by using the index in the sorted array as a tie-breaker.
 
 use num_traits::AsPrimitive;
 
+/// Morton code of a point in the unit square/cube
+pub trait MortonCode: num_traits::PrimInt + Send + Sync + 'static {
+    /// number of bits in the code
+    const NUM_BIT: i64;
+    /// * `x`, `y` - float number between 0 and 1
+    fn from_xy(x: f32, y: f32) -> Self;
+    /// * `x`, `y`, `z` - float number between 0 and 1
+    fn from_xyz(x: f32, y: f32, z: f32) -> Self;
+}
+
+impl MortonCode for u32 {
+    const NUM_BIT: i64 = 32;
+    fn from_xy(x: f32, y: f32) -> Self {
+        morton_code2(x, y)
+    }
+    fn from_xyz(x: f32, y: f32, z: f32) -> Self {
+        morton_code3(x, y, z)
+    }
+}
+
+impl MortonCode for u64 {
+    const NUM_BIT: i64 = 64;
+    fn from_xy(x: f32, y: f32) -> Self {
+        morton_code2_u64(x, y)
+    }
+    fn from_xyz(x: f32, y: f32, z: f32) -> Self {
+        morton_code3_u64(x, y, z)
+    }
+}
+
 fn expand_bits2(x: u32) -> u32 {
     let x = (x | (x << 8)) & 0x00ff00ff;
     let x = (x | (x << 4)) & 0x0f0f0f0f;
@@ -38,15 +72,46 @@ fn test_morton_code2() {
     assert_eq!(morton_code2(0., 1.), 0b01010101010101010101);
 }
 
-pub fn sorted_morten_code2<Index>(
+/// Expands a 32-bit integer into 64 bits by putting a zero before each bit
+fn expand_bits2_u64(x: u32) -> u64 {
+    let x = x as u64;
+    let x = (x | (x << 16)) & 0x0000FFFF0000FFFF;
+    let x = (x | (x << 8)) & 0x00FF00FF00FF00FF;
+    let x = (x | (x << 4)) & 0x0F0F0F0F0F0F0F0F;
+    let x = (x | (x << 2)) & 0x3333333333333333;
+    (x | (x << 1)) & 0x5555555555555555
+}
+
+/// compute 64-bit morton code for 2D point
+/// 32-bits for each coordinate
+fn morton_code2_u64(x: f32, y: f32) -> u64 {
+    let ix = (x as f64 * 4294967296_f64).clamp(0_f64, 4294967295_f64) as u32;
+    let iy = (y as f64 * 4294967296_f64).clamp(0_f64, 4294967295_f64) as u32;
+    expand_bits2_u64(ix) * 2 + expand_bits2_u64(iy)
+}
+
+#[test]
+fn test_morton_code2_u64() {
+    assert_eq!(expand_bits2_u64(0b10001001), 0b0100000001000001);
+    assert_eq!(morton_code2_u64(0., 0.), 0u64);
+    assert_eq!(morton_code2_u64(1., 1.), u64::MAX);
+    // the top bits are the same as the 32-bit code
+    assert_eq!(
+        (morton_code2_u64(0.3, 0.7) >> 44) as u32,
+        morton_code2(0.3, 0.7)
+    );
+}
+
+pub fn sorted_morten_code2<Index, Morton>(
     idx2vtx: &mut [Index],
-    idx2morton: &mut [u32],
-    vtx2morton: &mut [u32],
+    idx2morton: &mut [Morton],
+    vtx2morton: &mut [Morton],
     vtx2xy: &[f32],
     transform_xy2uni: &[f32; 9],
 ) where
     Index: num_traits::PrimInt + 'static + AsPrimitive<usize>,
     usize: AsPrimitive<Index>,
+    Morton: MortonCode,
 {
     assert_eq!(idx2vtx.len(), idx2morton.len());
     assert_eq!(idx2vtx.len(), vtx2morton.len());
@@ -60,7 +125,7 @@ pub fn sorted_morten_code2<Index>(
                 &[xy[0], xy[1]],
             )
             .unwrap();
-            *m = morton_code2(xy[0], xy[1]);
+            *m = Morton::from_xy(xy[0], xy[1]);
         });
     idx2vtx
         .iter_mut()
@@ -115,18 +180,57 @@ fn test_morton_code3() {
     assert_eq!(morton_code3(1., 1., 1.), 0xFFFFFFFF >> 2);
 }
 
+/// Expands a 21-bit integer into 63 bits
+/// by putting two zeros before each bit
+fn expand_bits3_u64(x: u32) -> u64 {
+    let x = (x as u64) & 0x1FFFFF;
+    let x = (x | (x << 32)) & 0x001F00000000FFFF;
+    let x = (x | (x << 16)) & 0x001F0000FF0000FF;
+    let x = (x | (x << 8)) & 0x100F00F00F00F00F;
+    let x = (x | (x << 4)) & 0x10C30C30C30C30C3;
+    (x | (x << 2)) & 0x1249249249249249
+}
+
+#[test]
+fn test_expand_bits3_u64() {
+    assert_eq!(expand_bits3_u64(0b11111111), 0b001001001001001001001001);
+    assert_eq!(expand_bits3_u64(0b10001001), 0b001000000000001000000001);
+    assert_eq!(expand_bits3_u64(0x1FFFFF), 0x1249249249249249);
+}
+
+/// compute 64-bit morton code for 3D point
+/// 21-bits for each coordinate
+fn morton_code3_u64(x: f32, y: f32, z: f32) -> u64 {
+    let ix = (x as f64 * 2097152_f64).clamp(0_f64, 2097151_f64) as u32;
+    let iy = (y as f64 * 2097152_f64).clamp(0_f64, 2097151_f64) as u32;
+    let iz = (z as f64 * 2097152_f64).clamp(0_f64, 2097151_f64) as u32;
+    expand_bits3_u64(ix) * 4 + expand_bits3_u64(iy) * 2 + expand_bits3_u64(iz)
+}
+
+#[test]
+fn test_morton_code3_u64() {
+    assert_eq!(morton_code3_u64(0., 0., 0.), 0u64);
+    assert_eq!(morton_code3_u64(1., 1., 1.), u64::MAX >> 1);
+    // the top bits are the same as the 32-bit code
+    assert_eq!(
+        (morton_code3_u64(0.3, 0.7, 0.1) >> 33) as u32,
+        morton_code3(0.3, 0.7, 0.1)
+    );
+}
+
 // above: 3D related
 // --------------------
 
-pub fn sorted_morten_code3<Index>(
+pub fn sorted_morten_code3<Index, Morton>(
     idx2vtx: &mut [Index],
-    idx2morton: &mut [u32],
-    vtx2morton: &mut [u32],
+    idx2morton: &mut [Morton],
+    vtx2morton: &mut [Morton],
     vtx2xyz: &[f32],
     transform_xy2uni: &[f32; 16],
 ) where
     Index: num_traits::PrimInt + 'static + AsPrimitive<usize>,
     usize: AsPrimitive<Index>,
+    Morton: MortonCode,
 {
     assert_eq!(idx2vtx.len(), idx2morton.len());
     assert_eq!(idx2vtx.len(), vtx2morton.len());
@@ -140,7 +244,7 @@ pub fn sorted_morten_code3<Index>(
                 &[xyz[0], xyz[1], xyz[2]],
             )
             .unwrap();
-            *m = morton_code3(xyz[0], xyz[1], xyz[2])
+            *m = Morton::from_xyz(xyz[0], xyz[1], xyz[2])
         });
     idx2vtx
         .iter_mut()
@@ -174,11 +278,22 @@ fn test_sorted_morten_code() {
 
 // ---------------
 
-fn delta(idx0: usize, idx1: usize, idx2morton: &[u32]) -> i64 {
-    (idx2morton[idx0] ^ idx2morton[idx1]).leading_zeros().into()
+/// length of the common prefix of the two codes.
+/// If the codes are the same, the indexes are appended to the codes to make them distinct
+fn delta<Morton: MortonCode>(idx0: usize, idx1: usize, idx2morton: &[Morton]) -> i64 {
+    let mc0 = idx2morton[idx0];
+    let mc1 = idx2morton[idx1];
+    if mc0 == mc1 {
+        Morton::NUM_BIT + i64::from((idx0 ^ idx1).leading_zeros())
+    } else {
+        i64::from((mc0 ^ mc1).leading_zeros())
+    }
 }
 
-fn morton_code_determine_range(idx2morton: &[u32], idx1: usize) -> (usize, usize) {
+fn morton_code_determine_range<Morton: MortonCode>(
+    idx2morton: &[Morton],
+    idx1: usize,
+) -> (usize, usize) {
     let num_mc = idx2morton.len();
     assert!(!idx2morton.is_empty());
     if idx1 == 0 {
@@ -187,21 +302,6 @@ fn morton_code_determine_range(idx2morton: &[u32], idx1: usize) -> (usize, usize
     if idx1 == num_mc - 1 {
         return (num_mc - 1, num_mc - 1);
     }
-    // ----------------------
-    let mc0: u32 = idx2morton[idx1 - 1];
-    let mc1: u32 = idx2morton[idx1];
-    let mc2: u32 = idx2morton[idx1 + 1];
-    if mc0 == mc1 && mc1 == mc2 {
-        // for hash value collision
-        let mut jdx = idx1 + 1;
-        while jdx < num_mc - 1 {
-            jdx += 1;
-            if idx2morton[jdx] != mc1 {
-                return (idx1, jdx - 1);
-            }
-        }
-        return (idx1, jdx);
-    }
     // get direction
     // (d==+1) -> imc is left-end, move forward
     // (d==-1) -> imc is right-end, move backward
@@ -249,7 +349,7 @@ fn morton_code_determine_range(idx2morton: &[u32], idx1: usize) -> (usize, usize
 /// check sorted morton codes
 /// panic if there is a bug in the sorted morton codes
 #[allow(dead_code)]
-fn check_morton_code_range_split(idx2morton: &[u32]) {
+fn check_morton_code_range_split<Morton: MortonCode>(idx2morton: &[Morton]) {
     assert!(!idx2morton.is_empty());
     for ini in 0..idx2morton.len() - 1 {
         let range = morton_code_determine_range(idx2morton, ini);
@@ -268,18 +368,17 @@ fn check_morton_code_range_split(idx2morton: &[u32]) {
     }
 }
 
-fn morton_code_find_split(idx2morton: &[u32], i_mc_start: usize, i_mc_end: usize) -> usize {
+fn morton_code_find_split<Morton: MortonCode>(
+    idx2morton: &[Morton],
+    i_mc_start: usize,
+    i_mc_end: usize,
+) -> usize {
     if i_mc_start == i_mc_end {
         return usize::MAX;
     }
 
-    let mc_start: u32 = idx2morton[i_mc_start];
-    let nbitcommon0: u32 = (mc_start ^ idx2morton[i_mc_end]).leading_zeros();
-
-    // handle duplicated morton code
-    if nbitcommon0 == 32 {
-        return i_mc_start;
-    } // sizeof(std::uint32_t)*8
+    // duplicated morton codes are handled inside `delta`
+    let nbitcommon0 = delta(i_mc_start, i_mc_end, idx2morton);
 
     // Use binary search to find where the next bit differs.
     // Specifically, we are looking for the highest object that
@@ -293,7 +392,7 @@ fn morton_code_find_split(idx2morton: &[u32], i_mc_start: usize, i_mc_end: usize
         if i_mc_new >= i_mc_end {
             continue;
         }
-        let nbitcommon1: u32 = (mc_start ^ idx2morton[i_mc_new]).leading_zeros();
+        let nbitcommon1 = delta(i_mc_start, i_mc_new, idx2morton);
         if nbitcommon1 > nbitcommon0 {
             i_mc_split = i_mc_new; // accept proposal
         }
@@ -301,10 +400,14 @@ fn morton_code_find_split(idx2morton: &[u32], i_mc_start: usize, i_mc_end: usize
     i_mc_split
 }
 
-pub fn update_bvhnodes<Index>(bvhnodes: &mut [Index], idx2vtx: &[Index], idx2morton: &[u32])
-where
+pub fn update_bvhnodes<Index, Morton>(
+    bvhnodes: &mut [Index],
+    idx2vtx: &[Index],
+    idx2morton: &[Morton],
+) where
     Index: num_traits::PrimInt + 'static + Copy,
     usize: AsPrimitive<Index>,
+    Morton: MortonCode,
 {
     assert_eq!(idx2vtx.len(), idx2morton.len());
     assert!(!idx2morton.is_empty());
@@ -399,15 +502,16 @@ fn test_2d() {
     crate::bvhnodes::check_bvh_topology(&bvhnodes, num_vtx);
 }
 
-pub fn update_sorted_morton_code<Index>(
+pub fn update_sorted_morton_code<Index, Morton>(
     idx2tri: &mut [Index],
-    idx2morton: &mut [u32],
-    tri2morton: &mut [u32],
+    idx2morton: &mut [Morton],
+    tri2morton: &mut [Morton],
     vtx2xyz: &[f32],
     num_dim: usize,
 ) where
     Index: num_traits::PrimInt + num_traits::AsPrimitive<usize>,
     usize: AsPrimitive<Index>,
+    Morton: MortonCode,
 {
     match num_dim {
         2 => {
@@ -455,11 +559,21 @@ pub fn from_vtx2xyz<Index>(vtx2xyz: &[f32], num_dim: usize) -> Vec<Index>
 where
     Index: num_traits::PrimInt + AsPrimitive<usize>,
     usize: AsPrimitive<Index>,
+{
+    from_vtx2xyz_with_morton_code::<Index, u32>(vtx2xyz, num_dim)
+}
+
+/// same as `from_vtx2xyz` but the type of the Morton code (`u32` or `u64`) can be specified
+pub fn from_vtx2xyz_with_morton_code<Index, Morton>(vtx2xyz: &[f32], num_dim: usize) -> Vec<Index>
+where
+    Index: num_traits::PrimInt + AsPrimitive<usize>,
+    usize: AsPrimitive<Index>,
+    Morton: MortonCode,
 {
     let num_tri = vtx2xyz.len() / num_dim;
     let mut idx2tri = vec![Index::one(); num_tri];
-    let mut idx2morton = vec![0u32; num_tri];
-    let mut tri2morton = vec![0u32; num_tri];
+    let mut idx2morton = vec![Morton::zero(); num_tri];
+    let mut tri2morton = vec![Morton::zero(); num_tri];
     update_sorted_morton_code(
         &mut idx2tri,
         &mut idx2morton,
@@ -476,10 +590,24 @@ pub fn from_triangle_mesh<Index>(tri2vtx: &[Index], vtx2xy: &[f32], num_dim: usi
 where
     Index: num_traits::PrimInt + AsPrimitive<usize>,
     usize: AsPrimitive<Index>,
+{
+    from_triangle_mesh_with_morton_code::<Index, u32>(tri2vtx, vtx2xy, num_dim)
+}
+
+/// same as `from_triangle_mesh` but the type of the Morton code (`u32` or `u64`) can be specified
+pub fn from_triangle_mesh_with_morton_code<Index, Morton>(
+    tri2vtx: &[Index],
+    vtx2xy: &[f32],
+    num_dim: usize,
+) -> Vec<Index>
+where
+    Index: num_traits::PrimInt + AsPrimitive<usize>,
+    usize: AsPrimitive<Index>,
+    Morton: MortonCode,
 {
     let tri2cntr =
         crate::elem2center::from_uniform_mesh_as_points::<Index, f32>(tri2vtx, 3, vtx2xy, num_dim);
-    from_vtx2xyz(&tri2cntr, num_dim)
+    from_vtx2xyz_with_morton_code::<Index, Morton>(&tri2cntr, num_dim)
 }
 
 pub fn update_for_triangle_mesh<Index>(
@@ -507,3 +635,82 @@ pub fn update_for_triangle_mesh<Index>(
     );
     update_bvhnodes(bvhnodes, &idx2tri, &idx2morton);
 }
+
+#[test]
+fn test_duplicated_morton_code() {
+    use rand::Rng;
+    use rand::SeedableRng;
+    let mut reng = rand_chacha::ChaChaRng::seed_from_u64(0u64);
+    // highly non-uniform points: a dense cluster where the 32-bit codes collide and two outliers
+    let num_vtx = 20000;
+    let vtx2xyz: Vec<f32> = (0..num_vtx * 3)
+        .map(|i| match i / 3 {
+            0 => 0.,
+            1 => 1.,
+            _ => 0.5 + (reng.random_range(0..64) as f32) * 1.0e-5,
+        })
+        .collect();
+    {
+        let mut idx2vtx = vec![0usize; num_vtx];
+        let mut idx2morton = vec![0u32; num_vtx];
+        let mut vtx2morton = vec![0u32; num_vtx];
+        sorted_morten_code3(
+            &mut idx2vtx,
+            &mut idx2morton,
+            &mut vtx2morton,
+            &vtx2xyz,
+            &del_geo_core::mat4_col_major::from_identity(),
+        );
+        check_morton_code_range_split(&idx2morton);
+        let mut bvhnodes = vec![0usize; (num_vtx * 2 - 1) * 3];
+        update_bvhnodes(&mut bvhnodes, &idx2vtx, &idx2morton);
+        crate::bvhnodes::check_bvh_topology(&bvhnodes, num_vtx);
+    }
+    // the 64-bit codes distinguish the points that the 32-bit codes cannot
+    let num_unique =
+        |idx2morton: &[u64]| 1 + idx2morton.windows(2).filter(|w| w[0] != w[1]).count();
+    let mut idx2vtx = vec![0usize; num_vtx];
+    let mut idx2morton32 = vec![0u32; num_vtx];
+    let mut vtx2morton32 = vec![0u32; num_vtx];
+    update_sorted_morton_code(
+        &mut idx2vtx,
+        &mut idx2morton32,
+        &mut vtx2morton32,
+        &vtx2xyz,
+        3,
+    );
+    let mut idx2morton64 = vec![0u64; num_vtx];
+    let mut vtx2morton64 = vec![0u64; num_vtx];
+    update_sorted_morton_code(
+        &mut idx2vtx,
+        &mut idx2morton64,
+        &mut vtx2morton64,
+        &vtx2xyz,
+        3,
+    );
+    for idx in 0..num_vtx - 1 {
+        assert!(idx2morton64[idx] <= idx2morton64[idx + 1]);
+    }
+    let idx2morton32: Vec<u64> = idx2morton32.iter().map(|&m| m as u64).collect();
+    assert!(num_unique(&idx2morton32) < num_unique(&idx2morton64));
+    check_morton_code_range_split(&idx2morton64);
+    let mut bvhnodes = vec![0usize; (num_vtx * 2 - 1) * 3];
+    update_bvhnodes(&mut bvhnodes, &idx2vtx, &idx2morton64);
+    crate::bvhnodes::check_bvh_topology(&bvhnodes, num_vtx);
+}
+
+#[test]
+fn test_from_triangle_mesh_u64() {
+    let (tri2vtx, vtx2xyz) = crate::trimesh3_primitive::torus_zup::<u32, f32>(1.0, 0.3, 64, 64);
+    let num_tri = tri2vtx.len() / 3;
+    let bvhnodes = from_triangle_mesh_with_morton_code::<u32, u64>(&tri2vtx, &vtx2xyz, 3);
+    crate::bvhnodes::check_bvh_topology(&bvhnodes, num_tri);
+    let (tri2vtx, vtx2xy) = crate::trimesh2_dynamic::meshing_from_polyloop2::<u32, f32>(
+        &[0., 0., 1., 0., 1., 1., 0., 1.],
+        0.03,
+        0.03,
+    );
+    let num_tri = tri2vtx.len() / 3;
+    let bvhnodes = from_triangle_mesh_with_morton_code::<u32, u64>(&tri2vtx, &vtx2xy, 2);
+    crate::bvhnodes::check_bvh_topology(&bvhnodes, num_tri);
+}
@@ -0,0 +1,264 @@
+//! top-down BVH construction with binned surface area heuristic (SAH).
+//! The layout of `bvhnodes` is the same as `bvhnodes_morton`:
+//! the internal nodes come first and the leaves are stored from the index `num_elem - 1`
+
+use num_traits::AsPrimitive;
+
+/// half of the surface area (3D) or the perimeter (2D) of the AABB
+fn half_area<Real>(aabb: &[Real], num_dim: usize) -> Real
+where
+    Real: num_traits::Float,
+{
+    let size: Vec<Real> = (0..num_dim)
+        .map(|i_dim| (aabb[num_dim + i_dim] - aabb[i_dim]).max(Real::zero()))
+        .collect();
+    match num_dim {
+        2 => size[0] + size[1],
+        3 => size[0] * size[1] + size[1] * size[2] + size[2] * size[0],
+        _ => panic!(),
+    }
+}
+
+fn empty_aabb<Real>(num_dim: usize) -> Vec<Real>
+where
+    Real: num_traits::Float,
+{
+    let mut aabb = vec![Real::infinity(); num_dim * 2];
+    aabb[num_dim..].fill(Real::neg_infinity());
+    aabb
+}
+
+fn add_aabb<Real>(aabb: &mut [Real], other: &[Real], num_dim: usize)
+where
+    Real: num_traits::Float,
+{
+    for i_dim in 0..num_dim {
+        aabb[i_dim] = aabb[i_dim].min(other[i_dim]);
+        aabb[num_dim + i_dim] = aabb[num_dim + i_dim].max(other[num_dim + i_dim]);
+    }
+}
+
+struct Builder<'a, Index, Real> {
+    bvhnodes: Vec<Index>,
+    elem2aabb: &'a [Real],
+    elem2cntr: Vec<Real>,
+    num_dim: usize,
+    num_bin: usize,
+    num_internal: usize,
+    num_leaf: usize,
+}
+
+impl<Index, Real> Builder<'_, Index, Real>
+where
+    Real: num_traits::Float + AsPrimitive<usize> + 'static,
+    Index: num_traits::PrimInt + AsPrimitive<usize>,
+    usize: AsPrimitive<Index> + AsPrimitive<Real>,
+{
+    /// the number of the elements in the first child. The elements are reordered in place
+    fn partition(&self, elems: &mut [usize]) -> usize {
+        let num_dim = self.num_dim;
+        let num_bin = self.num_bin;
+        // bounding box of the centers
+        let mut cmin = vec![Real::infinity(); num_dim];
+        let mut cmax = vec![Real::neg_infinity(); num_dim];
+        for &i_elem in elems.iter() {
+            for i_dim in 0..num_dim {
+                let c = self.elem2cntr[i_elem * num_dim + i_dim];
+                cmin[i_dim] = cmin[i_dim].min(c);
+                cmax[i_dim] = cmax[i_dim].max(c);
+            }
+        }
+        let bin_of = |i_elem: usize, i_dim: usize| -> usize {
+            let c = self.elem2cntr[i_elem * num_dim + i_dim];
+            let nb: Real = num_bin.as_();
+            let r: usize = ((c - cmin[i_dim]) / (cmax[i_dim] - cmin[i_dim]) * nb).as_();
+            r.min(num_bin - 1)
+        };
+        // (cost, dimension, the number of the bins in the first child)
+        let mut best: Option<(Real, usize, usize)> = None;
+        for i_dim in 0..num_dim {
+            if cmax[i_dim] <= cmin[i_dim] {
+                continue;
+            }
+            let mut bin2aabb = vec![empty_aabb::<Real>(num_dim); num_bin];
+            let mut bin2cnt = vec![0usize; num_bin];
+            for &i_elem in elems.iter() {
+                let i_bin = bin_of(i_elem, i_dim);
+                bin2cnt[i_bin] += 1;
+                let aabb = &self.elem2aabb[i_elem * num_dim * 2..(i_elem + 1) * num_dim * 2];
+                add_aabb(&mut bin2aabb[i_bin], aabb, num_dim);
+            }
+            // sweep from the right to get the cost of the second children
+            let mut split2cost_right = vec![Real::zero(); num_bin];
+            {
+                let mut aabb = empty_aabb::<Real>(num_dim);
+                let mut cnt = 0usize;
+                for i_bin in (1..num_bin).rev() {
+                    add_aabb(&mut aabb, &bin2aabb[i_bin], num_dim);
+                    cnt += bin2cnt[i_bin];
+                    let cnt: Real = cnt.as_();
+                    split2cost_right[i_bin] = half_area(&aabb, num_dim) * cnt;
+                }
+            }
+            let mut aabb = empty_aabb::<Real>(num_dim);
+            let mut cnt = 0usize;
+            for i_bin in 0..num_bin - 1 {
+                add_aabb(&mut aabb, &bin2aabb[i_bin], num_dim);
+                cnt += bin2cnt[i_bin];
+                if cnt == 0 || cnt == elems.len() {
+                    continue;
+                }
+                let cnt_left: Real = cnt.as_();
+                let cost = half_area(&aabb, num_dim) * cnt_left + split2cost_right[i_bin + 1];
+                match best {
+                    Some((cost_best, _, _)) if cost_best <= cost => {}
+                    _ => best = Some((cost, i_dim, i_bin + 1)),
+                }
+            }
+        }
+        if let Some((_cost, i_dim, num_bin_left)) = best {
+            // in-place partition
+            let mut num_left = 0;
+            for i in 0..elems.len() {
+                if bin_of(elems[i], i_dim) < num_bin_left {
+                    elems.swap(i, num_left);
+                    num_left += 1;
+                }
+            }
+            return num_left;
+        }
+        // all the centers are at the same location
+        elems.len() / 2
+    }
+
+    fn build(&mut self, elems: &mut [usize], i_node_parent: usize) -> usize {
+        if elems.len() == 1 {
+            let i_node = self.num_internal_total() + self.num_leaf;
+            self.num_leaf += 1;
+            self.bvhnodes[i_node * 3] = i_node_parent.as_();
+            self.bvhnodes[i_node * 3 + 1] = elems[0].as_();
+            self.bvhnodes[i_node * 3 + 2] = Index::max_value();
+            return i_node;
+        }
+        let i_node = self.num_internal;
+        self.num_internal += 1;
+        let num_left = self.partition(elems);
+        let (elems_left, elems_right) = elems.split_at_mut(num_left);
+        let i_node_left = self.build(elems_left, i_node);
+        let i_node_right = self.build(elems_right, i_node);
+        self.bvhnodes[i_node * 3] = i_node_parent.as_();
+        self.bvhnodes[i_node * 3 + 1] = i_node_left.as_();
+        self.bvhnodes[i_node * 3 + 2] = i_node_right.as_();
+        i_node
+    }
+
+    fn num_internal_total(&self) -> usize {
+        self.elem2aabb.len() / (self.num_dim * 2) - 1
+    }
+}
+
+/// build BVH from the AABBs of the elements
+/// * `elem2aabb` - `[min_x, min_y, (min_z), max_x, max_y, (max_z)]` for each element
+/// * `num_dim` - 2 or 3
+/// * `num_bin` - the number of the bins for each axis to evaluate the split candidates (e.g., 16)
+pub fn from_elem2aabb<Index, Real>(elem2aabb: &[Real], num_dim: usize, num_bin: usize) -> Vec<Index>
+where
+    Real: num_traits::Float + AsPrimitive<usize> + 'static,
+    Index: num_traits::PrimInt + AsPrimitive<usize>,
+    usize: AsPrimitive<Index> + AsPrimitive<Real>,
+{
+    assert!(num_dim == 2 || num_dim == 3);
+    assert!(num_bin >= 2);
+    assert_eq!(elem2aabb.len() % (num_dim * 2), 0);
+    let num_elem = elem2aabb.len() / (num_dim * 2);
+    assert!(num_elem > 0);
+    let half = Real::one() / (Real::one() + Real::one());
+    let elem2cntr: Vec<Real> = (0..num_elem * num_dim)
+        .map(|i| {
+            let (i_elem, i_dim) = (i / num_dim, i % num_dim);
+            let aabb = &elem2aabb[i_elem * num_dim * 2..(i_elem + 1) * num_dim * 2];
+            (aabb[i_dim] + aabb[num_dim + i_dim]) * half
+        })
+        .collect();
+    let mut builder = Builder {
+        bvhnodes: vec![Index::zero(); (num_elem * 2 - 1) * 3],
+        elem2aabb,
+        elem2cntr,
+        num_dim,
+        num_bin,
+        num_internal: 0,
+        num_leaf: 0,
+    };
+    let mut elems: Vec<usize> = (0..num_elem).collect();
+    builder.build(&mut elems, usize::MAX);
+    builder.bvhnodes[0] = Index::max_value();
+    builder.bvhnodes
+}
+
+/// build BVH for the uniform mesh (e.g., triangle mesh with `num_node == 3`)
+pub fn from_uniform_mesh<Index, Real>(
+    elem2vtx: &[Index],
+    num_node: usize,
+    vtx2xyz: &[Real],
+    num_dim: usize,
+    num_bin: usize,
+) -> Vec<Index>
+where
+    Real: num_traits::Float + AsPrimitive<usize> + 'static,
+    Index: num_traits::PrimInt + AsPrimitive<usize>,
+    usize: AsPrimitive<Index> + AsPrimitive<Real>,
+{
+    let num_elem = elem2vtx.len() / num_node;
+    let mut elem2aabb = Vec::with_capacity(num_elem * num_dim * 2);
+    for node2vtx in elem2vtx.chunks(num_node) {
+        let mut aabb = empty_aabb::<Real>(num_dim);
+        for &i_vtx in node2vtx {
+            let i_vtx: usize = i_vtx.as_();
+            let p = &vtx2xyz[i_vtx * num_dim..(i_vtx + 1) * num_dim];
+            for i_dim in 0..num_dim {
+                aabb[i_dim] = aabb[i_dim].min(p[i_dim]);
+                aabb[num_dim + i_dim] = aabb[num_dim + i_dim].max(p[i_dim]);
+            }
+        }
+        elem2aabb.extend(aabb);
+    }
+    from_elem2aabb(&elem2aabb, num_dim, num_bin)
+}
+
+#[test]
+fn test_sah() {
+    let (tri2vtx, vtx2xyz) = crate::trimesh3_primitive::sphere_yup::<usize, f32>(1.0, 32, 32);
+    let num_tri = tri2vtx.len() / 3;
+    let bvhnodes_sah = from_uniform_mesh::<usize, f32>(&tri2vtx, 3, &vtx2xyz, 3, 16);
+    crate::bvhnodes::check_bvh_topology(&bvhnodes_sah, num_tri);
+    let aabbs_sah = crate::bvhnode2aabb3::from_uniform_mesh_with_bvh::<usize, f32>(
+        0,
+        &bvhnodes_sah,
+        Some((&tri2vtx, 3)),
+        &vtx2xyz,
+        None,
+    );
+    let q_sah = crate::bvhnodes::quality(&bvhnodes_sah, &aabbs_sah, 3, 0, 1.0, 1.0);
+    let bvhnodes_morton =
+        crate::bvhnodes_morton::from_triangle_mesh::<usize>(&tri2vtx, &vtx2xyz, 3);
+    let aabbs_morton = crate::bvhnode2aabb3::from_uniform_mesh_with_bvh::<usize, f32>(
+        0,
+        &bvhnodes_morton,
+        Some((&tri2vtx, 3)),
+        &vtx2xyz,
+        None,
+    );
+    let q_morton = crate::bvhnodes::quality(&bvhnodes_morton, &aabbs_morton, 3, 0, 1.0, 1.0);
+    assert_eq!(q_sah.num_leaf, num_tri);
+    assert!(q_sah.sah_cost < q_morton.sah_cost);
+    // 2D
+    let (tri2vtx, vtx2xy) = crate::trimesh2::from_circle(1.0, 64);
+    let bvhnodes = from_uniform_mesh::<u32, f32>(
+        &tri2vtx.iter().map(|&i| i as u32).collect::<Vec<_>>(),
+        3,
+        &vtx2xy,
+        2,
+        8,
+    );
+    crate::bvhnodes::check_bvh_topology(&bvhnodes, tri2vtx.len() / 3);
+}
@@ -0,0 +1,271 @@
+//! 3D convex hull by the quickhull algorithm
+
+use num_traits::AsPrimitive;
+
+/// face of the hull under construction
+struct Face {
+    node2vtx: [usize; 3],
+    /// adjacent face opposite to each node
+    node2face: [usize; 3],
+    /// points strictly outside of this face
+    outside: Vec<usize>,
+    is_alive: bool,
+}
+
+/// four points in general position to start the hull.
+/// `None` if all the points are on a plane
+pub(crate) fn initial_tetrahedron<Real>(vtx2xyz: &[[Real; 3]]) -> Option<[usize; 4]>
+where
+    Real: num_traits::Float + 'static,
+    f64: AsPrimitive<Real>,
+{
+    use crate::tetmesh_delaunay::orient3d;
+    use del_geo_core::vec3;
+    let num_vtx = vtx2xyz.len();
+    let i0 = (0..num_vtx).min_by(|&i, &j| {
+        vtx2xyz[i]
+            .partial_cmp(&vtx2xyz[j])
+            .unwrap_or(std::cmp::Ordering::Equal)
+    })?;
+    let p0 = &vtx2xyz[i0];
+    let i1 = (0..num_vtx)
+        .map(|i| (i, vec3::norm(&vec3::sub(&vtx2xyz[i], p0))))
+        .filter(|&(_, d)| d > Real::zero())
+        .max_by(|a, b| a.1.partial_cmp(&b.1).unwrap())?
+        .0;
+    let p1 = &vtx2xyz[i1];
+    let d01 = vec3::sub(p1, p0);
+    let i2 = (0..num_vtx)
+        .map(|i| {
+            let c = vec3::cross(&d01, &vec3::sub(&vtx2xyz[i], p0));
+            (i, vec3::dot(&c, &c))
+        })
+        .filter(|&(_, d)| d > Real::zero())
+        .max_by(|a, b| a.1.partial_cmp(&b.1).unwrap())?
+        .0;
+    let p2 = &vtx2xyz[i2];
+    let n = vec3::cross(&d01, &vec3::sub(p2, p0));
+    // the exact predicate decides whether the points are coplanar
+    let i3 = (0..num_vtx)
+        .filter(|&i| orient3d(p0, p1, p2, &vtx2xyz[i]) != 0)
+        .max_by(|&i, &j| {
+            let di = vec3::dot(&n, &vec3::sub(&vtx2xyz[i], p0)).abs();
+            let dj = vec3::dot(&n, &vec3::sub(&vtx2xyz[j], p0)).abs();
+            di.partial_cmp(&dj).unwrap()
+        })?;
+    if orient3d(p0, p1, p2, &vtx2xyz[i3]) > 0 {
+        Some([i0, i1, i2, i3])
+    } else {
+        Some([i0, i2, i1, i3])
+    }
+}
+
+/// 3D convex hull of the points.
+/// The visibility of the faces is tested with the exact orientation predicate,
+/// so that coplanar and duplicated points are handled robustly.
+/// The points on the faces or edges of the hull are not used as the vertices of the hull.
+/// # Returns
+/// `tri2vtx` of the closed triangle mesh whose normals point outward, referring to the indices of the input points.
+/// Empty if all the points are on a plane.
+pub fn convex_hull3<Real>(vtx2xyz: &[Real]) -> Vec<usize>
+where
+    Real: num_traits::Float + 'static,
+    f64: AsPrimitive<Real>,
+{
+    use crate::tetmesh_delaunay::orient3d;
+    use del_geo_core::vec3;
+    let vtx2xyz: Vec<[Real; 3]> = vtx2xyz.chunks(3).map(|p| [p[0], p[1], p[2]]).collect();
+    let Some(tet) = initial_tetrahedron(&vtx2xyz) else {
+        return vec![];
+    };
+    let x = &vtx2xyz;
+    let is_outside =
+        |f: &[usize; 3], i_vtx: usize| orient3d(&x[f[0]], &x[f[1]], &x[f[2]], &x[i_vtx]) > 0;
+    let is_on_or_outside =
+        |f: &[usize; 3], i_vtx: usize| orient3d(&x[f[0]], &x[f[1]], &x[f[2]], &x[i_vtx]) >= 0;
+    let distance = |f: &[usize; 3], i_vtx: usize| {
+        let n = vec3::cross(
+            &vec3::sub(&x[f[1]], &x[f[0]]),
+            &vec3::sub(&x[f[2]], &x[f[0]]),
+        );
+        vec3::dot(&n, &vec3::sub(&x[i_vtx], &x[f[0]]))
+    };
+    // assign the points to one of the faces they are outside
+    let assign = |faces: &mut [Face], i_faces: &[usize], vtxs: &[usize]| {
+        for &i_vtx in vtxs {
+            if let Some(&i_face) = i_faces
+                .iter()
+                .find(|&&i_face| is_outside(&faces[i_face].node2vtx, i_vtx))
+            {
+                faces[i_face].outside.push(i_vtx);
+            }
+        }
+    };
+    let mut faces: Vec<Face> = crate::tetmesh::TET2FACE_NODES
+        .iter()
+        .map(|nodes| {
+            Face {
+                node2vtx: nodes.map(|j_node| tet[j_node]),
+                // the face sharing the edge opposite to a node is the face opposite to that node in the tetrahedron
+                node2face: *nodes,
+                outside: vec![],
+                is_alive: true,
+            }
+        })
+        .collect();
+    {
+        let vtxs: Vec<usize> = (0..x.len()).filter(|i| !tet.contains(i)).collect();
+        assign(&mut faces, &[0, 1, 2, 3], &vtxs);
+    }
+    let mut stack: Vec<usize> = vec![0, 1, 2, 3];
+    while let Some(i_face0) = stack.pop() {
+        if !faces[i_face0].is_alive || faces[i_face0].outside.is_empty() {
+            continue;
+        }
+        let f0 = faces[i_face0].node2vtx;
+        let i_vtx_apex = *faces[i_face0]
+            .outside
+            .iter()
+            .max_by(|&&i, &&j| distance(&f0, i).partial_cmp(&distance(&f0, j)).unwrap())
+            .unwrap();
+        // faces visible from the apex. The faces coplanar to the apex are also removed
+        // so that the points on the faces or edges of the hull do not remain as the vertices
+        let mut visible = vec![i_face0];
+        faces[i_face0].is_alive = false;
+        // (i_vtx0, i_vtx1, i_face) where the edge `(i_vtx0, i_vtx1)` is oriented as in the visible face
+        let mut horizon = vec![];
+        let mut i_visible = 0;
+        while i_visible < visible.len() {
+            let i_face = visible[i_visible];
+            i_visible += 1;
+            for i_node in 0..3 {
+                let j_face = faces[i_face].node2face[i_node];
+                if !faces[j_face].is_alive {
+                    continue;
+                }
+                if is_on_or_outside(&faces[j_face].node2vtx, i_vtx_apex) {
+                    faces[j_face].is_alive = false;
+                    visible.push(j_face);
+                } else {
+                    let f = &faces[i_face].node2vtx;
+                    horizon.push((f[(i_node + 1) % 3], f[(i_node + 2) % 3], j_face));
+                }
+            }
+        }
+        // cone from the horizon to the apex
+        let mut edge2face = std::collections::HashMap::<(usize, usize), usize>::new();
+        let i_face_start = faces.len();
+        for &(i_vtx0, i_vtx1, j_face) in &horizon {
+            let i_face = faces.len();
+            let j_node = (0..3)
+                .find(|&j_node| {
+                    let g = &faces[j_face].node2vtx;
+                    g[(j_node + 1) % 3] == i_vtx1 && g[(j_node + 2) % 3] == i_vtx0
+                })
+                .unwrap();
+            faces[j_face].node2face[j_node] = i_face;
+            faces.push(Face {
+                node2vtx: [i_vtx0, i_vtx1, i_vtx_apex],
+                node2face: [usize::MAX, usize::MAX, j_face],
+                outside: vec![],
+                is_alive: true,
+            });
+            edge2face.insert((i_vtx1, i_vtx_apex), i_face);
+            edge2face.insert((i_vtx_apex, i_vtx0), i_face);
+        }
+        for face in faces[i_face_start..].iter_mut() {
+            let [i_vtx0, i_vtx1, _] = face.node2vtx;
+            face.node2face[0] = edge2face[&(i_vtx_apex, i_vtx1)];
+            face.node2face[1] = edge2face[&(i_vtx0, i_vtx_apex)];
+        }
+        let vtxs: Vec<usize> = visible
+            .iter()
+            .flat_map(|&i_face| std::mem::take(&mut faces[i_face].outside))
+            .filter(|&i_vtx| i_vtx != i_vtx_apex)
+            .collect();
+        let new_faces: Vec<usize> = (i_face_start..faces.len()).collect();
+        assign(&mut faces, &new_faces, &vtxs);
+        stack.extend(new_faces);
+    }
+    faces
+        .iter()
+        .filter(|f| f.is_alive)
+        .flat_map(|f| f.node2vtx)
+        .collect()
+}
+
+#[test]
+fn test_convex_hull3() {
+    use rand::Rng;
+    use rand::SeedableRng;
+    let check = |tri2vtx: &[usize], vtx2xyz: &[f64]| {
+        // closed and consistently oriented
+        let num_vtx = vtx2xyz.len() / 3;
+        let (face2idx, idx2node) = crate::elem2elem::face2node_of_simplex_element(3);
+        let tri2tri =
+            crate::elem2elem::from_uniform_mesh(tri2vtx, 3, &face2idx, &idx2node, num_vtx);
+        assert!(tri2tri.iter().all(|&j_tri| j_tri != usize::MAX));
+        let mut edges = std::collections::HashSet::new();
+        for node2vtx in tri2vtx.chunks(3) {
+            for i_node in 0..3 {
+                assert!(edges.insert((node2vtx[i_node], node2vtx[(i_node + 1) % 3])));
+            }
+        }
+        // all the points are inside or on the hull
+        for node2vtx in tri2vtx.chunks(3) {
+            let p = |i: usize| crate::vtx2xyz::to_vec3(vtx2xyz, node2vtx[i]);
+            for i_vtx in 0..num_vtx {
+                let q = crate::vtx2xyz::to_vec3(vtx2xyz, i_vtx);
+                assert!(crate::tetmesh_delaunay::orient3d(p(0), p(1), p(2), q) <= 0);
+            }
+        }
+    };
+    let volume = |tri2vtx: &[usize], vtx2xyz: &[f64]| {
+        (0..tri2vtx.len() / 3)
+            .map(|i_tri| {
+                let (p0, p1, p2) = crate::trimesh3::to_corner_points(tri2vtx, vtx2xyz, i_tri);
+                crate::tetmesh::volume(&[0.; 3], &p0, &p1, &p2)
+            })
+            .sum::<f64>()
+    };
+    let mut reng = rand_chacha::ChaChaRng::seed_from_u64(0);
+    {
+        // random points in a ball
+        let mut vtx2xyz: Vec<f64> = vec![];
+        while vtx2xyz.len() < 300 * 3 {
+            let p: [f64; 3] = std::array::from_fn(|_| reng.random::<f64>() * 2. - 1.);
+            if del_geo_core::vec3::norm(&p) < 1. {
+                vtx2xyz.extend(p);
+            }
+        }
+        let tri2vtx = convex_hull3(&vtx2xyz);
+        check(&tri2vtx, &vtx2xyz);
+        let volume = volume(&tri2vtx, &vtx2xyz);
+        assert!(volume > 0. && volume < 4. / 3. * std::f64::consts::PI);
+    }
+    {
+        // lattice points with many coplanar and duplicated points
+        let mut vtx2xyz: Vec<f64> = (0..64)
+            .flat_map(|i| [(i % 4) as f64, ((i / 4) % 4) as f64, (i / 16) as f64])
+            .collect();
+        vtx2xyz.extend_from_slice(&vtx2xyz.clone()[..30]);
+        let tri2vtx = convex_hull3(&vtx2xyz);
+        check(&tri2vtx, &vtx2xyz);
+        // only the corners of the cube are on the hull
+        let mut vtxs = tri2vtx.clone();
+        vtxs.sort();
+        vtxs.dedup();
+        assert_eq!(vtxs.len(), 8);
+        assert_eq!(tri2vtx.len() / 3, 12);
+        assert!((volume(&tri2vtx, &vtx2xyz) - 27.).abs() < 1.0e-10);
+        crate::io_obj::save_tri2vtx_vtx2xyz("../target/convex_hull3.obj", &tri2vtx, &vtx2xyz, 3)
+            .unwrap();
+    }
+    {
+        // points on a plane
+        let vtx2xyz: Vec<f64> = (0..16)
+            .flat_map(|i| [(i % 4) as f64, (i / 4) as f64, 1.])
+            .collect();
+        assert!(convex_hull3(&vtx2xyz).is_empty());
+    }
+}
@@ -0,0 +1,554 @@
+//! intersection, distance and penetration between two 3D convex hulls using GJK and EPA
+
+use del_geo_core::vec3;
+
+/// convex hull of the 3D points (e.g., the vertices of a convex mesh) placed in the world
+pub struct ConvexHull3<'a, Real> {
+    pub vtx2xyz: &'a [Real],
+    /// rigid transformation (column major) from the local coordinate of `vtx2xyz`
+    /// to the world coordinate. `None` for the identity
+    pub transform: Option<&'a [Real; 16]>,
+}
+
+impl<Real> ConvexHull3<'_, Real>
+where
+    Real: num_traits::Float,
+{
+    /// the furthest point in the direction `dir` in the world coordinate
+    pub fn support(&self, dir: &[Real; 3]) -> [Real; 3] {
+        // the direction in the local coordinate using the transpose of the rotation
+        let dir = match self.transform {
+            None => *dir,
+            Some(m) => std::array::from_fn(|i| vec3::dot(arrayref::array_ref![m, i * 4, 3], dir)),
+        };
+        let mut max_dist = Real::neg_infinity();
+        let mut ret = [Real::zero(); 3];
+        for xyz in self.vtx2xyz.chunks(3) {
+            let xyz = arrayref::array_ref![xyz, 0, 3];
+            let dist = vec3::dot(&dir, xyz);
+            if dist <= max_dist {
+                continue;
+            }
+            max_dist = dist;
+            ret = *xyz;
+        }
+        match self.transform {
+            None => ret,
+            Some(m) => del_geo_core::mat4_col_major::transform_homogeneous(m, &ret).unwrap(),
+        }
+    }
+}
+
+/// point on the Minkowski difference `A - B` with the points on `A` and `B` that make it
+#[derive(Clone, Copy)]
+struct SupportPoint<Real> {
+    w: [Real; 3],
+    a: [Real; 3],
+    b: [Real; 3],
+}
+
+fn support<Real>(
+    hull_a: &ConvexHull3<Real>,
+    hull_b: &ConvexHull3<Real>,
+    dir: &[Real; 3],
+) -> SupportPoint<Real>
+where
+    Real: num_traits::Float,
+{
+    let a = hull_a.support(dir);
+    let b = hull_b.support(&vec3::scale(dir, -Real::one()));
+    SupportPoint {
+        w: vec3::sub(&a, &b),
+        a,
+        b,
+    }
+}
+
+/// barycentric coordinates of the point on the segment closest to the origin
+fn closest_to_origin_on_segment<Real>(p0: &[Real; 3], p1: &[Real; 3]) -> [Real; 2]
+where
+    Real: num_traits::Float,
+{
+    let d = vec3::sub(p1, p0);
+    let dd = vec3::dot(&d, &d);
+    if dd == Real::zero() {
+        return [Real::one(), Real::zero()];
+    }
+    let t = (-vec3::dot(p0, &d) / dd).max(Real::zero()).min(Real::one());
+    [Real::one() - t, t]
+}
+
+/// barycentric coordinates of the point on the triangle closest to the origin
+fn closest_to_origin_on_triangle<Real>(a: &[Real; 3], b: &[Real; 3], c: &[Real; 3]) -> [Real; 3]
+where
+    Real: num_traits::Float,
+{
+    let zero = Real::zero();
+    let one = Real::one();
+    let ab = vec3::sub(b, a);
+    let ac = vec3::sub(c, a);
+    let (d1, d2) = (-vec3::dot(&ab, a), -vec3::dot(&ac, a));
+    if d1 <= zero && d2 <= zero {
+        return [one, zero, zero];
+    }
+    let (d3, d4) = (-vec3::dot(&ab, b), -vec3::dot(&ac, b));
+    if d3 >= zero && d4 <= d3 {
+        return [zero, one, zero];
+    }
+    let vc = d1 * d4 - d3 * d2;
+    if vc <= zero && d1 >= zero && d3 <= zero {
+        let v = d1 / (d1 - d3);
+        return [one - v, v, zero];
+    }
+    let (d5, d6) = (-vec3::dot(&ab, c), -vec3::dot(&ac, c));
+    if d6 >= zero && d5 <= d6 {
+        return [zero, zero, one];
+    }
+    let vb = d5 * d2 - d1 * d6;
+    if vb <= zero && d2 >= zero && d6 <= zero {
+        let w = d2 / (d2 - d6);
+        return [one - w, zero, w];
+    }
+    let va = d3 * d6 - d5 * d4;
+    if va <= zero && d4 - d3 >= zero && d5 - d6 >= zero {
+        let w = (d4 - d3) / ((d4 - d3) + (d5 - d6));
+        return [zero, one - w, w];
+    }
+    let sum = va + vb + vc;
+    if sum <= zero {
+        // degenerate triangle
+        let [r0, r1] = closest_to_origin_on_segment(a, b);
+        let [s0, s1] = closest_to_origin_on_segment(b, c);
+        let [t0, t1] = closest_to_origin_on_segment(c, a);
+        let cands = [[r0, r1, zero], [zero, s0, s1], [t1, zero, t0]];
+        return cands
+            .into_iter()
+            .min_by(|x, y| {
+                let dx = vec3::squared_norm(&barycentric_point(&[*a, *b, *c], x));
+                let dy = vec3::squared_norm(&barycentric_point(&[*a, *b, *c], y));
+                dx.partial_cmp(&dy).unwrap()
+            })
+            .unwrap();
+    }
+    [va / sum, vb / sum, vc / sum]
+}
+
+/// six times the signed volume of the tetrahedron
+fn volume6<Real>(p0: &[Real; 3], p1: &[Real; 3], p2: &[Real; 3], p3: &[Real; 3]) -> Real
+where
+    Real: num_traits::Float,
+{
+    let n = vec3::cross(&vec3::sub(p1, p0), &vec3::sub(p2, p0));
+    vec3::dot(&n, &vec3::sub(p3, p0))
+}
+
+fn barycentric_point<Real>(ps: &[[Real; 3]], rs: &[Real]) -> [Real; 3]
+where
+    Real: num_traits::Float,
+{
+    ps.iter()
+        .zip(rs.iter())
+        .fold([Real::zero(); 3], |s, (p, &r)| vec3::axpy(r, p, &s))
+}
+
+/// barycentric coordinates of the point on the simplex closest to the origin.
+/// `None` if the origin is inside the tetrahedron
+fn closest_to_origin_on_simplex<Real>(ws: &[[Real; 3]]) -> Option<Vec<Real>>
+where
+    Real: num_traits::Float,
+{
+    match ws.len() {
+        1 => Some(vec![Real::one()]),
+        2 => Some(closest_to_origin_on_segment(&ws[0], &ws[1]).to_vec()),
+        3 => Some(closest_to_origin_on_triangle(&ws[0], &ws[1], &ws[2]).to_vec()),
+        4 => {
+            let mut best: Option<(Real, Vec<Real>)> = None;
+            // all the faces are examined if the tetrahedron is flat
+            let is_flat = {
+                let vol = volume6(&ws[0], &ws[1], &ws[2], &ws[3]).abs();
+                let size = ws
+                    .iter()
+                    .map(|w| vec3::norm(&vec3::sub(w, &ws[0])))
+                    .fold(Real::zero(), |a, b| a.max(b));
+                vol <= Real::epsilon() * Real::from(1000).unwrap() * size * size * size
+            };
+            for (i_node, nodes) in crate::tetmesh::TET2FACE_NODES.iter().enumerate() {
+                let [p0, p1, p2] = nodes.map(|j_node| ws[j_node]);
+                let n = vec3::cross(&vec3::sub(&p1, &p0), &vec3::sub(&p2, &p0));
+                let side_origin = -vec3::dot(&n, &p0);
+                let side_node = vec3::dot(&n, &vec3::sub(&ws[i_node], &p0));
+                if !is_flat && side_origin * side_node > Real::zero() {
+                    continue; // the origin is on the same side as the opposite node
+                }
+                let r = closest_to_origin_on_triangle(&p0, &p1, &p2);
+                let dist = vec3::squared_norm(&barycentric_point(&[p0, p1, p2], &r));
+                if best.as_ref().is_some_and(|(d, _)| *d <= dist) {
+                    continue;
+                }
+                let mut rs = vec![Real::zero(); 4];
+                for (k, &j_node) in nodes.iter().enumerate() {
+                    rs[j_node] = r[k];
+                }
+                best = Some((dist, rs));
+            }
+            best.map(|(_, rs)| rs)
+        }
+        _ => panic!(),
+    }
+}
+
+/// result of the GJK algorithm.
+/// The simplex contains the origin if the hulls intersect.
+/// Otherwise, the closest point of the simplex to the origin is given by the barycentric coordinates
+fn gjk3<Real>(
+    hull_a: &ConvexHull3<Real>,
+    hull_b: &ConvexHull3<Real>,
+) -> (Vec<SupportPoint<Real>>, Option<Vec<Real>>)
+where
+    Real: num_traits::Float,
+{
+    let eps = Real::epsilon() * Real::from(1000).unwrap();
+    let mut simplex = vec![support(
+        hull_a,
+        hull_b,
+        &[Real::one(), Real::zero(), Real::zero()],
+    )];
+    let mut max_sq_norm = vec3::squared_norm(&simplex[0].w);
+    for _iter in 0..256 {
+        let ws: Vec<[Real; 3]> = simplex.iter().map(|s| s.w).collect();
+        let Some(rs) = closest_to_origin_on_simplex(&ws) else {
+            return (simplex, None);
+        };
+        let v = barycentric_point(&ws, &rs);
+        let vv = vec3::squared_norm(&v);
+        if vv <= eps * eps * max_sq_norm {
+            return (simplex, None);
+        }
+        // keep the points supporting the closest point
+        let (simplex_new, rs): (Vec<_>, Vec<_>) = simplex
+            .iter()
+            .zip(rs.iter())
+            .filter(|(_, &r)| r > Real::zero())
+            .map(|(&s, &r)| (s, r))
+            .unzip();
+        simplex = simplex_new;
+        let p = support(hull_a, hull_b, &vec3::scale(&v, -Real::one()));
+        if vv - vec3::dot(&v, &p.w) <= eps * vv || simplex.iter().any(|s| s.w == p.w) {
+            return (simplex, Some(rs));
+        }
+        max_sq_norm = max_sq_norm.max(vec3::squared_norm(&p.w));
+        simplex.push(p);
+    }
+    let ws: Vec<[Real; 3]> = simplex.iter().map(|s| s.w).collect();
+    let rs = closest_to_origin_on_simplex(&ws);
+    (simplex, rs)
+}
+
+/// returns true if the convex hulls of the two 3D point sets intersect (including touching)
+pub fn is_intersect_two_convexhull3s_using_gjk<Real>(
+    hull_a: &ConvexHull3<Real>,
+    hull_b: &ConvexHull3<Real>,
+) -> bool
+where
+    Real: num_traits::Float,
+{
+    assert!(!hull_a.vtx2xyz.is_empty() && !hull_b.vtx2xyz.is_empty());
+    gjk3(hull_a, hull_b).1.is_none()
+}
+
+/// distance between the convex hulls of the two 3D point sets
+/// # Returns
+/// `(distance, p_a, p_b)` where `p_a` and `p_b` are the closest points on each hull in the world coordinate.
+/// `None` if the hulls intersect
+pub fn distance_between_two_convexhull3s_using_gjk<Real>(
+    hull_a: &ConvexHull3<Real>,
+    hull_b: &ConvexHull3<Real>,
+) -> Option<(Real, [Real; 3], [Real; 3])>
+where
+    Real: num_traits::Float,
+{
+    assert!(!hull_a.vtx2xyz.is_empty() && !hull_b.vtx2xyz.is_empty());
+    let (simplex, rs) = gjk3(hull_a, hull_b);
+    let rs = rs?;
+    let pa: Vec<[Real; 3]> = simplex.iter().map(|s| s.a).collect();
+    let pb: Vec<[Real; 3]> = simplex.iter().map(|s| s.b).collect();
+    let pa = barycentric_point(&pa, &rs);
+    let pb = barycentric_point(&pb, &rs);
+    Some((vec3::distance(&pa, &pb), pa, pb))
+}
+
+/// computing the penetration depth and its normal for the intersection of the 3D convex hulls.
+/// If we move all the vertices of B with the returned vector, there is no collision.
+/// The norm of the vector is the penetration depth.
+/// `None` if the hulls do not intersect
+pub fn penetration_between_two_convexhull3s_using_epa<Real>(
+    hull_a: &ConvexHull3<Real>,
+    hull_b: &ConvexHull3<Real>,
+    tolerance: Real,
+) -> Option<[Real; 3]>
+where
+    Real: num_traits::Float,
+{
+    let (mut simplex, rs) = gjk3(hull_a, hull_b);
+    if rs.is_some() {
+        return None;
+    }
+    // expand the simplex into a tetrahedron containing the origin
+    let dirs: Vec<[Real; 3]> = {
+        let (one, zero) = (Real::one(), Real::zero());
+        let axes = [[one, zero, zero], [zero, one, zero], [zero, zero, one]];
+        axes.iter()
+            .flat_map(|a| [*a, vec3::scale(a, -one)])
+            .collect()
+    };
+    while simplex.len() < 4 {
+        let ws: Vec<[Real; 3]> = simplex.iter().map(|s| s.w).collect();
+        // size of the simplex after adding `w`. Zero if it is degenerate
+        let measure = |w: &[Real; 3]| match ws.len() {
+            1 => vec3::distance(&ws[0], w),
+            2 => vec3::norm(&vec3::cross(
+                &vec3::sub(&ws[1], &ws[0]),
+                &vec3::sub(w, &ws[0]),
+            )),
+            _ => volume6(&ws[0], &ws[1], &ws[2], w).abs(),
+        };
+        let mut cands: Vec<[Real; 3]> = dirs.clone();
+        if ws.len() == 2 {
+            let d = vec3::sub(&ws[1], &ws[0]);
+            cands.extend(dirs.iter().map(|a| vec3::cross(&d, a)));
+        } else if ws.len() == 3 {
+            let n = vec3::cross(&vec3::sub(&ws[1], &ws[0]), &vec3::sub(&ws[2], &ws[0]));
+            cands.extend([n, vec3::scale(&n, -Real::one())]);
+        }
+        let p = cands
+            .iter()
+            .map(|dir| support(hull_a, hull_b, dir))
+            .max_by(|p, q| measure(&p.w).partial_cmp(&measure(&q.w)).unwrap())
+            .unwrap();
+        if measure(&p.w) <= Real::zero() {
+            // the Minkowski difference is flat
+            return Some([Real::zero(); 3]);
+        }
+        simplex.push(p);
+    }
+    if volume6(&simplex[0].w, &simplex[1].w, &simplex[2].w, &simplex[3].w) < Real::zero() {
+        simplex.swap(2, 3);
+    }
+    let mut vtx2w: Vec<[Real; 3]> = simplex.iter().map(|s| s.w).collect();
+    let mut faces: Vec<[usize; 3]> = crate::tetmesh::TET2FACE_NODES.to_vec();
+    // unit normal and the distance from the origin
+    let plane = |vtx2w: &[[Real; 3]], f: &[usize; 3]| {
+        let n = vec3::cross(
+            &vec3::sub(&vtx2w[f[1]], &vtx2w[f[0]]),
+            &vec3::sub(&vtx2w[f[2]], &vtx2w[f[0]]),
+        );
+        let len = vec3::norm(&n);
+        if len == Real::zero() {
+            return (n, Real::infinity());
+        }
+        let n = vec3::scale(&n, Real::one() / len);
+        (n, vec3::dot(&n, &vtx2w[f[0]]))
+    };
+    let mut best = [Real::zero(); 3];
+    for _iter in 0..256 {
+        let (i_face, (n, dist)) = faces
+            .iter()
+            .map(|f| plane(&vtx2w, f))
+            .enumerate()
+            .min_by(|a, b| a.1 .1.partial_cmp(&b.1 .1).unwrap())
+            .unwrap();
+        best = vec3::scale(&n, dist);
+        let w = support(hull_a, hull_b, &n).w;
+        if vec3::dot(&w, &n) - dist < tolerance {
+            return Some(best);
+        }
+        // remove the faces visible from `w` and fill the hole with the cone
+        let i_vtx_new = vtx2w.len();
+        vtx2w.push(w);
+        let (visible, rest): (Vec<_>, Vec<_>) =
+            faces.into_iter().enumerate().partition(|&(j_face, f)| {
+                j_face == i_face || {
+                    let (n, _) = plane(&vtx2w, &f);
+                    vec3::dot(&n, &vec3::sub(&w, &vtx2w[f[0]])) > Real::zero()
+                }
+            });
+        faces = rest.into_iter().map(|(_, f)| f).collect();
+        let edges: std::collections::HashSet<(usize, usize)> = visible
+            .iter()
+            .flat_map(|(_, f)| [(f[0], f[1]), (f[1], f[2]), (f[2], f[0])])
+            .collect();
+        for &(i0, i1) in &edges {
+            if !edges.contains(&(i1, i0)) {
+                faces.push([i0, i1, i_vtx_new]);
+            }
+        }
+    }
+    Some(best)
+}
+
+#[test]
+fn test_gjk_epa3() {
+    use rand::Rng;
+    use rand::SeedableRng;
+    let mut reng = rand_chacha::ChaCha8Rng::seed_from_u64(0);
+    // reference by the separating axis theorem using the faces and edges of the hulls
+    let is_intersect_sat = |vtx2xyz_a: &[f64], vtx2xyz_b: &[f64]| {
+        let mut axes = vec![];
+        let mut edges = vec![];
+        for vtx2xyz in [vtx2xyz_a, vtx2xyz_b] {
+            let tri2vtx = crate::convexhull3::convex_hull3(vtx2xyz);
+            let mut es = vec![];
+            for i_tri in 0..tri2vtx.len() / 3 {
+                let (p0, p1, p2) = crate::trimesh3::to_corner_points(&tri2vtx, vtx2xyz, i_tri);
+                axes.push(del_geo_core::tri3::normal(&p0, &p1, &p2));
+                es.extend([
+                    vec3::sub(&p1, &p0),
+                    vec3::sub(&p2, &p1),
+                    vec3::sub(&p0, &p2),
+                ]);
+            }
+            edges.push(es);
+        }
+        for e0 in &edges[0] {
+            for e1 in &edges[1] {
+                axes.push(vec3::cross(e0, e1));
+            }
+        }
+        let range = |vtx2xyz: &[f64], a: &[f64; 3]| {
+            vtx2xyz
+                .chunks(3)
+                .map(|p| vec3::dot(a, arrayref::array_ref![p, 0, 3]))
+                .fold((f64::INFINITY, f64::NEG_INFINITY), |(l, u), d| {
+                    (l.min(d), u.max(d))
+                })
+        };
+        !axes.iter().any(|a| {
+            if vec3::norm(a) < 1.0e-10 {
+                return false;
+            }
+            let a = vec3::normalize(a);
+            let (ra, rb) = (range(vtx2xyz_a, &a), range(vtx2xyz_b, &a));
+            ra.1 < rb.0 - 1.0e-10 || rb.1 < ra.0 - 1.0e-10
+        })
+    };
+    let mut num_intersect = 0;
+    for _itr in 0..30 {
+        let vtx2xyz_a: Vec<f64> = (0..12 * 3)
+            .map(|_| reng.random::<f64>() * 2. - 1.)
+            .collect();
+        let vtx2xyz_b: Vec<f64> = (0..8 * 3).map(|_| reng.random::<f64>() * 2. - 1.).collect();
+        for it in 0..20 {
+            let t = it as f64 * 0.1;
+            let transform = del_geo_core::mat4_col_major::mult_mat_col_major(
+                &del_geo_core::mat4_col_major::from_translate(&[2.5 * (3. * t).sin(), 0.3, 0.]),
+                &del_geo_core::mat4_col_major::from_bryant_angles(t, 2. * t, 0.5 * t),
+            );
+            let hull_a = ConvexHull3 {
+                vtx2xyz: &vtx2xyz_a,
+                transform: None,
+            };
+            let hull_b = ConvexHull3 {
+                vtx2xyz: &vtx2xyz_b,
+                transform: Some(&transform),
+            };
+            let vtx2xyz_b_world: Vec<f64> = vtx2xyz_b
+                .chunks(3)
+                .flat_map(|p| {
+                    del_geo_core::mat4_col_major::transform_homogeneous(
+                        &transform,
+                        arrayref::array_ref![p, 0, 3],
+                    )
+                    .unwrap()
+                })
+                .collect();
+            let is_intersect = is_intersect_two_convexhull3s_using_gjk(&hull_a, &hull_b);
+            assert_eq!(is_intersect, is_intersect_sat(&vtx2xyz_a, &vtx2xyz_b_world));
+            if let Some((dist, pa, pb)) =
+                distance_between_two_convexhull3s_using_gjk(&hull_a, &hull_b)
+            {
+                assert!(!is_intersect);
+                assert!((vec3::distance(&pa, &pb) - dist).abs() < 1.0e-10);
+                // the slab between the witness points separates the hulls
+                let n = vec3::scale(&vec3::sub(&pb, &pa), 1. / dist);
+                for p in vtx2xyz_a.chunks(3) {
+                    let p = arrayref::array_ref![p, 0, 3];
+                    assert!(vec3::dot(&n, &vec3::sub(p, &pa)) < 1.0e-6);
+                }
+                for p in vtx2xyz_b_world.chunks(3) {
+                    let p = arrayref::array_ref![p, 0, 3];
+                    assert!(vec3::dot(&n, &vec3::sub(p, &pb)) > -1.0e-6);
+                }
+                continue;
+            }
+            assert!(is_intersect);
+            num_intersect += 1;
+            let normal_a =
+                penetration_between_two_convexhull3s_using_epa(&hull_a, &hull_b, 1.0e-8).unwrap();
+            for (ratio, is_intersect) in [(1.002, false), (0.998, true)] {
+                let t =
+                    del_geo_core::mat4_col_major::from_translate(&vec3::scale(&normal_a, ratio));
+                let transform1 = del_geo_core::mat4_col_major::mult_mat_col_major(&t, &transform);
+                let hull_b1 = ConvexHull3 {
+                    vtx2xyz: &vtx2xyz_b,
+                    transform: Some(&transform1),
+                };
+                assert_eq!(
+                    is_intersect_two_convexhull3s_using_gjk(&hull_a, &hull_b1),
+                    is_intersect
+                );
+            }
+        }
+    }
+    assert!(num_intersect > 50);
+    {
+        // two unit cubes
+        let vtx2xyz: Vec<f64> = (0..8)
+            .flat_map(|i| [(i % 2) as f64, ((i / 2) % 2) as f64, (i / 4) as f64])
+            .collect();
+        let hull_a = ConvexHull3 {
+            vtx2xyz: &vtx2xyz,
+            transform: None,
+        };
+        let t = del_geo_core::mat4_col_major::from_translate(&[0.75, 0.5, 0.1]);
+        let hull_b = ConvexHull3 {
+            vtx2xyz: &vtx2xyz,
+            transform: Some(&t),
+        };
+        let normal_a =
+            penetration_between_two_convexhull3s_using_epa(&hull_a, &hull_b, 1.0e-10).unwrap();
+        assert!(vec3::distance(&normal_a, &[0.25, 0., 0.]) < 1.0e-10);
+        let t = del_geo_core::mat4_col_major::from_translate(&[2., 0.5, 0.1]);
+        let hull_b = ConvexHull3 {
+            vtx2xyz: &vtx2xyz,
+            transform: Some(&t),
+        };
+        let (dist, pa, pb) = distance_between_two_convexhull3s_using_gjk(&hull_a, &hull_b).unwrap();
+        assert!((dist - 1.).abs() < 1.0e-10);
+        assert!((pa[0] - 1.).abs() < 1.0e-10 && (pb[0] - 2.).abs() < 1.0e-10);
+        // touching
+        let t = del_geo_core::mat4_col_major::from_translate(&[1., 0.5, 0.1]);
+        let hull_b = ConvexHull3 {
+            vtx2xyz: &vtx2xyz,
+            transform: Some(&t),
+        };
+        assert!(is_intersect_two_convexhull3s_using_gjk(&hull_a, &hull_b));
+        // a single point
+        for (xyz, depth) in [([0.9, 0.5, 0.2], 0.1), ([0.5, 1.5, 0.5], -0.5)] {
+            let hull_b = ConvexHull3 {
+                vtx2xyz: &xyz,
+                transform: None,
+            };
+            if depth > 0. {
+                let normal_a =
+                    penetration_between_two_convexhull3s_using_epa(&hull_a, &hull_b, 1.0e-10)
+                        .unwrap();
+                assert!(vec3::distance(&normal_a, &[depth, 0., 0.]) < 1.0e-10);
+            } else {
+                let (dist, pa, _) =
+                    distance_between_two_convexhull3s_using_gjk(&hull_a, &hull_b).unwrap();
+                assert!((dist + depth).abs() < 1.0e-10);
+                assert!(vec3::distance(&pa, &[0.5, 1., 0.5]) < 1.0e-10);
+            }
+        }
+    }
+}
@@ -0,0 +1,204 @@
+//! save and load BVH (`bvhnodes` and `bvhnode2aabb`) as a binary file
+//! together with the hash of the mesh, so that a stale BVH is not used for a modified mesh.
+//!
+//! layout (little endian):
+//! * magic `b"DELMSHBV"`
+//! * `u64` hash of the mesh
+//! * `u64` number of BVH nodes
+//! * `u64` number of values in an AABB (4 for 2D, 6 for 3D)
+//! * `u32` * 3 for each node (`u32::MAX` for the invalid index)
+//! * `f32` AABBs
+
+use anyhow::Context;
+use num_traits::AsPrimitive;
+
+const MAGIC: &[u8; 8] = b"DELMSHBV";
+
+/// FNV-1a hash of the connectivity and the coordinates of a mesh
+pub fn hash_of_mesh<Index>(elem2vtx: &[Index], vtx2xyz: &[f32]) -> u64
+where
+    Index: AsPrimitive<usize>,
+{
+    const PRIME: u64 = 0x100000001b3;
+    let mut h: u64 = 0xcbf29ce484222325;
+    let mut add = |bytes: &[u8]| {
+        for &b in bytes {
+            h ^= b as u64;
+            h = h.wrapping_mul(PRIME);
+        }
+    };
+    add(&(elem2vtx.len() as u64).to_le_bytes());
+    for &i_vtx in elem2vtx {
+        add(&(i_vtx.as_() as u64).to_le_bytes());
+    }
+    add(&(vtx2xyz.len() as u64).to_le_bytes());
+    for &x in vtx2xyz {
+        add(&x.to_bits().to_le_bytes());
+    }
+    h
+}
+
+pub fn save<P, Index>(
+    file_path: P,
+    bvhnodes: &[Index],
+    bvhnode2aabb: &[f32],
+    hash: u64,
+) -> anyhow::Result<()>
+where
+    P: AsRef<std::path::Path>,
+    Index: num_traits::PrimInt + AsPrimitive<usize>,
+{
+    use std::io::Write;
+    let num_bvhnode = bvhnodes.len() / 3;
+    if bvhnodes.len() != num_bvhnode * 3 || num_bvhnode >= u32::MAX as usize {
+        anyhow::bail!("invalid number of BVH nodes");
+    }
+    // the empty BVH is saved with zero values per AABB
+    let num_aabb_value = bvhnode2aabb.len().checked_div(num_bvhnode).unwrap_or(0);
+    if bvhnode2aabb.len() != num_bvhnode * num_aabb_value
+        || !(num_aabb_value == 4 || num_aabb_value == 6 || num_bvhnode == 0)
+    {
+        anyhow::bail!("the size of the AABBs does not match the BVH nodes");
+    }
+    let mut buf = Vec::<u8>::with_capacity(32 + bvhnodes.len() * 4 + bvhnode2aabb.len() * 4);
+    buf.extend_from_slice(MAGIC);
+    buf.extend_from_slice(&hash.to_le_bytes());
+    buf.extend_from_slice(&(num_bvhnode as u64).to_le_bytes());
+    buf.extend_from_slice(&(num_aabb_value as u64).to_le_bytes());
+    for &i in bvhnodes {
+        let i: u32 = if i == Index::max_value() {
+            u32::MAX
+        } else {
+            i.as_() as u32
+        };
+        buf.extend_from_slice(&i.to_le_bytes());
+    }
+    for &x in bvhnode2aabb {
+        buf.extend_from_slice(&x.to_le_bytes());
+    }
+    let mut file = std::fs::File::create(file_path.as_ref())
+        .with_context(|| format!("cannot create {:?}", file_path.as_ref()))?;
+    file.write_all(&buf)?;
+    Ok(())
+}
+
+/// load the BVH. Returns error if the file is broken or
+/// the hash in the file is different from `hash` (i.e., the BVH is stale)
+pub fn load<P, Index>(file_path: P, hash: u64) -> anyhow::Result<(Vec<Index>, Vec<f32>)>
+where
+    P: AsRef<std::path::Path>,
+    Index: num_traits::PrimInt + 'static,
+    usize: AsPrimitive<Index>,
+{
+    let buf = std::fs::read(file_path.as_ref())
+        .with_context(|| format!("cannot read {:?}", file_path.as_ref()))?;
+    if buf.len() < 32 || &buf[0..8] != MAGIC {
+        anyhow::bail!("not a BVH file");
+    }
+    let read_u64 = |pos: usize| u64::from_le_bytes(buf[pos..pos + 8].try_into().unwrap());
+    let hash_file = read_u64(8);
+    if hash_file != hash {
+        anyhow::bail!("stale BVH: the mesh is modified after the BVH is saved");
+    }
+    let num_bvhnode = read_u64(16);
+    let num_aabb_value = read_u64(24);
+    if !(num_aabb_value == 4 || num_aabb_value == 6 || (num_bvhnode, num_aabb_value) == (0, 0)) {
+        anyhow::bail!("broken BVH file: invalid number of values in an AABB");
+    }
+    // the header may be corrupted, so the size is computed without overflow
+    let num_byte = num_bvhnode
+        .checked_mul((3 + num_aabb_value) * 4)
+        .and_then(|n| n.checked_add(32));
+    if num_byte != Some(buf.len() as u64) {
+        anyhow::bail!("broken BVH file: the size does not match the header");
+    }
+    let num_bvhnode = num_bvhnode as usize;
+    let (buf_bvhnodes, buf_aabbs) = buf[32..].split_at(num_bvhnode * 3 * 4);
+    let bvhnodes: Vec<Index> = buf_bvhnodes
+        .chunks_exact(4)
+        .map(|b| {
+            let i = u32::from_le_bytes(b.try_into().unwrap());
+            if i == u32::MAX {
+                Index::max_value()
+            } else {
+                (i as usize).as_()
+            }
+        })
+        .collect();
+    let bvhnode2aabb: Vec<f32> = buf_aabbs
+        .chunks_exact(4)
+        .map(|b| f32::from_le_bytes(b.try_into().unwrap()))
+        .collect();
+    Ok((bvhnodes, bvhnode2aabb))
+}
+
+#[test]
+fn test_save_load() -> anyhow::Result<()> {
+    let (tri2vtx, vtx2xyz) = crate::trimesh3_primitive::sphere_yup::<usize, f32>(1.0, 16, 16);
+    let bvhnodes = crate::bvhnodes_morton::from_triangle_mesh(&tri2vtx, &vtx2xyz, 3);
+    let bvhnode2aabb = crate::bvhnode2aabb3::from_uniform_mesh_with_bvh(
+        0,
+        &bvhnodes,
+        Some((&tri2vtx, 3)),
+        &vtx2xyz,
+        None,
+    );
+    let hash = hash_of_mesh(&tri2vtx, &vtx2xyz);
+    let path = "../target/bvh.bin";
+    save(path, &bvhnodes, &bvhnode2aabb, hash)?;
+    let (bvhnodes1, bvhnode2aabb1) = load::<_, usize>(path, hash)?;
+    assert_eq!(bvhnodes, bvhnodes1);
+    assert_eq!(bvhnode2aabb, bvhnode2aabb1);
+    // the mesh is modified
+    let mut vtx2xyz1 = vtx2xyz.clone();
+    vtx2xyz1[0] += 1.0e-3;
+    assert!(load::<_, usize>(path, hash_of_mesh(&tri2vtx, &vtx2xyz1)).is_err());
+    let mut tri2vtx1 = tri2vtx.clone();
+    tri2vtx1.swap(0, 1);
+    assert!(load::<_, u32>(path, hash_of_mesh(&tri2vtx1, &vtx2xyz)).is_err());
+    Ok(())
+}
+
+#[test]
+fn test_broken_file() -> anyhow::Result<()> {
+    let bvhnodes: Vec<u32> = vec![u32::MAX, 0, u32::MAX];
+    let bvhnode2aabb = vec![0f32, 0., 0., 1., 1., 1.];
+    let path = "../target/bvh_broken.bin";
+    save(path, &bvhnodes, &bvhnode2aabb, 0)?;
+    let buf = std::fs::read(path)?;
+    assert_eq!(
+        load::<_, u32>(path, 0)?,
+        (bvhnodes.clone(), bvhnode2aabb.clone())
+    );
+    // the number of the nodes is corrupted so that the size overflows
+    for num_bvhnode in [u64::MAX, u64::MAX / 10 + 1, 1 << 62, 2] {
+        let mut buf1 = buf.clone();
+        buf1[16..24].copy_from_slice(&num_bvhnode.to_le_bytes());
+        std::fs::write(path, &buf1)?;
+        assert!(load::<_, u32>(path, 0).is_err());
+    }
+    // the number of the values in an AABB is corrupted
+    for num_aabb_value in [0u64, 5, u64::MAX] {
+        let mut buf1 = buf.clone();
+        buf1[24..32].copy_from_slice(&num_aabb_value.to_le_bytes());
+        std::fs::write(path, &buf1)?;
+        assert!(load::<_, u32>(path, 0).is_err());
+    }
+    // truncated
+    std::fs::write(path, &buf[..buf.len() - 4])?;
+    assert!(load::<_, u32>(path, 0).is_err());
+    std::fs::write(path, &buf[..20])?;
+    assert!(load::<_, u32>(path, 0).is_err());
+    // mismatched sizes are rejected when saving
+    assert!(save(path, &bvhnodes, &bvhnode2aabb[..5], 0).is_err());
+    Ok(())
+}
+
+#[test]
+fn test_empty() -> anyhow::Result<()> {
+    let path = "../target/bvh_empty.bin";
+    save::<_, u32>(path, &[], &[], 3)?;
+    let (bvhnodes, bvhnode2aabb) = load::<_, u32>(path, 3)?;
+    assert!(bvhnodes.is_empty() && bvhnode2aabb.is_empty());
+    Ok(())
+}
@@ -0,0 +1,296 @@
+//! Kd-tree for N-dimensional points with k-nearest neighbour and fixed-radius search
+
+/// Kd-tree owning the coordinates of the points
+/// * `nodes` - `[point index, left child, right child]` for each node. `usize::MAX` if there is no child.
+///   The node at depth `d` splits the space in the `d % N`-th coordinate
+/// * `vtx2vecn` - coordinates of the points (`N` values for each point)
+pub struct KdTree<Real, const N: usize> {
+    pub nodes: Vec<usize>,
+    pub vtx2vecn: Vec<Real>,
+}
+
+/// construct the sub-tree for the points `idx2vtx` and returns its node index
+#[allow(clippy::identity_op)]
+fn construct<Real, const N: usize>(
+    nodes: &mut Vec<usize>,
+    idx2vtx: &mut [usize],
+    vtx2vecn: &[Real],
+    i_depth: usize,
+) -> usize
+where
+    Real: num_traits::Float,
+{
+    let i_dim = i_depth % N;
+    let idx_mid = idx2vtx.len() / 2; // median point
+    idx2vtx.select_nth_unstable_by(idx_mid, |&i0, &i1| {
+        vtx2vecn[i0 * N + i_dim]
+            .partial_cmp(&vtx2vecn[i1 * N + i_dim])
+            .unwrap()
+    });
+    let i_node = nodes.len() / 3;
+    nodes.extend([idx2vtx[idx_mid], usize::MAX, usize::MAX]);
+    let (idx2vtx_left, idx2vtx_right) = idx2vtx.split_at_mut(idx_mid);
+    let idx2vtx_right = &mut idx2vtx_right[1..];
+    if !idx2vtx_left.is_empty() {
+        nodes[i_node * 3 + 1] = construct::<Real, N>(nodes, idx2vtx_left, vtx2vecn, i_depth + 1);
+    }
+    if !idx2vtx_right.is_empty() {
+        nodes[i_node * 3 + 2] = construct::<Real, N>(nodes, idx2vtx_right, vtx2vecn, i_depth + 1);
+    }
+    i_node
+}
+
+impl<Real, const N: usize> KdTree<Real, N>
+where
+    Real: num_traits::Float,
+{
+    pub fn from_vtx2vecn(vtx2vecn: &[Real]) -> Self {
+        assert_eq!(vtx2vecn.len() % N, 0);
+        let num_vtx = vtx2vecn.len() / N;
+        let mut nodes = Vec::<usize>::with_capacity(num_vtx * 3);
+        if num_vtx > 0 {
+            let mut idx2vtx: Vec<usize> = (0..num_vtx).collect();
+            construct::<Real, N>(&mut nodes, &mut idx2vtx, vtx2vecn, 0);
+        }
+        KdTree {
+            nodes,
+            vtx2vecn: vtx2vecn.to_vec(),
+        }
+    }
+
+    pub fn num_vtx(&self) -> usize {
+        self.vtx2vecn.len() / N
+    }
+
+    fn squared_distance(&self, i_vtx: usize, pos: &[Real; N]) -> Real {
+        let p = &self.vtx2vecn[i_vtx * N..(i_vtx + 1) * N];
+        p.iter()
+            .zip(pos.iter())
+            .fold(Real::zero(), |s, (&a, &b)| s + (a - b) * (a - b))
+    }
+
+    /// `knn` is the list of the pairs of the squared distance and the point index sorted by distance
+    #[allow(clippy::identity_op)]
+    fn k_nearest_in_branch(
+        &self,
+        knn: &mut Vec<(Real, usize)>,
+        pos: &[Real; N],
+        k: usize,
+        i_node: usize,
+        i_depth: usize,
+    ) {
+        if i_node == usize::MAX {
+            return;
+        }
+        let i_vtx = self.nodes[i_node * 3 + 0];
+        let dist = self.squared_distance(i_vtx, pos);
+        if knn.len() < k || dist < knn[knn.len() - 1].0 {
+            let idx = knn.partition_point(|v| v.0 <= dist);
+            knn.insert(idx, (dist, i_vtx));
+            knn.truncate(k);
+        }
+        let i_dim = i_depth % N;
+        let diff = pos[i_dim] - self.vtx2vecn[i_vtx * N + i_dim];
+        let (i_node_near, i_node_far) = if diff < Real::zero() {
+            (self.nodes[i_node * 3 + 1], self.nodes[i_node * 3 + 2])
+        } else {
+            (self.nodes[i_node * 3 + 2], self.nodes[i_node * 3 + 1])
+        };
+        self.k_nearest_in_branch(knn, pos, k, i_node_near, i_depth + 1);
+        if knn.len() < k || diff * diff < knn[knn.len() - 1].0 {
+            self.k_nearest_in_branch(knn, pos, k, i_node_far, i_depth + 1);
+        }
+    }
+
+    /// `k` nearest points from `pos` as the pairs of the point index and the distance
+    /// sorted by the distance. The length is smaller than `k` if there are fewer points
+    pub fn k_nearest(&self, pos: &[Real; N], k: usize) -> Vec<(usize, Real)> {
+        let mut knn = Vec::<(Real, usize)>::with_capacity(k + 1);
+        if k > 0 && !self.nodes.is_empty() {
+            self.k_nearest_in_branch(&mut knn, pos, k, 0, 0);
+        }
+        knn.iter().map(|&(d, i_vtx)| (i_vtx, d.sqrt())).collect()
+    }
+
+    /// the nearest point from `pos` as the pair of the point index and the distance
+    pub fn nearest(&self, pos: &[Real; N]) -> Option<(usize, Real)> {
+        self.k_nearest(pos, 1).first().copied()
+    }
+
+    #[allow(clippy::identity_op)]
+    fn inside_radius_in_branch(
+        &self,
+        idx2vtx: &mut Vec<usize>,
+        pos: &[Real; N],
+        rad: Real,
+        i_node: usize,
+        i_depth: usize,
+    ) {
+        if i_node == usize::MAX {
+            return;
+        }
+        let i_vtx = self.nodes[i_node * 3 + 0];
+        if self.squared_distance(i_vtx, pos) < rad * rad {
+            idx2vtx.push(i_vtx);
+        }
+        let i_dim = i_depth % N;
+        let diff = pos[i_dim] - self.vtx2vecn[i_vtx * N + i_dim];
+        if diff < rad {
+            self.inside_radius_in_branch(
+                idx2vtx,
+                pos,
+                rad,
+                self.nodes[i_node * 3 + 1],
+                i_depth + 1,
+            );
+        }
+        if -diff < rad {
+            self.inside_radius_in_branch(
+                idx2vtx,
+                pos,
+                rad,
+                self.nodes[i_node * 3 + 2],
+                i_depth + 1,
+            );
+        }
+    }
+
+    /// indices of the points closer than `rad` from `pos` (in no particular order)
+    pub fn inside_radius(&self, pos: &[Real; N], rad: Real) -> Vec<usize> {
+        let mut idx2vtx = vec![];
+        if !self.nodes.is_empty() {
+            self.inside_radius_in_branch(&mut idx2vtx, pos, rad, 0, 0);
+        }
+        idx2vtx
+    }
+}
+
+impl<Real, const N: usize> KdTree<Real, N>
+where
+    Real: num_traits::Float + Send + Sync,
+{
+    /// k-nearest neighbours for many query points in parallel
+    /// * `qry2vecn` - coordinates of the query points
+    ///
+    /// returns the point indices and the distances (`k` values for each query).
+    /// The missing neighbours are padded with `usize::MAX` and infinity
+    pub fn k_nearest_for_points(&self, qry2vecn: &[Real], k: usize) -> (Vec<usize>, Vec<Real>) {
+        use rayon::prelude::*;
+        let num_qry = qry2vecn.len() / N;
+        let mut qry2idx = vec![usize::MAX; num_qry * k];
+        let mut qry2dist = vec![Real::infinity(); num_qry * k];
+        if k == 0 {
+            return (qry2idx, qry2dist);
+        }
+        qry2idx
+            .par_chunks_mut(k)
+            .zip(qry2dist.par_chunks_mut(k))
+            .enumerate()
+            .for_each(|(i_qry, (idx, dist))| {
+                let pos: &[Real; N] = qry2vecn[i_qry * N..(i_qry + 1) * N].try_into().unwrap();
+                for (i_knn, (i_vtx, d)) in self.k_nearest(pos, k).into_iter().enumerate() {
+                    idx[i_knn] = i_vtx;
+                    dist[i_knn] = d;
+                }
+            });
+        (qry2idx, qry2dist)
+    }
+
+    /// fixed-radius search for many query points in parallel
+    /// * `qry2vecn` - coordinates of the query points
+    ///
+    /// returns the points found for each query in the jagged array format `(qry2idx, idx2vtx)`
+    pub fn inside_radius_for_points(
+        &self,
+        qry2vecn: &[Real],
+        rad: Real,
+    ) -> (Vec<usize>, Vec<usize>) {
+        use rayon::prelude::*;
+        let num_qry = qry2vecn.len() / N;
+        let qry2vtxs: Vec<Vec<usize>> = (0..num_qry)
+            .into_par_iter()
+            .map(|i_qry| {
+                self.inside_radius(
+                    qry2vecn[i_qry * N..(i_qry + 1) * N].try_into().unwrap(),
+                    rad,
+                )
+            })
+            .collect();
+        let mut qry2idx = vec![0usize; num_qry + 1];
+        for i_qry in 0..num_qry {
+            qry2idx[i_qry + 1] = qry2idx[i_qry] + qry2vtxs[i_qry].len();
+        }
+        let idx2vtx = qry2vtxs.concat();
+        (qry2idx, idx2vtx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::KdTree;
+
+    fn distance<const N: usize>(vtx2vecn: &[f64], i_vtx: usize, pos: &[f64; N]) -> f64 {
+        (0..N)
+            .map(|i_dim| (vtx2vecn[i_vtx * N + i_dim] - pos[i_dim]).powi(2))
+            .sum::<f64>()
+            .sqrt()
+    }
+
+    fn check<const N: usize>() {
+        use rand::Rng;
+        use rand::SeedableRng;
+        let mut reng = rand_chacha::ChaChaRng::seed_from_u64(0);
+        let num_vtx = 2000;
+        let vtx2vecn: Vec<f64> = (0..num_vtx * N).map(|_| reng.random::<f64>()).collect();
+        let tree = KdTree::<f64, N>::from_vtx2vecn(&vtx2vecn);
+        assert_eq!(tree.nodes.len(), num_vtx * 3);
+        let qry2vecn: Vec<f64> = (0..100 * N).map(|_| reng.random::<f64>()).collect();
+        let k = 7;
+        let rad = 0.2;
+        let (qry2knn, qry2dist) = tree.k_nearest_for_points(&qry2vecn, k);
+        let (qry2idx, idx2vtx) = tree.inside_radius_for_points(&qry2vecn, rad);
+        for (i_qry, pos) in qry2vecn.chunks(N).enumerate() {
+            let pos: &[f64; N] = pos.try_into().unwrap();
+            let mut vtx2dist: Vec<(f64, usize)> = (0..num_vtx)
+                .map(|i_vtx| (distance(&vtx2vecn, i_vtx, pos), i_vtx))
+                .collect();
+            vtx2dist.sort_by(|a, b| a.partial_cmp(b).unwrap());
+            // k-nearest
+            let knn = tree.k_nearest(pos, k);
+            assert_eq!(knn.len(), k);
+            for i_knn in 0..k {
+                assert_eq!(knn[i_knn].1, vtx2dist[i_knn].0);
+                assert_eq!(qry2dist[i_qry * k + i_knn], knn[i_knn].1);
+                assert_eq!(qry2knn[i_qry * k + i_knn], knn[i_knn].0);
+            }
+            assert_eq!(tree.nearest(pos).unwrap().0, vtx2dist[0].1);
+            // radius
+            let mut vtxs0 = idx2vtx[qry2idx[i_qry]..qry2idx[i_qry + 1]].to_vec();
+            vtxs0.sort();
+            let mut vtxs1: Vec<usize> =
+                vtx2dist.iter().filter(|v| v.0 < rad).map(|v| v.1).collect();
+            vtxs1.sort();
+            assert_eq!(vtxs0, vtxs1);
+        }
+    }
+
+    #[test]
+    fn test_k_nearest_inside_radius() {
+        check::<2>();
+        check::<3>();
+        check::<5>();
+    }
+
+    #[test]
+    fn test_few_points() {
+        let tree = KdTree::<f32, 3>::from_vtx2vecn(&[]);
+        assert!(tree.nearest(&[0., 0., 0.]).is_none());
+        let tree = KdTree::<f32, 3>::from_vtx2vecn(&[0., 0., 0., 1., 0., 0.]);
+        let knn = tree.k_nearest(&[0.9, 0., 0.], 5);
+        assert_eq!(knn.len(), 2);
+        assert_eq!(knn[0].0, 1);
+        let (qry2knn, qry2dist) = tree.k_nearest_for_points(&[0.2, 0., 0.], 3);
+        assert_eq!(qry2knn, vec![0, 1, usize::MAX]);
+        assert!(qry2dist[2].is_infinite());
+    }
+}
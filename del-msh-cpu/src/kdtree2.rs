@@ -2,8 +2,6 @@
 
 use num_traits::AsPrimitive;
 
-// TODO: insert point in KD-tree for poisson disk sampling
-
 /// construct Kd-tree recursively
 /// * `nodes`
 /// * `idx_node`
@@ -375,6 +373,325 @@ pub fn inside_square<Real>(
     }
 }
 
+// ------------------------------------
+// dynamic Kd-tree
+
+/// 2D Kd-tree supporting the insertion and the deletion of points.
+/// The nodes have the same layout as [`construct_kdtree`] (`[point index, left child, right child]`)
+/// except that the root is `root` and the children are `usize::MAX` if they do not exist.
+/// A deleted point stays in the tree until the tree is rebuilt.
+/// The sub-tree that becomes too deep by insertions is rebuilt (scapegoat tree)
+/// and the whole tree is rebuilt when the half of the nodes are deleted,
+/// so each operation takes amortized O(log n)
+pub struct DynamicKdTree2<Real> {
+    pub nodes: Vec<usize>,
+    pub root: usize,
+    pub vtx2xy: Vec<Real>,
+    vtx2isalive: Vec<bool>,
+    /// node slots not used in the tree
+    free_nodes: Vec<usize>,
+    /// number of the deleted points still in the tree
+    num_dead: usize,
+}
+
+impl<Real> Default for DynamicKdTree2<Real> {
+    fn default() -> Self {
+        Self {
+            nodes: vec![],
+            root: usize::MAX,
+            vtx2xy: vec![],
+            vtx2isalive: vec![],
+            free_nodes: vec![],
+            num_dead: 0,
+        }
+    }
+}
+
+impl<Real> DynamicKdTree2<Real>
+where
+    Real: num_traits::Float,
+{
+    /// the weight-balance factor of the scapegoat tree
+    const ALPHA: f64 = 0.7;
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn from_vtx2xy(vtx2xy: &[Real]) -> Self {
+        let mut tree = Self {
+            vtx2xy: vtx2xy.to_vec(),
+            vtx2isalive: vec![true; vtx2xy.len() / 2],
+            ..Self::default()
+        };
+        tree.rebuild();
+        tree
+    }
+
+    /// number of the points not deleted
+    pub fn num_point(&self) -> usize {
+        self.num_node() - self.num_dead
+    }
+
+    pub fn is_alive(&self, i_vtx: usize) -> bool {
+        self.vtx2isalive[i_vtx]
+    }
+
+    fn num_node(&self) -> usize {
+        self.nodes.len() / 3 - self.free_nodes.len()
+    }
+
+    fn xy(&self, i_vtx: usize) -> [Real; 2] {
+        [self.vtx2xy[i_vtx * 2], self.vtx2xy[i_vtx * 2 + 1]]
+    }
+
+    #[allow(clippy::identity_op)]
+    fn new_node(&mut self, slots: &mut Vec<usize>, i_vtx: usize) -> usize {
+        let i_node = match slots.pop().or_else(|| self.free_nodes.pop()) {
+            Some(i_node) => i_node,
+            None => {
+                self.nodes.extend([0, 0, 0]);
+                self.nodes.len() / 3 - 1
+            }
+        };
+        self.nodes[i_node * 3 + 0] = i_vtx;
+        self.nodes[i_node * 3 + 1] = usize::MAX;
+        self.nodes[i_node * 3 + 2] = usize::MAX;
+        i_node
+    }
+
+    /// balanced sub-tree of the points `idx2vtx`. The node slots are taken from the back of `slots`
+    fn build(&mut self, slots: &mut Vec<usize>, idx2vtx: &mut [usize], i_depth: usize) -> usize {
+        let i_dim = i_depth % 2;
+        let idx_mid = idx2vtx.len() / 2; // median point
+        let vtx2xy = &self.vtx2xy;
+        idx2vtx.select_nth_unstable_by(idx_mid, |&i0, &i1| {
+            vtx2xy[i0 * 2 + i_dim]
+                .partial_cmp(&vtx2xy[i1 * 2 + i_dim])
+                .unwrap()
+        });
+        let i_node = self.new_node(slots, idx2vtx[idx_mid]);
+        let (idx2vtx_left, idx2vtx_right) = idx2vtx.split_at_mut(idx_mid);
+        let idx2vtx_right = &mut idx2vtx_right[1..];
+        if !idx2vtx_left.is_empty() {
+            self.nodes[i_node * 3 + 1] = self.build(slots, idx2vtx_left, i_depth + 1);
+        }
+        if !idx2vtx_right.is_empty() {
+            self.nodes[i_node * 3 + 2] = self.build(slots, idx2vtx_right, i_depth + 1);
+        }
+        i_node
+    }
+
+    /// node slots and the points alive in the sub-tree
+    fn collect(&self, i_node: usize, slots: &mut Vec<usize>, idx2vtx: &mut Vec<usize>) {
+        if i_node == usize::MAX {
+            return;
+        }
+        slots.push(i_node);
+        let i_vtx = self.nodes[i_node * 3];
+        if self.vtx2isalive[i_vtx] {
+            idx2vtx.push(i_vtx);
+        }
+        self.collect(self.nodes[i_node * 3 + 1], slots, idx2vtx);
+        self.collect(self.nodes[i_node * 3 + 2], slots, idx2vtx);
+    }
+
+    fn subtree_size(&self, i_node: usize) -> usize {
+        if i_node == usize::MAX {
+            return 0;
+        }
+        1 + self.subtree_size(self.nodes[i_node * 3 + 1])
+            + self.subtree_size(self.nodes[i_node * 3 + 2])
+    }
+
+    /// rebuild the sub-tree at `i_node` in the depth `i_depth` removing the deleted points.
+    /// returns the new root of the sub-tree
+    fn rebuild_subtree(&mut self, i_node: usize, i_depth: usize) -> usize {
+        let mut slots = vec![];
+        let mut idx2vtx = vec![];
+        self.collect(i_node, &mut slots, &mut idx2vtx);
+        self.num_dead -= slots.len() - idx2vtx.len();
+        slots.reverse(); // `i_node` is used first for the root of the sub-tree
+        let i_node_new = if idx2vtx.is_empty() {
+            usize::MAX
+        } else {
+            self.build(&mut slots, &mut idx2vtx, i_depth)
+        };
+        self.free_nodes.extend(slots);
+        i_node_new
+    }
+
+    /// rebuild the whole tree removing the deleted points
+    pub fn rebuild(&mut self) {
+        let idx2vtx: Vec<usize> = (0..self.vtx2isalive.len())
+            .filter(|&i_vtx| self.vtx2isalive[i_vtx])
+            .collect();
+        self.nodes.clear();
+        self.free_nodes.clear();
+        self.num_dead = 0;
+        self.root = usize::MAX;
+        if !idx2vtx.is_empty() {
+            let mut idx2vtx = idx2vtx;
+            self.root = self.build(&mut vec![], &mut idx2vtx, 0);
+        }
+    }
+
+    /// insert a point and returns its index
+    #[allow(clippy::identity_op)]
+    pub fn insert(&mut self, xy: [Real; 2]) -> usize {
+        let i_vtx = self.vtx2isalive.len();
+        self.vtx2xy.extend(xy);
+        self.vtx2isalive.push(true);
+        if self.root == usize::MAX {
+            self.root = self.new_node(&mut vec![], i_vtx);
+            return i_vtx;
+        }
+        // path from the root to the new node
+        let mut path = vec![self.root];
+        loop {
+            let i_node = path[path.len() - 1];
+            let i_dim = (path.len() - 1) % 2;
+            let split = self.vtx2xy[self.nodes[i_node * 3 + 0] * 2 + i_dim];
+            let i_side = if xy[i_dim] < split { 1 } else { 2 };
+            let i_child = self.nodes[i_node * 3 + i_side];
+            if i_child == usize::MAX {
+                let i_node_new = self.new_node(&mut vec![], i_vtx);
+                self.nodes[i_node * 3 + i_side] = i_node_new;
+                path.push(i_node_new);
+                break;
+            }
+            path.push(i_child);
+        }
+        let depth_max = (self.num_node() as f64).ln() / (1. / Self::ALPHA).ln();
+        if (path.len() - 1) as f64 <= depth_max.floor() + 1. {
+            return i_vtx;
+        }
+        // find the scapegoat, the deepest unbalanced ancestor
+        let mut size_child = 1;
+        for i_depth in (0..path.len() - 1).rev() {
+            let i_node = path[i_depth];
+            let i_sibling = if self.nodes[i_node * 3 + 1] == path[i_depth + 1] {
+                self.nodes[i_node * 3 + 2]
+            } else {
+                self.nodes[i_node * 3 + 1]
+            };
+            let size = 1 + size_child + self.subtree_size(i_sibling);
+            if size_child as f64 > Self::ALPHA * size as f64 {
+                let i_node_new = self.rebuild_subtree(i_node, i_depth);
+                if i_depth == 0 {
+                    self.root = i_node_new;
+                } else {
+                    let i_parent = path[i_depth - 1];
+                    let i_side = if self.nodes[i_parent * 3 + 1] == i_node {
+                        1
+                    } else {
+                        2
+                    };
+                    self.nodes[i_parent * 3 + i_side] = i_node_new;
+                }
+                break;
+            }
+            size_child = size;
+        }
+        i_vtx
+    }
+
+    /// delete the point `i_vtx`
+    pub fn delete(&mut self, i_vtx: usize) {
+        assert!(self.vtx2isalive[i_vtx], "the point is already deleted");
+        self.vtx2isalive[i_vtx] = false;
+        self.num_dead += 1;
+        if self.num_dead * 2 > self.num_node() {
+            self.rebuild();
+        }
+    }
+
+    /// move the point `i_vtx` to `xy`. The point gets a new index that is returned
+    pub fn relocate(&mut self, i_vtx: usize, xy: [Real; 2]) -> usize {
+        self.delete(i_vtx);
+        self.insert(xy)
+    }
+
+    #[allow(clippy::identity_op)]
+    fn nearest_in_branch(
+        &self,
+        pos_near: &mut (usize, Real),
+        xy: &[Real; 2],
+        i_node: usize,
+        i_depth: usize,
+    ) {
+        use del_geo_core::vec2::Vec2;
+        if i_node == usize::MAX {
+            return;
+        }
+        let i_vtx = self.nodes[i_node * 3 + 0];
+        let pos = self.xy(i_vtx);
+        if self.vtx2isalive[i_vtx] {
+            let dist = pos.sub(xy).norm();
+            if dist < pos_near.1 {
+                *pos_near = (i_vtx, dist);
+            }
+        }
+        let i_dim = i_depth % 2;
+        let diff = xy[i_dim] - pos[i_dim];
+        let (i_node_near, i_node_far) = if diff < Real::zero() {
+            (self.nodes[i_node * 3 + 1], self.nodes[i_node * 3 + 2])
+        } else {
+            (self.nodes[i_node * 3 + 2], self.nodes[i_node * 3 + 1])
+        };
+        self.nearest_in_branch(pos_near, xy, i_node_near, i_depth + 1);
+        if diff.abs() < pos_near.1 {
+            self.nearest_in_branch(pos_near, xy, i_node_far, i_depth + 1);
+        }
+    }
+
+    /// the nearest point from `xy` as the pair of the point index and the distance
+    pub fn nearest(&self, xy: &[Real; 2]) -> Option<(usize, Real)> {
+        let mut pos_near = (usize::MAX, Real::infinity());
+        self.nearest_in_branch(&mut pos_near, xy, self.root, 0);
+        if pos_near.0 == usize::MAX {
+            None
+        } else {
+            Some(pos_near)
+        }
+    }
+
+    #[allow(clippy::identity_op)]
+    fn inside_circle_in_branch(
+        &self,
+        idx2vtx: &mut Vec<usize>,
+        xy: &[Real; 2],
+        rad: Real,
+        i_node: usize,
+        i_depth: usize,
+    ) {
+        use del_geo_core::vec2::Vec2;
+        if i_node == usize::MAX {
+            return;
+        }
+        let i_vtx = self.nodes[i_node * 3 + 0];
+        let pos = self.xy(i_vtx);
+        if self.vtx2isalive[i_vtx] && pos.sub(xy).norm() < rad {
+            idx2vtx.push(i_vtx);
+        }
+        let i_dim = i_depth % 2;
+        let diff = xy[i_dim] - pos[i_dim];
+        if diff < rad {
+            self.inside_circle_in_branch(idx2vtx, xy, rad, self.nodes[i_node * 3 + 1], i_depth + 1);
+        }
+        if -diff < rad {
+            self.inside_circle_in_branch(idx2vtx, xy, rad, self.nodes[i_node * 3 + 2], i_depth + 1);
+        }
+    }
+
+    /// indices of the points closer than `rad` from `xy`
+    pub fn inside_circle(&self, xy: &[Real; 2], rad: Real) -> Vec<usize> {
+        let mut idx2vtx = vec![];
+        self.inside_circle_in_branch(&mut idx2vtx, xy, rad, self.root, 0);
+        idx2vtx
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use crate::kdtree2::TreeBranch;
@@ -520,4 +837,68 @@ mod tests {
             assert_eq!(idxs1, idxs0);
         }
     }
+
+    fn depth(tree: &crate::kdtree2::DynamicKdTree2<f64>, i_node: usize) -> usize {
+        if i_node == usize::MAX {
+            return 0;
+        }
+        1 + depth(tree, tree.nodes[i_node * 3 + 1]).max(depth(tree, tree.nodes[i_node * 3 + 2]))
+    }
+
+    #[test]
+    fn check_dynamic_kdtree() {
+        use del_geo_core::vec2::Vec2;
+        use rand::Rng;
+        use rand::SeedableRng;
+        let mut reng = rand_chacha::ChaChaRng::seed_from_u64(0);
+        let mut tree = crate::kdtree2::DynamicKdTree2::<f64>::new();
+        assert!(tree.nearest(&[0., 0.]).is_none());
+        // sorted insertion makes an unbalanced tree without the rebuild
+        for i in 0..1000 {
+            let x = i as f64 * 0.001;
+            tree.insert([x, x]);
+        }
+        assert_eq!(tree.num_point(), 1000);
+        assert!(depth(&tree, tree.root) < 40, "{}", depth(&tree, tree.root));
+        for _itr in 0..3000 {
+            if reng.random::<f64>() < 0.4 {
+                let alive: Vec<usize> = (0..tree.vtx2xy.len() / 2)
+                    .filter(|&i_vtx| tree.is_alive(i_vtx))
+                    .collect();
+                if !alive.is_empty() {
+                    tree.delete(alive[reng.random_range(0..alive.len())]);
+                }
+            } else {
+                tree.insert([reng.random(), reng.random()]);
+            }
+        }
+        let alive: Vec<usize> = (0..tree.vtx2xy.len() / 2)
+            .filter(|&i_vtx| tree.is_alive(i_vtx))
+            .collect();
+        assert_eq!(tree.num_point(), alive.len());
+        assert!(depth(&tree, tree.root) < 40, "{}", depth(&tree, tree.root));
+        for _itr in 0..500 {
+            let p0 = [reng.random::<f64>(), reng.random::<f64>()];
+            let (i_vtx, dist) = tree.nearest(&p0).unwrap();
+            assert!(tree.is_alive(i_vtx));
+            for &j_vtx in alive.iter() {
+                let xy = arrayref::array_ref![tree.vtx2xy, j_vtx * 2, 2];
+                assert!(xy.sub(&p0).norm() >= dist);
+            }
+            let rad = 0.05;
+            let mut idxs0 = tree.inside_circle(&p0, rad);
+            idxs0.sort();
+            let idxs1: Vec<usize> = alive
+                .iter()
+                .filter(|&&j_vtx| {
+                    arrayref::array_ref![tree.vtx2xy, j_vtx * 2, 2]
+                        .sub(&p0)
+                        .norm()
+                        < rad
+                })
+                .cloned()
+                .collect();
+            assert_eq!(idxs0, idxs1);
+        }
+    }
 }
@@ -15,6 +15,7 @@ pub mod polyloop;
 pub mod polyloop2;
 pub mod polyloop3;
 pub mod quadmesh;
+pub mod tetmesh;
 pub mod trimesh2;
 pub mod trimesh3;
 pub mod trimesh3_primitive;
@@ -32,6 +33,7 @@ pub mod extract;
 pub mod unindex;
 
 // io
+pub mod io_bvh;
 pub mod io_nas;
 pub mod io_obj;
 pub mod io_off;
@@ -40,14 +42,21 @@ pub mod io_svg;
 pub mod io_vtk;
 
 // search
+pub mod bvh3_flat;
 pub mod bvhnode2aabb2;
 pub mod bvhnode2aabb3;
 pub mod bvhnodes;
 pub mod bvhnodes_morton;
+pub mod bvhnodes_sah;
 pub mod bvhnodes_topdown_trimesh3;
+pub mod kdtree;
 pub mod kdtree2;
 pub mod search_bvh2;
 pub mod search_bvh3;
+pub mod search_bvh3_packet;
+pub mod search_bvh3_pair;
+pub mod search_bvh3_primitive;
+pub mod uniform_hash_grid;
 
 // self intersection
 pub mod trimesh3_intersection;
@@ -58,9 +67,13 @@ pub mod trimesh3_proximity;
 // misc
 pub mod convexhull2;
 pub mod convexhull2_intersection;
+pub mod convexhull3;
+pub mod convexhull3_intersection;
 pub mod cumsum;
 pub mod polygon_mesh;
+pub mod tetmesh_delaunay;
 pub mod trimesh;
+pub mod trimesh2_constrained_delaunay;
 pub mod trimesh2_dynamic;
 pub mod trimesh_topology;
 pub mod uniform_mesh;
@@ -383,6 +383,106 @@ pub fn contacting_pair(poly2vtx: &[usize], vtx2xyz: &[f32], dist0: f32) -> (Vec<
     (pair_idx, pair_prm)
 }
 
+/// same as [`contacting_pair`] but using a BVH of the segments, whose AABBs are inflated by `dist0`.
+/// The order of the output pairs may differ from [`contacting_pair`]
+pub fn contacting_pair_with_bvh(
+    poly2vtx: &[usize],
+    vtx2xyz: &[f32],
+    dist0: f32,
+) -> (Vec<usize>, Vec<f32>) {
+    let num_poly = poly2vtx.len() - 1;
+    let mut seg2vtx: Vec<usize> = vec![];
+    let mut seg2poly: Vec<usize> = vec![];
+    for i_poly in 0..num_poly {
+        for i_seg in poly2vtx[i_poly]..poly2vtx[i_poly + 1] - 1 {
+            seg2vtx.extend([i_seg, i_seg + 1]);
+            seg2poly.push(i_poly);
+        }
+    }
+    let mut pair_idx = Vec::<usize>::new();
+    let mut pair_prm = Vec::<f32>::new();
+    if seg2poly.len() < 2 {
+        return (pair_idx, pair_prm);
+    }
+    let seg2cntr = crate::elem2center::from_uniform_mesh_as_points(&seg2vtx, 2, vtx2xyz, 3);
+    let bvhnodes = crate::bvhnodes_morton::from_vtx2xyz::<usize>(&seg2cntr, 3);
+    let bvhnode2aabb = crate::bvhnode2aabb3::from_uniform_mesh_with_bvh::<usize, f32>(
+        0,
+        &bvhnodes,
+        Some((&seg2vtx, 2)),
+        vtx2xyz,
+        None,
+    );
+    crate::search_bvh3_pair::overlapping_leaves_inside_branch(
+        &mut |k_seg, l_seg| {
+            let (k_seg, l_seg) = if seg2poly[k_seg] < seg2poly[l_seg] {
+                (k_seg, l_seg)
+            } else {
+                (l_seg, k_seg)
+            };
+            let (i_poly, j_poly) = (seg2poly[k_seg], seg2poly[l_seg]);
+            if i_poly == j_poly {
+                return;
+            }
+            let (i_seg, j_seg) = (seg2vtx[k_seg * 2], seg2vtx[l_seg * 2]);
+            let pi = crate::vtx2xyz::to_vec3(vtx2xyz, i_seg);
+            let qi = crate::vtx2xyz::to_vec3(vtx2xyz, i_seg + 1);
+            let pj = crate::vtx2xyz::to_vec3(vtx2xyz, j_seg);
+            let qj = crate::vtx2xyz::to_vec3(vtx2xyz, j_seg + 1);
+            let (dist, ri, rj) = del_geo_core::edge3::nearest_to_edge3(pi, qi, pj, qj);
+            if dist > dist0 {
+                return;
+            }
+            pair_idx.extend([i_poly, j_poly]);
+            pair_prm.push((i_seg - poly2vtx[i_poly]) as f32 + ri);
+            pair_prm.push((j_seg - poly2vtx[j_poly]) as f32 + rj);
+        },
+        &crate::search_bvh3_pair::BvhWithTransform {
+            bvhnodes: &bvhnodes,
+            bvhnode2aabb: &bvhnode2aabb,
+            transform: None,
+        },
+        0,
+        dist0,
+    );
+    (pair_idx, pair_prm)
+}
+
+#[test]
+fn test_contacting_pair_with_bvh() {
+    use rand::Rng;
+    use rand::SeedableRng;
+    let mut reng = rand_chacha::ChaChaRng::seed_from_u64(0);
+    // random walks
+    let mut poly2vtx = vec![0usize];
+    let mut vtx2xyz: Vec<f32> = vec![];
+    for _i_poly in 0..10 {
+        let mut p: [f32; 3] = std::array::from_fn(|_| reng.random::<f32>());
+        for _i_vtx in 0..30 {
+            vtx2xyz.extend(p);
+            p = std::array::from_fn(|i| p[i] + (reng.random::<f32>() - 0.5) * 0.1);
+        }
+        poly2vtx.push(vtx2xyz.len() / 3);
+    }
+    let dist0 = 0.05;
+    let (pair_idx0, pair_prm0) = contacting_pair(&poly2vtx, &vtx2xyz, dist0);
+    let (pair_idx1, pair_prm1) = contacting_pair_with_bvh(&poly2vtx, &vtx2xyz, dist0);
+    assert!(!pair_idx0.is_empty());
+    let to_sorted = |pair_idx: &[usize], pair_prm: &[f32]| {
+        let mut a: Vec<_> = pair_idx
+            .chunks(2)
+            .zip(pair_prm.chunks(2))
+            .map(|(i, r)| (i[0], i[1], r[0], r[1]))
+            .collect();
+        a.sort_by(|x, y| x.partial_cmp(y).unwrap());
+        a
+    };
+    assert_eq!(
+        to_sorted(&pair_idx0, &pair_prm0),
+        to_sorted(&pair_idx1, &pair_prm1)
+    );
+}
+
 pub fn position_from_barycentric_coordinate<T>(vtx2xyz: &[T], r: T) -> [T; 3]
 where
     T: num_traits::Float + num_traits::AsPrimitive<usize>,
@@ -418,33 +418,24 @@ pub fn poisson_disk_sampling<RNG>(
 where
     RNG: rand::Rng,
 {
-    use del_geo_core::vec2::Vec2;
     let (tri2vtx, vtx2xyz) =
         crate::trimesh2_dynamic::meshing_from_polyloop2::<usize, f32>(vtxl2xy, -1., -1.);
     let tri2cumarea = crate::trimesh::tri2cumsumarea(&tri2vtx, &vtx2xyz, 2);
-    let mut vtx2vectwo: Vec<[f32; 2]> = vec![];
+    let mut tree = crate::kdtree2::DynamicKdTree2::<f32>::new();
     for _iter in 0..num_iteration {
         let (i_tri, r0, r1) =
             crate::trimesh::sample_uniformly(&tri2cumarea, reng.random(), reng.random());
         let pos = crate::trimesh::position_from_barycentric_coordinate::<f32, 2>(
             &tri2vtx, &vtx2xyz, i_tri, r0, r1,
         );
-        let mut is_near = false;
-        for pos0 in &vtx2vectwo {
-            // TODO: use kd-tree to accelerate this process
-            if pos0.sub(&pos).norm() > radius {
+        if let Some((_, dist)) = tree.nearest(&pos) {
+            if dist <= radius {
                 continue;
             }
-            is_near = true;
-            break;
         }
-        if is_near {
-            continue;
-        }
-        vtx2vectwo.push(pos);
+        tree.insert(pos);
     }
-    use slice_of_array::SliceFlatExt;
-    vtx2vectwo.flat().to_vec()
+    tree.vtx2xy
 }
 
 #[test]
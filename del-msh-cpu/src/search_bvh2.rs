@@ -47,3 +47,379 @@ pub fn including_point<Real, Index>(
         aabbs,
     );
 }
+
+/// distance from `point` to the element. Zero if a triangle includes `point`.
+/// The element is an edge if `num_node == 2` and a triangle if `num_node == 3`
+fn distance_element_to_point<Real, Index>(
+    elem2vtx: &[Index],
+    num_node: usize,
+    vtx2xy: &[Real],
+    i_elem: usize,
+    point: &[Real; 2],
+) -> Real
+where
+    Real: num_traits::Float,
+    Index: AsPrimitive<usize>,
+{
+    use del_geo_core::vec2::Vec2;
+    let node2xy: Vec<&[Real; 2]> = elem2vtx[i_elem * num_node..(i_elem + 1) * num_node]
+        .iter()
+        .map(|&i_vtx| arrayref::array_ref![vtx2xy, i_vtx.as_() * 2, 2])
+        .collect();
+    if num_node == 3
+        && del_geo_core::tri2::is_inside(node2xy[0], node2xy[1], node2xy[2], point, Real::one())
+            .is_some()
+    {
+        return Real::zero();
+    }
+    let num_edge = if num_node == 2 { 1 } else { num_node };
+    (0..num_edge)
+        .map(|i_edge| {
+            let (p0, p1) = (node2xy[i_edge], node2xy[(i_edge + 1) % num_node]);
+            let (_r, pn) = del_geo_core::edge2::nearest_to_point(p0, p1, point);
+            pn.sub(point).norm()
+        })
+        .fold(Real::infinity(), |a, b| a.min(b))
+}
+
+/// the element nearest to `point` and its distance
+/// * `nearest` - the nearest element found so far. Initialize with `None`
+/// * `num_node` - 2 for polylines (`elem2vtx` is `edge2vtx`) and 3 for triangle meshes
+#[allow(clippy::too_many_arguments)]
+pub fn nearest_to_point<Real, Index>(
+    nearest: &mut Option<(Index, Real)>,
+    elem2vtx: &[Index],
+    num_node: usize,
+    vtx2xy: &[Real],
+    point: &[Real; 2],
+    i_bvhnode: usize,
+    bvhnodes: &[Index],
+    aabbs: &[Real],
+) where
+    Real: num_traits::Float,
+    Index: AsPrimitive<usize> + num_traits::PrimInt,
+    usize: AsPrimitive<Index>,
+{
+    assert_eq!(bvhnodes.len() / 3, aabbs.len() / 4);
+    let dist_aabb = del_geo_core::aabb2::sdf(arrayref::array_ref![aabbs, i_bvhnode * 4, 4], point)
+        .max(Real::zero());
+    if let Some((_, dist_min)) = nearest {
+        if dist_aabb > *dist_min {
+            return;
+        }
+    }
+    if bvhnodes[i_bvhnode * 3 + 2] == Index::max_value() {
+        // leaf node
+        let i_elem: usize = bvhnodes[i_bvhnode * 3 + 1].as_();
+        let dist = distance_element_to_point(elem2vtx, num_node, vtx2xy, i_elem, point);
+        match nearest {
+            Some((_, dist_min)) if *dist_min <= dist => {}
+            _ => *nearest = Some((i_elem.as_(), dist)),
+        }
+        return;
+    }
+    // visit the closer child first
+    let ichild0: usize = bvhnodes[i_bvhnode * 3 + 1].as_();
+    let ichild1: usize = bvhnodes[i_bvhnode * 3 + 2].as_();
+    let d0 = del_geo_core::aabb2::sdf(arrayref::array_ref![aabbs, ichild0 * 4, 4], point);
+    let d1 = del_geo_core::aabb2::sdf(arrayref::array_ref![aabbs, ichild1 * 4, 4], point);
+    let (ichild0, ichild1) = if d0 <= d1 {
+        (ichild0, ichild1)
+    } else {
+        (ichild1, ichild0)
+    };
+    nearest_to_point(
+        nearest, elem2vtx, num_node, vtx2xy, point, ichild0, bvhnodes, aabbs,
+    );
+    nearest_to_point(
+        nearest, elem2vtx, num_node, vtx2xy, point, ichild1, bvhnodes, aabbs,
+    );
+}
+
+/// elements whose AABBs intersect the box `aabb` (`[min_x, min_y, max_x, max_y]`)
+pub fn elements_intersecting_aabb<Real, Index>(
+    hits: &mut Vec<Index>,
+    aabb: &[Real; 4],
+    i_bvhnode: usize,
+    bvhnodes: &[Index],
+    aabbs: &[Real],
+) where
+    Real: num_traits::Float,
+    Index: AsPrimitive<usize> + num_traits::PrimInt,
+{
+    assert_eq!(bvhnodes.len() / 3, aabbs.len() / 4);
+    let aabb_node = arrayref::array_ref![aabbs, i_bvhnode * 4, 4];
+    if aabb_node[0] > aabb[2]
+        || aabb[0] > aabb_node[2]
+        || aabb_node[1] > aabb[3]
+        || aabb[1] > aabb_node[3]
+    {
+        return;
+    }
+    if bvhnodes[i_bvhnode * 3 + 2] == Index::max_value() {
+        // leaf node
+        hits.push(bvhnodes[i_bvhnode * 3 + 1]);
+        return;
+    }
+    elements_intersecting_aabb(
+        hits,
+        aabb,
+        bvhnodes[i_bvhnode * 3 + 1].as_(),
+        bvhnodes,
+        aabbs,
+    );
+    elements_intersecting_aabb(
+        hits,
+        aabb,
+        bvhnodes[i_bvhnode * 3 + 2].as_(),
+        bvhnodes,
+        aabbs,
+    );
+}
+
+/// range of the parameter `t` where `ray_org + t * ray_dir` is inside the AABB
+fn range_ray_in_aabb<Real>(
+    aabb: &[Real; 4],
+    ray_org: &[Real; 2],
+    ray_dir: &[Real; 2],
+) -> Option<(Real, Real)>
+where
+    Real: num_traits::Float,
+{
+    let mut tmin = Real::neg_infinity();
+    let mut tmax = Real::infinity();
+    for i_dim in 0..2 {
+        if ray_dir[i_dim] == Real::zero() {
+            if ray_org[i_dim] < aabb[i_dim] || ray_org[i_dim] > aabb[i_dim + 2] {
+                return None;
+            }
+            continue;
+        }
+        let t0 = (aabb[i_dim] - ray_org[i_dim]) / ray_dir[i_dim];
+        let t1 = (aabb[i_dim + 2] - ray_org[i_dim]) / ray_dir[i_dim];
+        tmin = tmin.max(t0.min(t1));
+        tmax = tmax.min(t0.max(t1));
+    }
+    if tmin > tmax {
+        return None;
+    }
+    Some((tmin, tmax))
+}
+
+/// the smallest parameter `t` in `[0, t_max]` where `ray_org + t * ray_dir` is on the element.
+/// The element is an edge if `num_node == 2` and a triangle if `num_node == 3`
+#[allow(clippy::too_many_arguments)]
+fn intersection_ray_element<Real, Index>(
+    elem2vtx: &[Index],
+    num_node: usize,
+    vtx2xy: &[Real],
+    i_elem: usize,
+    ray_org: &[Real; 2],
+    ray_dir: &[Real; 2],
+    t_max: Real,
+) -> Option<Real>
+where
+    Real: num_traits::Float,
+    Index: AsPrimitive<usize>,
+{
+    use del_geo_core::vec2::Vec2;
+    let node2xy: Vec<&[Real; 2]> = elem2vtx[i_elem * num_node..(i_elem + 1) * num_node]
+        .iter()
+        .map(|&i_vtx| arrayref::array_ref![vtx2xy, i_vtx.as_() * 2, 2])
+        .collect();
+    let cross = |a: &[Real; 2], b: &[Real; 2]| a[0] * b[1] - a[1] * b[0];
+    if num_node == 2 {
+        let e = node2xy[1].sub(node2xy[0]);
+        let w = node2xy[0].sub(ray_org);
+        let det = cross(ray_dir, &e);
+        if det == Real::zero() {
+            return None; // parallel
+        }
+        let t = cross(&w, &e) / det;
+        let s = cross(&w, ray_dir) / det;
+        if t < Real::zero() || t > t_max || s < Real::zero() || s > Real::one() {
+            return None;
+        }
+        return Some(t);
+    }
+    // clip the ray by the half planes of the edges of the triangle
+    let sign = if cross(&node2xy[1].sub(node2xy[0]), &node2xy[2].sub(node2xy[0])) > Real::zero() {
+        Real::one()
+    } else {
+        -Real::one()
+    };
+    let (mut t0, mut t1) = (Real::zero(), t_max);
+    for i_edge in 0..3 {
+        let p0 = node2xy[i_edge];
+        let e = node2xy[(i_edge + 1) % 3].sub(p0);
+        // the inside is where `sign * cross(e, x - p0) >= 0`
+        let c0 = sign * cross(&e, &ray_org.sub(p0));
+        let c1 = sign * cross(&e, ray_dir);
+        if c1 == Real::zero() {
+            if c0 < Real::zero() {
+                return None;
+            }
+            continue;
+        }
+        let t = -c0 / c1;
+        if c1 > Real::zero() {
+            t0 = t0.max(t);
+        } else {
+            t1 = t1.min(t);
+        }
+    }
+    if t0 > t1 {
+        return None;
+    }
+    Some(t0)
+}
+
+/// elements intersecting the ray `ray_org + t * ray_dir` (`0 <= t <= t_max`) and the smallest `t` on them.
+/// Set `t_max = 1` for the segment from `ray_org` to `ray_org + ray_dir`,
+/// and `t_max = Real::infinity()` for the ray
+/// * `num_node` - 2 for polylines (`elem2vtx` is `edge2vtx`) and 3 for triangle meshes
+#[allow(clippy::too_many_arguments)]
+pub fn intersections_against_ray<Real, Index>(
+    hits: &mut Vec<(Index, Real)>,
+    elem2vtx: &[Index],
+    num_node: usize,
+    vtx2xy: &[Real],
+    ray_org: &[Real; 2],
+    ray_dir: &[Real; 2],
+    t_max: Real,
+    i_bvhnode: usize,
+    bvhnodes: &[Index],
+    aabbs: &[Real],
+) where
+    Real: num_traits::Float,
+    Index: AsPrimitive<usize> + num_traits::PrimInt,
+    usize: AsPrimitive<Index>,
+{
+    assert_eq!(bvhnodes.len() / 3, aabbs.len() / 4);
+    let Some((t0, t1)) = range_ray_in_aabb(
+        arrayref::array_ref![aabbs, i_bvhnode * 4, 4],
+        ray_org,
+        ray_dir,
+    ) else {
+        return;
+    };
+    if t1 < Real::zero() || t0 > t_max {
+        return;
+    }
+    if bvhnodes[i_bvhnode * 3 + 2] == Index::max_value() {
+        // leaf node
+        let i_elem: usize = bvhnodes[i_bvhnode * 3 + 1].as_();
+        if let Some(t) =
+            intersection_ray_element(elem2vtx, num_node, vtx2xy, i_elem, ray_org, ray_dir, t_max)
+        {
+            hits.push((i_elem.as_(), t));
+        }
+        return;
+    }
+    for i_child in 1..3 {
+        intersections_against_ray(
+            hits,
+            elem2vtx,
+            num_node,
+            vtx2xy,
+            ray_org,
+            ray_dir,
+            t_max,
+            bvhnodes[i_bvhnode * 3 + i_child].as_(),
+            bvhnodes,
+            aabbs,
+        );
+    }
+}
+
+#[test]
+fn test_nearest_range_ray() {
+    use rand::Rng;
+    use rand::SeedableRng;
+    let mut reng = rand_chacha::ChaChaRng::seed_from_u64(0);
+    let (tri2vtx, vtx2xy) = crate::trimesh2::from_circle(1.0, 32);
+    let edge2vtx: Vec<usize> = {
+        // a random polyline
+        let num_vtx = vtx2xy.len() / 2;
+        (0..100)
+            .flat_map(|_| {
+                let i0 = reng.random_range(0..num_vtx);
+                let i1 = (i0 + reng.random_range(1..num_vtx)) % num_vtx;
+                [i0, i1]
+            })
+            .collect()
+    };
+    for (elem2vtx, num_node) in [(&tri2vtx, 3), (&edge2vtx, 2)] {
+        let num_elem = elem2vtx.len() / num_node;
+        let elem2cntr =
+            crate::elem2center::from_uniform_mesh_as_points(elem2vtx, num_node, &vtx2xy, 2);
+        let bvhnodes = crate::bvhnodes_morton::from_vtx2xyz::<usize>(&elem2cntr, 2);
+        let aabbs = crate::bvhnode2aabb2::from_uniform_mesh_with_bvh::<usize, f32>(
+            0,
+            &bvhnodes,
+            Some((elem2vtx, num_node)),
+            &vtx2xy,
+            None,
+        );
+        for _itr in 0..100 {
+            let p: [f32; 2] = std::array::from_fn(|_| reng.random::<f32>() * 3.0 - 1.5);
+            // nearest
+            let mut nearest = None;
+            nearest_to_point(
+                &mut nearest,
+                elem2vtx,
+                num_node,
+                &vtx2xy,
+                &p,
+                0,
+                &bvhnodes,
+                &aabbs,
+            );
+            let (_i_elem, dist) = nearest.unwrap();
+            let dist_bf = (0..num_elem)
+                .map(|i_elem| distance_element_to_point(elem2vtx, num_node, &vtx2xy, i_elem, &p))
+                .fold(f32::INFINITY, |a, b| a.min(b));
+            assert!((dist - dist_bf).abs() < 1.0e-6, "{dist} {dist_bf}");
+            // box range
+            let aabb = [p[0] - 0.2, p[1] - 0.1, p[0] + 0.1, p[1] + 0.2];
+            let mut hits = vec![];
+            elements_intersecting_aabb(&mut hits, &aabb, 0, &bvhnodes, &aabbs);
+            hits.sort();
+            let hits_bf: Vec<usize> = (0..num_elem)
+                .filter(|&i_elem| {
+                    let a = crate::vtx2xy::aabb2_indexed(
+                        &elem2vtx[i_elem * num_node..(i_elem + 1) * num_node],
+                        &vtx2xy,
+                        0f32,
+                    );
+                    a[0] <= aabb[2] && aabb[0] <= a[2] && a[1] <= aabb[3] && aabb[1] <= a[3]
+                })
+                .collect();
+            assert_eq!(hits, hits_bf);
+            // segment and ray
+            let dir: [f32; 2] = std::array::from_fn(|_| reng.random::<f32>() * 2.0 - 1.0);
+            for t_max in [1f32, f32::INFINITY] {
+                let mut hits = vec![];
+                intersections_against_ray(
+                    &mut hits, elem2vtx, num_node, &vtx2xy, &p, &dir, t_max, 0, &bvhnodes, &aabbs,
+                );
+                hits.sort_by_key(|a| a.0);
+                let hits_bf: Vec<(usize, f32)> = (0..num_elem)
+                    .filter_map(|i_elem| {
+                        intersection_ray_element(
+                            elem2vtx, num_node, &vtx2xy, i_elem, &p, &dir, t_max,
+                        )
+                        .map(|t| (i_elem, t))
+                    })
+                    .collect();
+                assert_eq!(hits, hits_bf);
+                for &(i_elem, t) in &hits {
+                    // the hit point is on the element
+                    let q = [p[0] + dir[0] * t, p[1] + dir[1] * t];
+                    let d = distance_element_to_point(elem2vtx, num_node, &vtx2xy, i_elem, &q);
+                    assert!(d < 1.0e-4, "{d}");
+                }
+            }
+        }
+    }
+}
@@ -213,6 +213,158 @@ where
     }
 }
 
+/// intersection of a ray and a triangle reported to the callbacks
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RayHit {
+    /// ray parameter of the hit point `ray_org + t * ray_dir`
+    pub t: f32,
+    pub i_tri: usize,
+    /// barycentric coordinates of the hit point in the triangle
+    pub bary: [f32; 3],
+}
+
+fn ray_hit_triangle<Index>(
+    ray_org: &[f32; 3],
+    ray_dir: &[f32; 3],
+    t_max: f32,
+    trimesh3: &TriMeshWithBvh<Index>,
+    i_tri: usize,
+) -> Option<RayHit>
+where
+    Index: AsPrimitive<usize>,
+{
+    let tri = crate::trimesh3::to_tri3(trimesh3.tri2vtx, trimesh3.vtx2xyz, i_tri);
+    let t = tri
+        .intersection_against_ray(ray_org, ray_dir)
+        .filter(|&t| t <= t_max)?;
+    let q = del_geo_core::vec3::axpy(t, ray_dir, ray_org);
+    let bary = del_geo_core::tri3::to_barycentric_coords(tri.p0, tri.p1, tri.p2, &q);
+    Some(RayHit { t, i_tri, bary })
+}
+
+/// call `callback` for each hit of the ray with `0 <= t <= t_max` in no particular order.
+/// The traversal stops when `callback` returns `ControlFlow::Break`
+pub fn intersections_ray_with_callback<Index, F>(
+    ray_org: &[f32; 3],
+    ray_dir: &[f32; 3],
+    t_max: f32,
+    trimesh3: &TriMeshWithBvh<Index>,
+    i_bvhnode: usize,
+    callback: &mut F,
+) -> std::ops::ControlFlow<()>
+where
+    Index: PrimInt + AsPrimitive<usize>,
+    F: FnMut(&RayHit) -> std::ops::ControlFlow<()>,
+{
+    use std::ops::ControlFlow;
+    assert_eq!(trimesh3.bvhnodes.len() / 3, trimesh3.bvhnode2aabb.len() / 6);
+    match del_geo_core::aabb3::from_aabbs(trimesh3.bvhnode2aabb, i_bvhnode)
+        .intersections_against_ray(ray_org, ray_dir)
+    {
+        Some((t0, _t1)) if t0 <= t_max => {}
+        _ => return ControlFlow::Continue(()),
+    }
+    if trimesh3.bvhnodes[i_bvhnode * 3 + 2] == Index::max_value() {
+        // leaf node
+        let i_tri: usize = trimesh3.bvhnodes[i_bvhnode * 3 + 1].as_();
+        return match ray_hit_triangle(ray_org, ray_dir, t_max, trimesh3, i_tri) {
+            Some(hit) => callback(&hit),
+            None => ControlFlow::Continue(()),
+        };
+    }
+    for i_child in 1..3 {
+        intersections_ray_with_callback(
+            ray_org,
+            ray_dir,
+            t_max,
+            trimesh3,
+            trimesh3.bvhnodes[i_bvhnode * 3 + i_child].as_(),
+            callback,
+        )?;
+    }
+    ControlFlow::Continue(())
+}
+
+/// any hit of the ray with `0 <= t <= t_max` accepted by `filter` (e.g., for shadow rays).
+/// The traversal terminates at the first accepted hit
+pub fn any_intersection_ray<Index, F>(
+    ray_org: &[f32; 3],
+    ray_dir: &[f32; 3],
+    t_max: f32,
+    trimesh3: &TriMeshWithBvh<Index>,
+    i_bvhnode: usize,
+    mut filter: F,
+) -> Option<RayHit>
+where
+    Index: PrimInt + AsPrimitive<usize>,
+    F: FnMut(&RayHit) -> bool,
+{
+    use std::ops::ControlFlow;
+    let mut res = None;
+    let _ = intersections_ray_with_callback(
+        ray_org,
+        ray_dir,
+        t_max,
+        trimesh3,
+        i_bvhnode,
+        &mut |hit: &RayHit| {
+            if filter(hit) {
+                res = Some(*hit);
+                return ControlFlow::Break(());
+            }
+            ControlFlow::Continue(())
+        },
+    );
+    res
+}
+
+/// the nearest hit of the ray with `0 <= t <= t_max` accepted by `filter`
+/// (e.g., ignoring the back faces or the triangles in specific groups)
+pub fn first_intersection_ray_with_filter<Index, F>(
+    ray_org: &[f32; 3],
+    ray_dir: &[f32; 3],
+    t_max: f32,
+    trimesh3: &TriMeshWithBvh<Index>,
+    i_bvhnode: usize,
+    filter: &mut F,
+) -> Option<RayHit>
+where
+    Index: PrimInt + AsPrimitive<usize>,
+    F: FnMut(&RayHit) -> bool,
+{
+    assert_eq!(trimesh3.bvhnodes.len() / 3, trimesh3.bvhnode2aabb.len() / 6);
+    match del_geo_core::aabb3::from_aabbs(trimesh3.bvhnode2aabb, i_bvhnode)
+        .intersections_against_ray(ray_org, ray_dir)
+    {
+        Some((t0, _t1)) if t0 <= t_max => {}
+        _ => return None,
+    }
+    if trimesh3.bvhnodes[i_bvhnode * 3 + 2] == Index::max_value() {
+        // leaf node
+        let i_tri: usize = trimesh3.bvhnodes[i_bvhnode * 3 + 1].as_();
+        return ray_hit_triangle(ray_org, ray_dir, t_max, trimesh3, i_tri)
+            .filter(|hit| filter(hit));
+    }
+    let mut res = None;
+    let mut t_max = t_max;
+    for i_child in 1..3 {
+        let hit = first_intersection_ray_with_filter(
+            ray_org,
+            ray_dir,
+            t_max,
+            trimesh3,
+            trimesh3.bvhnodes[i_bvhnode * 3 + i_child].as_(),
+            filter,
+        );
+        if let Some(hit) = hit {
+            // the second child is searched only before this hit
+            t_max = hit.t;
+            res = Some(hit);
+        }
+    }
+    res
+}
+
 /*
 /// check if a point alone ray_dir is closer than an aabb
 fn is_point_closer(aabb: &[f32; 6], ray_dir: &[f32; 3], t: f32) -> bool {
@@ -300,3 +452,94 @@ fn test_first_intersection_ray() {
         }
     }
 }
+
+#[test]
+fn test_intersections_ray_with_callback() {
+    use std::ops::ControlFlow;
+    let (tri2vtx, vtx2xyz) = crate::trimesh3_primitive::sphere_yup::<usize, f32>(1.0, 32, 32);
+    let bvhnodes = crate::bvhnodes_morton::from_triangle_mesh(&tri2vtx, &vtx2xyz, 3);
+    let bvhnode2aabb = crate::bvhnode2aabb3::from_uniform_mesh_with_bvh(
+        0,
+        &bvhnodes,
+        Some((&tri2vtx, 3)),
+        &vtx2xyz,
+        None,
+    );
+    let trimesh3 = TriMeshWithBvh {
+        tri2vtx: &tri2vtx,
+        vtx2xyz: &vtx2xyz,
+        bvhnodes: &bvhnodes,
+        bvhnode2aabb: &bvhnode2aabb,
+    };
+    use rand::Rng;
+    use rand::SeedableRng;
+    let mut reng = rand_chacha::ChaChaRng::seed_from_u64(0u64);
+    for _iter in 0..300 {
+        // inside the silhouette of the sphere
+        let theta = reng.random::<f32>() * std::f32::consts::PI * 2.0;
+        let r = reng.random::<f32>() * 0.7;
+        let ray_org = [r * theta.cos(), r * theta.sin(), 2.0];
+        let ray_dir = [0., 0., -1.];
+        // all hits
+        let mut hits0 = vec![];
+        intersections_ray(&mut hits0, &ray_org, &ray_dir, &trimesh3, 0);
+        let mut hits1 = vec![];
+        let _ = intersections_ray_with_callback(
+            &ray_org,
+            &ray_dir,
+            f32::INFINITY,
+            &trimesh3,
+            0,
+            &mut |hit: &RayHit| {
+                let tri = crate::trimesh3::to_tri3(&tri2vtx, &vtx2xyz, hit.i_tri);
+                let q0 = del_geo_core::vec3::axpy(hit.t, &ray_dir, &ray_org);
+                let q1: [f32; 3] = std::array::from_fn(|i| {
+                    tri.p0[i] * hit.bary[0] + tri.p1[i] * hit.bary[1] + tri.p2[i] * hit.bary[2]
+                });
+                assert!(del_geo_core::edge3::length(&q0, &q1) < 1.0e-5);
+                hits1.push((hit.t, hit.i_tri));
+                ControlFlow::Continue(())
+            },
+        );
+        assert_eq!(hits0, hits1);
+        assert_eq!(hits0.len(), 2);
+        // nearest front face
+        let mut is_front = |hit: &RayHit| {
+            let nrm = crate::trimesh3::to_tri3(&tri2vtx, &vtx2xyz, hit.i_tri).normal();
+            del_geo_core::vec3::dot(&nrm, &ray_dir) < 0.
+        };
+        let hit_front = first_intersection_ray_with_filter(
+            &ray_org,
+            &ray_dir,
+            f32::INFINITY,
+            &trimesh3,
+            0,
+            &mut is_front,
+        )
+        .unwrap();
+        let t_near = hits0[0].0.min(hits0[1].0);
+        assert_eq!(hit_front.t, t_near);
+        // nearest back face
+        let hit_back = first_intersection_ray_with_filter(
+            &ray_org,
+            &ray_dir,
+            f32::INFINITY,
+            &trimesh3,
+            0,
+            &mut |hit: &RayHit| !is_front(hit),
+        )
+        .unwrap();
+        assert_eq!(hit_back.t, hits0[0].0.max(hits0[1].0));
+        // shadow ray terminates at the first hit
+        let mut num_call = 0;
+        let hit_any = any_intersection_ray(&ray_org, &ray_dir, f32::INFINITY, &trimesh3, 0, |_| {
+            num_call += 1;
+            true
+        });
+        assert!(hit_any.is_some());
+        assert_eq!(num_call, 1);
+        // ray segment not reaching the sphere
+        let hit_any = any_intersection_ray(&ray_org, &ray_dir, 0.5, &trimesh3, 0, |_| true);
+        assert!(hit_any.is_none());
+    }
+}
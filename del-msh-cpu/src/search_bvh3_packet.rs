@@ -0,0 +1,216 @@
+//! ray-packet traversal of 3D BVH. A packet of `PACKET_SIZE` rays traverses the tree together
+//! and the AABBs and the triangles are tested against all the rays in the packet at once.
+//! The rays in a packet should be coherent (e.g., neighboring pixels) to be efficient
+//!
+//! The tests are plain loops over the lanes without explicit SIMD instructions.
+//! The AABB test is written in the structure-of-arrays layout so that the compiler can vectorize it,
+//! while the triangle test calls the same routine as `search_bvh3::first_intersection_ray` for each lane
+//! so that the parameter of a hit is bit-identical to the single-ray traversal
+
+use num_traits::AsPrimitive;
+
+pub const PACKET_SIZE: usize = 8;
+
+type Lanes<T> = [T; PACKET_SIZE];
+
+struct RayPacket {
+    org: [Lanes<f32>; 3],
+    dir: [Lanes<f32>; 3],
+    dir_inv: [Lanes<f32>; 3],
+    /// the parameter of the closest hit. `-inf` for the unused lanes
+    t_max: Lanes<f32>,
+    i_tri: Lanes<usize>,
+}
+
+impl RayPacket {
+    fn new(ray2org: &[f32], ray2dir: &[f32], t_max: f32) -> Self {
+        let num_ray = ray2org.len() / 3;
+        assert!(num_ray <= PACKET_SIZE);
+        let mut p = RayPacket {
+            org: [[0f32; PACKET_SIZE]; 3],
+            dir: [[1f32; PACKET_SIZE]; 3],
+            dir_inv: [[1f32; PACKET_SIZE]; 3],
+            t_max: [f32::NEG_INFINITY; PACKET_SIZE],
+            i_tri: [usize::MAX; PACKET_SIZE],
+        };
+        for i_ray in 0..num_ray {
+            for i_dim in 0..3 {
+                p.org[i_dim][i_ray] = ray2org[i_ray * 3 + i_dim];
+                p.dir[i_dim][i_ray] = ray2dir[i_ray * 3 + i_dim];
+                p.dir_inv[i_dim][i_ray] = 1f32 / ray2dir[i_ray * 3 + i_dim];
+            }
+            p.t_max[i_ray] = t_max;
+        }
+        p
+    }
+
+    /// if any of the rays hits the AABB before its current closest hit
+    fn is_hit_aabb(&self, aabb: &[f32]) -> bool {
+        let mut t0 = [0f32; PACKET_SIZE];
+        let mut t1 = self.t_max;
+        for i_dim in 0..3 {
+            for i in 0..PACKET_SIZE {
+                let s0 = (aabb[i_dim] - self.org[i_dim][i]) * self.dir_inv[i_dim][i];
+                let s1 = (aabb[i_dim + 3] - self.org[i_dim][i]) * self.dir_inv[i_dim][i];
+                t0[i] = t0[i].max(s0.min(s1));
+                t1[i] = t1[i].min(s0.max(s1));
+            }
+        }
+        (0..PACKET_SIZE).any(|i| t0[i] <= t1[i])
+    }
+
+    /// update the closest hits with the triangle
+    fn intersect_triangle(&mut self, tri: &del_geo_core::tri3::Tri3<f32>, i_tri: usize) {
+        for i in 0..PACKET_SIZE {
+            if self.t_max[i] == f32::NEG_INFINITY {
+                continue; // unused lane
+            }
+            let org = [self.org[0][i], self.org[1][i], self.org[2][i]];
+            let dir = [self.dir[0][i], self.dir[1][i], self.dir[2][i]];
+            if let Some(t) = tri.intersection_against_ray(&org, &dir) {
+                if t < self.t_max[i] {
+                    self.t_max[i] = t;
+                    self.i_tri[i] = i_tri;
+                }
+            }
+        }
+    }
+}
+
+/// the closest hits of a packet of at most `PACKET_SIZE` rays
+fn first_intersection_packet<Index>(
+    packet: &mut RayPacket,
+    trimesh3: &crate::search_bvh3::TriMeshWithBvh<Index>,
+    i_bvhnode_root: usize,
+) where
+    Index: num_traits::PrimInt + AsPrimitive<usize>,
+{
+    let bvhnodes = trimesh3.bvhnodes;
+    let aabbs = trimesh3.bvhnode2aabb;
+    // sum of the directions to decide the order of the children
+    let dir_sum: [f32; 3] = std::array::from_fn(|i_dim| {
+        (0..PACKET_SIZE)
+            .filter(|&i| packet.t_max[i] != f32::NEG_INFINITY)
+            .map(|i| packet.dir[i_dim][i])
+            .sum()
+    });
+    let mut stack = vec![i_bvhnode_root];
+    while let Some(i_bvhnode) = stack.pop() {
+        if !packet.is_hit_aabb(&aabbs[i_bvhnode * 6..(i_bvhnode + 1) * 6]) {
+            continue;
+        }
+        if bvhnodes[i_bvhnode * 3 + 2] == Index::max_value() {
+            // leaf node
+            let i_tri: usize = bvhnodes[i_bvhnode * 3 + 1].as_();
+            let tri = crate::trimesh3::to_tri3(trimesh3.tri2vtx, trimesh3.vtx2xyz, i_tri);
+            packet.intersect_triangle(&tri, i_tri);
+            continue;
+        }
+        let i_left: usize = bvhnodes[i_bvhnode * 3 + 1].as_();
+        let i_right: usize = bvhnodes[i_bvhnode * 3 + 2].as_();
+        // the far child is pushed first so that the near child is visited first
+        let dot = (0..3)
+            .map(|i_dim| {
+                let c_left = aabbs[i_left * 6 + i_dim] + aabbs[i_left * 6 + i_dim + 3];
+                let c_right = aabbs[i_right * 6 + i_dim] + aabbs[i_right * 6 + i_dim + 3];
+                (c_right - c_left) * dir_sum[i_dim]
+            })
+            .sum::<f32>();
+        if dot >= 0f32 {
+            stack.push(i_right);
+            stack.push(i_left);
+        } else {
+            stack.push(i_left);
+            stack.push(i_right);
+        }
+    }
+}
+
+/// the closest hits of many rays computed with the ray packets in parallel.
+/// The consecutive `PACKET_SIZE` rays make a packet so the coherent rays should be next to each other.
+/// * `ray2tri` - the index of the hit triangle or `Index::max_value()` if there is no hit
+/// * `ray2t` - the ray parameter of the hit or `f32::INFINITY` if there is no hit
+pub fn first_intersection_rays<Index>(
+    ray2tri: &mut [Index],
+    ray2t: &mut [f32],
+    ray2org: &[f32],
+    ray2dir: &[f32],
+    trimesh3: &crate::search_bvh3::TriMeshWithBvh<Index>,
+    i_bvhnode_root: usize,
+) where
+    Index: num_traits::PrimInt + AsPrimitive<usize> + Send + Sync,
+    usize: AsPrimitive<Index>,
+{
+    use rayon::prelude::*;
+    let num_ray = ray2tri.len();
+    assert_eq!(ray2t.len(), num_ray);
+    assert_eq!(ray2org.len(), num_ray * 3);
+    assert_eq!(ray2dir.len(), num_ray * 3);
+    ray2tri
+        .par_chunks_mut(PACKET_SIZE)
+        .zip(ray2t.par_chunks_mut(PACKET_SIZE))
+        .zip(ray2org.par_chunks(PACKET_SIZE * 3))
+        .zip(ray2dir.par_chunks(PACKET_SIZE * 3))
+        .for_each(|(((idx2tri, idx2t), idx2org), idx2dir)| {
+            let mut packet = RayPacket::new(idx2org, idx2dir, f32::INFINITY);
+            first_intersection_packet(&mut packet, trimesh3, i_bvhnode_root);
+            for i in 0..idx2tri.len() {
+                if packet.i_tri[i] == usize::MAX {
+                    idx2tri[i] = Index::max_value();
+                    idx2t[i] = f32::INFINITY;
+                } else {
+                    idx2tri[i] = packet.i_tri[i].as_();
+                    idx2t[i] = packet.t_max[i];
+                }
+            }
+        });
+}
+
+#[test]
+fn test_first_intersection_rays() {
+    use rand::Rng;
+    use rand::SeedableRng;
+    let (tri2vtx, vtx2xyz) = crate::trimesh3_primitive::sphere_yup::<usize, f32>(1.0, 64, 64);
+    let bvhnodes = crate::bvhnodes_morton::from_triangle_mesh(&tri2vtx, &vtx2xyz, 3);
+    let bvhnode2aabb = crate::bvhnode2aabb3::from_uniform_mesh_with_bvh(
+        0,
+        &bvhnodes,
+        Some((&tri2vtx, 3)),
+        &vtx2xyz,
+        None,
+    );
+    let trimesh3 = crate::search_bvh3::TriMeshWithBvh {
+        tri2vtx: &tri2vtx,
+        vtx2xyz: &vtx2xyz,
+        bvhnodes: &bvhnodes,
+        bvhnode2aabb: &bvhnode2aabb,
+    };
+    let mut reng = rand_chacha::ChaChaRng::seed_from_u64(0u64);
+    let num_ray = 1001; // the last packet is not full
+    let ray2org: Vec<f32> = (0..num_ray * 3)
+        .map(|_| reng.random::<f32>() * 3.0 - 1.5)
+        .collect();
+    let ray2dir: Vec<f32> = (0..num_ray * 3)
+        .map(|_| reng.random::<f32>() * 2.0 - 1.0)
+        .collect();
+    let mut ray2tri = vec![0usize; num_ray];
+    let mut ray2t = vec![0f32; num_ray];
+    first_intersection_rays(&mut ray2tri, &mut ray2t, &ray2org, &ray2dir, &trimesh3, 0);
+    let mut num_hit = 0;
+    for i_ray in 0..num_ray {
+        let org = arrayref::array_ref![ray2org, i_ray * 3, 3];
+        let dir = arrayref::array_ref![ray2dir, i_ray * 3, 3];
+        let res = crate::search_bvh3::first_intersection_ray(org, dir, &trimesh3, 0, f32::INFINITY);
+        match res {
+            Some((t, _i_tri)) => {
+                num_hit += 1;
+                assert!((t - ray2t[i_ray]).abs() < 1.0e-5, "{} {}", t, ray2t[i_ray]);
+                assert_ne!(ray2tri[i_ray], usize::MAX);
+            }
+            None => {
+                assert_eq!(ray2tri[i_ray], usize::MAX);
+            }
+        }
+    }
+    assert!(num_hit > 0);
+}
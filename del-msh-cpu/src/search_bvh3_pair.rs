@@ -0,0 +1,256 @@
+//! dual-tree traversal of two 3D Bounding Volume Hierarchies to find overlapping leaf pairs
+
+use num_traits::{AsPrimitive, PrimInt};
+
+/// BVH of a 3D mesh placed in the world with an optional transformation
+pub struct BvhWithTransform<'a, Index, Real> {
+    pub bvhnodes: &'a [Index],
+    pub bvhnode2aabb: &'a [Real],
+    /// homogeneous transformation (column major) from the local coordinate of `bvhnode2aabb`
+    /// to the world coordinate. `None` for the identity
+    pub transform: Option<&'a [Real; 16]>,
+}
+
+impl<Index, Real> BvhWithTransform<'_, Index, Real>
+where
+    Index: PrimInt + AsPrimitive<usize>,
+    Real: num_traits::Float,
+{
+    /// AABB of the node in the world coordinate
+    pub fn aabb(&self, i_bvhnode: usize) -> [Real; 6] {
+        let aabb = arrayref::array_ref![self.bvhnode2aabb, i_bvhnode * 6, 6];
+        match self.transform {
+            None => *aabb,
+            Some(transform) => aabb3_transformed(aabb, transform),
+        }
+    }
+
+    /// the element index if the node is a leaf
+    pub fn leaf_element(&self, i_bvhnode: usize) -> Option<usize> {
+        if self.bvhnodes[i_bvhnode * 3 + 2] == Index::max_value() {
+            Some(self.bvhnodes[i_bvhnode * 3 + 1].as_())
+        } else {
+            None
+        }
+    }
+
+    pub fn children(&self, i_bvhnode: usize) -> (usize, usize) {
+        (
+            self.bvhnodes[i_bvhnode * 3 + 1].as_(),
+            self.bvhnodes[i_bvhnode * 3 + 2].as_(),
+        )
+    }
+}
+
+/// AABB enclosing the eight corners of `aabb` transformed by `transform`
+pub fn aabb3_transformed<Real>(aabb: &[Real; 6], transform: &[Real; 16]) -> [Real; 6]
+where
+    Real: num_traits::Float,
+{
+    let mut res = [Real::zero(); 6];
+    for i_corner in 0..8 {
+        let p = [
+            if i_corner & 1 == 0 { aabb[0] } else { aabb[3] },
+            if i_corner & 2 == 0 { aabb[1] } else { aabb[4] },
+            if i_corner & 4 == 0 { aabb[2] } else { aabb[5] },
+        ];
+        let q = del_geo_core::mat4_col_major::transform_homogeneous(transform, &p).unwrap();
+        if i_corner == 0 {
+            del_geo_core::aabb3::set_as_cube(&mut res, &q, Real::zero());
+        } else {
+            del_geo_core::aabb3::add_point(&mut res, &q, Real::zero());
+        }
+    }
+    res
+}
+
+fn is_overlap<Real>(aabb0: &[Real; 6], aabb1: &[Real; 6], margin: Real) -> bool
+where
+    Real: num_traits::Float,
+{
+    (0..3).all(|i_dim| {
+        aabb0[i_dim] <= aabb1[i_dim + 3] + margin && aabb1[i_dim] <= aabb0[i_dim + 3] + margin
+    })
+}
+
+/// call `callback(i_elem0, i_elem1)` for all the pairs of leaves whose AABBs
+/// (in the world coordinate) are closer than `margin`
+/// * `i_bvhnode0` - starting node of `bvh0` (typically the root `0`)
+/// * `i_bvhnode1` - starting node of `bvh1` (typically the root `0`)
+pub fn overlapping_leaves<Index, Real, F>(
+    callback: &mut F,
+    bvh0: &BvhWithTransform<Index, Real>,
+    i_bvhnode0: usize,
+    bvh1: &BvhWithTransform<Index, Real>,
+    i_bvhnode1: usize,
+    margin: Real,
+) where
+    Index: PrimInt + AsPrimitive<usize>,
+    Real: num_traits::Float,
+    F: FnMut(usize, usize),
+{
+    assert_eq!(bvh0.bvhnodes.len() / 3, bvh0.bvhnode2aabb.len() / 6);
+    assert_eq!(bvh1.bvhnodes.len() / 3, bvh1.bvhnode2aabb.len() / 6);
+    if !is_overlap(&bvh0.aabb(i_bvhnode0), &bvh1.aabb(i_bvhnode1), margin) {
+        return;
+    }
+    match (bvh0.leaf_element(i_bvhnode0), bvh1.leaf_element(i_bvhnode1)) {
+        (Some(i_elem0), Some(i_elem1)) => {
+            callback(i_elem0, i_elem1);
+        }
+        (None, Some(_)) => {
+            let (ichild0_0, ichild0_1) = bvh0.children(i_bvhnode0);
+            overlapping_leaves(callback, bvh0, ichild0_0, bvh1, i_bvhnode1, margin);
+            overlapping_leaves(callback, bvh0, ichild0_1, bvh1, i_bvhnode1, margin);
+        }
+        (Some(_), None) => {
+            let (ichild1_0, ichild1_1) = bvh1.children(i_bvhnode1);
+            overlapping_leaves(callback, bvh0, i_bvhnode0, bvh1, ichild1_0, margin);
+            overlapping_leaves(callback, bvh0, i_bvhnode0, bvh1, ichild1_1, margin);
+        }
+        (None, None) => {
+            let (ichild0_0, ichild0_1) = bvh0.children(i_bvhnode0);
+            let (ichild1_0, ichild1_1) = bvh1.children(i_bvhnode1);
+            overlapping_leaves(callback, bvh0, ichild0_0, bvh1, ichild1_0, margin);
+            overlapping_leaves(callback, bvh0, ichild0_1, bvh1, ichild1_0, margin);
+            overlapping_leaves(callback, bvh0, ichild0_0, bvh1, ichild1_1, margin);
+            overlapping_leaves(callback, bvh0, ichild0_1, bvh1, ichild1_1, margin);
+        }
+    }
+}
+
+/// call `callback(i_elem0, i_elem1)` for all the pairs of different leaves inside the branch
+/// `i_bvhnode` whose AABBs are closer than `margin`. Each pair is visited only once
+pub fn overlapping_leaves_inside_branch<Index, Real, F>(
+    callback: &mut F,
+    bvh: &BvhWithTransform<Index, Real>,
+    i_bvhnode: usize,
+    margin: Real,
+) where
+    Index: PrimInt + AsPrimitive<usize>,
+    Real: num_traits::Float,
+    F: FnMut(usize, usize),
+{
+    if bvh.leaf_element(i_bvhnode).is_some() {
+        return;
+    }
+    let (ichild0, ichild1) = bvh.children(i_bvhnode);
+    overlapping_leaves(callback, bvh, ichild0, bvh, ichild1, margin);
+    overlapping_leaves_inside_branch(callback, bvh, ichild0, margin);
+    overlapping_leaves_inside_branch(callback, bvh, ichild1, margin);
+}
+
+#[test]
+fn test_overlapping_leaves() {
+    use rand::Rng;
+    use rand::SeedableRng;
+    let mut reng = rand_chacha::ChaChaRng::seed_from_u64(0);
+    let (tri2vtx0, vtx2xyz0) = crate::trimesh3_primitive::sphere_yup::<usize, f32>(1.0, 16, 16);
+    let (tri2vtx1, vtx2xyz1) = crate::trimesh3_primitive::torus_zup::<usize, f32>(0.8, 0.2, 16, 8);
+    let bvhnodes0 = crate::bvhnodes_morton::from_triangle_mesh(&tri2vtx0, &vtx2xyz0, 3);
+    let bvhnode2aabb0 = crate::bvhnode2aabb3::from_uniform_mesh_with_bvh::<usize, f32>(
+        0,
+        &bvhnodes0,
+        Some((&tri2vtx0, 3)),
+        &vtx2xyz0,
+        None,
+    );
+    let bvhnodes1 = crate::bvhnodes_morton::from_triangle_mesh(&tri2vtx1, &vtx2xyz1, 3);
+    let bvhnode2aabb1 = crate::bvhnode2aabb3::from_uniform_mesh_with_bvh::<usize, f32>(
+        0,
+        &bvhnodes1,
+        Some((&tri2vtx1, 3)),
+        &vtx2xyz1,
+        None,
+    );
+    for _itr in 0..10 {
+        let transform1 = {
+            let t: [f32; 3] = std::array::from_fn(|_| reng.random::<f32>() - 0.5);
+            let r: [f32; 3] = std::array::from_fn(|_| reng.random::<f32>() * 3.0);
+            let rot = del_geo_core::mat4_col_major::from_bryant_angles(r[0], r[1], r[2]);
+            let trans = del_geo_core::mat4_col_major::from_translate(&t);
+            del_geo_core::mat4_col_major::mult_mat_col_major(&trans, &rot)
+        };
+        let margin = 0.05f32;
+        let mut pairs = vec![];
+        overlapping_leaves(
+            &mut |i_tri, j_tri| pairs.push((i_tri, j_tri)),
+            &BvhWithTransform {
+                bvhnodes: &bvhnodes0,
+                bvhnode2aabb: &bvhnode2aabb0,
+                transform: None,
+            },
+            0,
+            &BvhWithTransform {
+                bvhnodes: &bvhnodes1,
+                bvhnode2aabb: &bvhnode2aabb1,
+                transform: Some(&transform1),
+            },
+            0,
+            margin,
+        );
+        // compare against the brute force
+        let vtx2xyz1 = crate::vtx2xyz::transform_homogeneous(&vtx2xyz1, &transform1);
+        let mut cnt = 0;
+        for i_tri in 0..tri2vtx0.len() / 3 {
+            let aabb0 =
+                crate::vtx2xyz::aabb3_indexed(&tri2vtx0[i_tri * 3..i_tri * 3 + 3], &vtx2xyz0, 0f32);
+            for j_tri in 0..tri2vtx1.len() / 3 {
+                let aabb1 = crate::vtx2xyz::aabb3_indexed(
+                    &tri2vtx1[j_tri * 3..j_tri * 3 + 3],
+                    &vtx2xyz1,
+                    0f32,
+                );
+                if is_overlap(&aabb0, &aabb1, margin) {
+                    cnt += 1;
+                    // leaf AABBs of the transformed tree are conservative
+                    assert!(pairs.contains(&(i_tri, j_tri)));
+                }
+            }
+        }
+        assert!(pairs.len() >= cnt);
+    }
+}
+
+#[test]
+fn test_overlapping_leaves_inside_branch() {
+    let (tri2vtx, vtx2xyz) = crate::trimesh3_primitive::torus_zup::<usize, f32>(0.8, 0.2, 32, 16);
+    let bvhnodes = crate::bvhnodes_morton::from_triangle_mesh(&tri2vtx, &vtx2xyz, 3);
+    let bvhnode2aabb = crate::bvhnode2aabb3::from_uniform_mesh_with_bvh::<usize, f32>(
+        0,
+        &bvhnodes,
+        Some((&tri2vtx, 3)),
+        &vtx2xyz,
+        None,
+    );
+    let margin = 0.02f32;
+    let mut pairs = vec![];
+    overlapping_leaves_inside_branch(
+        &mut |i_tri, j_tri| pairs.push((i_tri.min(j_tri), i_tri.max(j_tri))),
+        &BvhWithTransform {
+            bvhnodes: &bvhnodes,
+            bvhnode2aabb: &bvhnode2aabb,
+            transform: None,
+        },
+        0,
+        margin,
+    );
+    let num_pair = pairs.len();
+    pairs.sort();
+    pairs.dedup();
+    assert_eq!(pairs.len(), num_pair); // no duplication
+    let mut pairs_bf = vec![];
+    let num_tri = tri2vtx.len() / 3;
+    for i_tri in 0..num_tri {
+        let aabb_i =
+            crate::vtx2xyz::aabb3_indexed(&tri2vtx[i_tri * 3..i_tri * 3 + 3], &vtx2xyz, 0f32);
+        for j_tri in i_tri + 1..num_tri {
+            let aabb_j =
+                crate::vtx2xyz::aabb3_indexed(&tri2vtx[j_tri * 3..j_tri * 3 + 3], &vtx2xyz, 0f32);
+            if is_overlap(&aabb_i, &aabb_j, margin) {
+                pairs_bf.push((i_tri, j_tri));
+            }
+        }
+    }
+    assert_eq!(pairs, pairs_bf);
+}
@@ -0,0 +1,712 @@
+//! methods for 3D Bounding Volume Hierarchy of non-triangle primitives:
+//! capsules (line segments with radius), spheres (points with radius) and quads.
+//! The AABBs of the capsules and the spheres are computed by
+//! `bvhnode2aabb3::from_uniform_mesh_with_bvh_and_radius`
+
+use num_traits::{AsPrimitive, PrimInt};
+
+/// line segments with the uniform radius (e.g., hair strands)
+pub struct CapsulesWithBvh<'a, Index> {
+    pub edge2vtx: &'a [Index],
+    pub vtx2xyz: &'a [f32],
+    pub radius: f32,
+    pub bvhnodes: &'a [Index],
+    pub bvhnode2aabb: &'a [f32],
+}
+
+/// points with radius (e.g., particles or splats). The leaves of the BVH store the vertex index
+pub struct SpheresWithBvh<'a, Index> {
+    pub vtx2xyz: &'a [f32],
+    pub vtx2rad: &'a [f32],
+    pub bvhnodes: &'a [Index],
+    pub bvhnode2aabb: &'a [f32],
+}
+
+/// quad mesh. A quad is split into two triangles `(0,1,2)` and `(0,2,3)` for the ray query
+pub struct QuadMeshWithBvh<'a, Index> {
+    pub quad2vtx: &'a [Index],
+    pub vtx2xyz: &'a [f32],
+    pub bvhnodes: &'a [Index],
+    pub bvhnode2aabb: &'a [f32],
+}
+
+/// the smallest `t >= 0` where the ray `org + t * dir` is on the sphere
+pub fn intersection_ray_sphere(
+    org: &[f32; 3],
+    dir: &[f32; 3],
+    center: &[f32; 3],
+    rad: f32,
+) -> Option<f32> {
+    use del_geo_core::vec3;
+    let oc = vec3::sub(org, center);
+    let a = vec3::dot(dir, dir);
+    let b = vec3::dot(dir, &oc);
+    let c = vec3::dot(&oc, &oc) - rad * rad;
+    let det = b * b - a * c;
+    if det < 0f32 || a == 0f32 {
+        return None;
+    }
+    let t0 = (-b - det.sqrt()) / a;
+    let t1 = (-b + det.sqrt()) / a;
+    if t0 >= 0f32 {
+        Some(t0)
+    } else if t1 >= 0f32 {
+        Some(t1)
+    } else {
+        None
+    }
+}
+
+/// the smallest `t >= 0` where the ray `org + t * dir` is on the capsule
+/// (the points within `rad` from the segment `p0`-`p1`)
+pub fn intersection_ray_capsule(
+    org: &[f32; 3],
+    dir: &[f32; 3],
+    p0: &[f32; 3],
+    p1: &[f32; 3],
+    rad: f32,
+) -> Option<f32> {
+    use del_geo_core::vec3;
+    let ba = vec3::sub(p1, p0);
+    let oa = vec3::sub(org, p0);
+    let baba = vec3::dot(&ba, &ba);
+    let bard = vec3::dot(&ba, dir);
+    let baoa = vec3::dot(&ba, &oa);
+    let rdoa = vec3::dot(dir, &oa);
+    let rdrd = vec3::dot(dir, dir);
+    let oaoa = vec3::dot(&oa, &oa);
+    // candidates on the two hemispherical caps
+    let mut t_min = [p0, p1]
+        .iter()
+        .filter_map(|c| intersection_ray_sphere(org, dir, c, rad))
+        .fold(f32::INFINITY, f32::min);
+    // candidates on the cylindrical side
+    let a = baba * rdrd - bard * bard;
+    let b = baba * rdoa - baoa * bard;
+    let c = baba * oaoa - baoa * baoa - rad * rad * baba;
+    let det = b * b - a * c;
+    if a != 0f32 && det >= 0f32 {
+        for t in [(-b - det.sqrt()) / a, (-b + det.sqrt()) / a] {
+            let y = baoa + t * bard;
+            if t >= 0f32 && y > 0f32 && y < baba {
+                t_min = t_min.min(t);
+            }
+        }
+    }
+    if t_min == f32::INFINITY {
+        None
+    } else {
+        Some(t_min)
+    }
+}
+
+// ------------------------------------
+// traversal shared by the primitives
+
+fn intersections_ray_elem<Index, F>(
+    hits: &mut Vec<(f32, usize)>,
+    ray_org: &[f32; 3],
+    ray_dir: &[f32; 3],
+    bvhnodes: &[Index],
+    bvhnode2aabb: &[f32],
+    i_bvhnode: usize,
+    elem_hit: &F,
+) where
+    Index: PrimInt + AsPrimitive<usize>,
+    F: Fn(usize) -> Option<f32>,
+{
+    assert_eq!(bvhnodes.len() / 3, bvhnode2aabb.len() / 6);
+    if del_geo_core::aabb3::from_aabbs(bvhnode2aabb, i_bvhnode)
+        .intersections_against_ray(ray_org, ray_dir)
+        .is_none()
+    {
+        return;
+    }
+    if bvhnodes[i_bvhnode * 3 + 2] == Index::max_value() {
+        // leaf node
+        let i_elem: usize = bvhnodes[i_bvhnode * 3 + 1].as_();
+        if let Some(t) = elem_hit(i_elem) {
+            hits.push((t, i_elem));
+        }
+        return;
+    }
+    for i_child in 1..3 {
+        intersections_ray_elem(
+            hits,
+            ray_org,
+            ray_dir,
+            bvhnodes,
+            bvhnode2aabb,
+            bvhnodes[i_bvhnode * 3 + i_child].as_(),
+            elem_hit,
+        );
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn first_intersection_ray_elem<Index, F>(
+    ray_org: &[f32; 3],
+    ray_dir: &[f32; 3],
+    bvhnodes: &[Index],
+    bvhnode2aabb: &[f32],
+    i_bvhnode: usize,
+    dis: f32,
+    elem_hit: &F,
+) -> Option<(f32, usize)>
+where
+    Index: PrimInt + AsPrimitive<usize>,
+    F: Fn(usize) -> Option<f32>,
+{
+    assert_eq!(bvhnodes.len() / 3, bvhnode2aabb.len() / 6);
+    match del_geo_core::aabb3::from_aabbs(bvhnode2aabb, i_bvhnode)
+        .intersections_against_ray(ray_org, ray_dir)
+    {
+        Some((t0, _t1)) if t0 < dis => {}
+        _ => return None,
+    }
+    if bvhnodes[i_bvhnode * 3 + 2] == Index::max_value() {
+        // leaf node
+        let i_elem: usize = bvhnodes[i_bvhnode * 3 + 1].as_();
+        return elem_hit(i_elem).filter(|&t| t < dis).map(|t| (t, i_elem));
+    }
+    let mut res = None;
+    let mut dis = dis;
+    for i_child in 1..3 {
+        if let Some((t, i_elem)) = first_intersection_ray_elem(
+            ray_org,
+            ray_dir,
+            bvhnodes,
+            bvhnode2aabb,
+            bvhnodes[i_bvhnode * 3 + i_child].as_(),
+            dis,
+            elem_hit,
+        ) {
+            dis = t;
+            res = Some((t, i_elem));
+        }
+    }
+    res
+}
+
+/// the element with the smallest signed distance `elem_dist` to the point.
+/// The AABBs of the nodes need to enclose the elements (e.g., inflated by the radius)
+fn nearest_to_point_elem<Index, F>(
+    nearest: &mut Option<(f32, usize)>,
+    point: &[f32; 3],
+    bvhnodes: &[Index],
+    bvhnode2aabb: &[f32],
+    i_bvhnode: usize,
+    elem_dist: &F,
+) where
+    Index: PrimInt + AsPrimitive<usize>,
+    F: Fn(usize) -> f32,
+{
+    assert_eq!(bvhnodes.len() / 3, bvhnode2aabb.len() / 6);
+    let aabb = arrayref::array_ref![bvhnode2aabb, i_bvhnode * 6, 6];
+    let dist_aabb = (0..3)
+        .map(|i_dim| {
+            let d = (aabb[i_dim] - point[i_dim]).max(point[i_dim] - aabb[i_dim + 3]);
+            d.max(0f32).powi(2)
+        })
+        .sum::<f32>()
+        .sqrt();
+    // `elem_dist` is signed (negative inside the element) but the AABB encloses the elements,
+    // so the elements are outside and at least `dist_aabb` away only when the point is outside the AABB
+    if let Some((dist_min, _)) = nearest {
+        if dist_aabb > dist_min.max(0f32) {
+            return;
+        }
+    }
+    if bvhnodes[i_bvhnode * 3 + 2] == Index::max_value() {
+        // leaf node
+        let i_elem: usize = bvhnodes[i_bvhnode * 3 + 1].as_();
+        let dist = elem_dist(i_elem);
+        match nearest {
+            Some((dist_min, _)) if *dist_min <= dist => {}
+            _ => *nearest = Some((dist, i_elem)),
+        }
+        return;
+    }
+    for i_child in 1..3 {
+        nearest_to_point_elem(
+            nearest,
+            point,
+            bvhnodes,
+            bvhnode2aabb,
+            bvhnodes[i_bvhnode * 3 + i_child].as_(),
+            elem_dist,
+        );
+    }
+}
+
+// ------------------------------------
+
+impl<Index> CapsulesWithBvh<'_, Index>
+where
+    Index: PrimInt + AsPrimitive<usize>,
+{
+    fn edge(&self, i_edge: usize) -> (&[f32; 3], &[f32; 3]) {
+        let i0: usize = self.edge2vtx[i_edge * 2].as_();
+        let i1: usize = self.edge2vtx[i_edge * 2 + 1].as_();
+        (
+            arrayref::array_ref![self.vtx2xyz, i0 * 3, 3],
+            arrayref::array_ref![self.vtx2xyz, i1 * 3, 3],
+        )
+    }
+
+    /// all the hits `(t, i_edge)` of the ray against the capsules
+    pub fn intersections_ray(
+        &self,
+        hits: &mut Vec<(f32, usize)>,
+        ray_org: &[f32; 3],
+        ray_dir: &[f32; 3],
+        i_bvhnode: usize,
+    ) {
+        let elem_hit = |i_edge: usize| {
+            let (p0, p1) = self.edge(i_edge);
+            intersection_ray_capsule(ray_org, ray_dir, p0, p1, self.radius)
+        };
+        intersections_ray_elem(
+            hits,
+            ray_org,
+            ray_dir,
+            self.bvhnodes,
+            self.bvhnode2aabb,
+            i_bvhnode,
+            &elem_hit,
+        );
+    }
+
+    /// the nearest hit `(t, i_edge)` of the ray closer than `dis`
+    pub fn first_intersection_ray(
+        &self,
+        ray_org: &[f32; 3],
+        ray_dir: &[f32; 3],
+        i_bvhnode: usize,
+        dis: f32,
+    ) -> Option<(f32, usize)> {
+        let elem_hit = |i_edge: usize| {
+            let (p0, p1) = self.edge(i_edge);
+            intersection_ray_capsule(ray_org, ray_dir, p0, p1, self.radius)
+        };
+        first_intersection_ray_elem(
+            ray_org,
+            ray_dir,
+            self.bvhnodes,
+            self.bvhnode2aabb,
+            i_bvhnode,
+            dis,
+            &elem_hit,
+        )
+    }
+
+    /// the segment nearest to `point`.
+    /// Returns the distance to the center line, the edge index and the ratio on the edge.
+    /// The distance to the surface of the capsule is `dist - radius`
+    pub fn nearest_to_point(
+        &self,
+        point: &[f32; 3],
+        i_bvhnode: usize,
+    ) -> Option<(f32, usize, f32)> {
+        let mut nearest = None;
+        let elem_dist = |i_edge: usize| {
+            let (p0, p1) = self.edge(i_edge);
+            del_geo_core::edge3::nearest_to_point3(p0, p1, point).0 - self.radius
+        };
+        nearest_to_point_elem(
+            &mut nearest,
+            point,
+            self.bvhnodes,
+            self.bvhnode2aabb,
+            i_bvhnode,
+            &elem_dist,
+        );
+        let (_dist, i_edge) = nearest?;
+        let (p0, p1) = self.edge(i_edge);
+        let (dist, ratio) = del_geo_core::edge3::nearest_to_point3(p0, p1, point);
+        Some((dist, i_edge, ratio))
+    }
+}
+
+impl<Index> SpheresWithBvh<'_, Index>
+where
+    Index: PrimInt + AsPrimitive<usize>,
+{
+    fn sphere(&self, i_vtx: usize) -> (&[f32; 3], f32) {
+        (
+            arrayref::array_ref![self.vtx2xyz, i_vtx * 3, 3],
+            self.vtx2rad[i_vtx],
+        )
+    }
+
+    /// all the hits `(t, i_vtx)` of the ray against the spheres
+    pub fn intersections_ray(
+        &self,
+        hits: &mut Vec<(f32, usize)>,
+        ray_org: &[f32; 3],
+        ray_dir: &[f32; 3],
+        i_bvhnode: usize,
+    ) {
+        let elem_hit = |i_vtx: usize| {
+            let (c, r) = self.sphere(i_vtx);
+            intersection_ray_sphere(ray_org, ray_dir, c, r)
+        };
+        intersections_ray_elem(
+            hits,
+            ray_org,
+            ray_dir,
+            self.bvhnodes,
+            self.bvhnode2aabb,
+            i_bvhnode,
+            &elem_hit,
+        );
+    }
+
+    /// the nearest hit `(t, i_vtx)` of the ray closer than `dis`
+    pub fn first_intersection_ray(
+        &self,
+        ray_org: &[f32; 3],
+        ray_dir: &[f32; 3],
+        i_bvhnode: usize,
+        dis: f32,
+    ) -> Option<(f32, usize)> {
+        let elem_hit = |i_vtx: usize| {
+            let (c, r) = self.sphere(i_vtx);
+            intersection_ray_sphere(ray_org, ray_dir, c, r)
+        };
+        first_intersection_ray_elem(
+            ray_org,
+            ray_dir,
+            self.bvhnodes,
+            self.bvhnode2aabb,
+            i_bvhnode,
+            dis,
+            &elem_hit,
+        )
+    }
+
+    /// the sphere whose surface is the nearest to `point` and the signed distance to it
+    pub fn nearest_to_point(&self, point: &[f32; 3], i_bvhnode: usize) -> Option<(f32, usize)> {
+        let mut nearest = None;
+        let elem_dist = |i_vtx: usize| {
+            let (c, r) = self.sphere(i_vtx);
+            del_geo_core::vec3::distance(c, point) - r
+        };
+        nearest_to_point_elem(
+            &mut nearest,
+            point,
+            self.bvhnodes,
+            self.bvhnode2aabb,
+            i_bvhnode,
+            &elem_dist,
+        );
+        nearest
+    }
+}
+
+impl<Index> QuadMeshWithBvh<'_, Index>
+where
+    Index: PrimInt + AsPrimitive<usize>,
+{
+    fn hit_quad(&self, ray_org: &[f32; 3], ray_dir: &[f32; 3], i_quad: usize) -> Option<f32> {
+        let p = |i_node: usize| {
+            let i_vtx: usize = self.quad2vtx[i_quad * 4 + i_node].as_();
+            arrayref::array_ref![self.vtx2xyz, i_vtx * 3, 3]
+        };
+        [(1, 2), (2, 3)]
+            .iter()
+            .filter_map(|&(i1, i2)| {
+                del_geo_core::tri3::Tri3 {
+                    p0: p(0),
+                    p1: p(i1),
+                    p2: p(i2),
+                }
+                .intersection_against_ray(ray_org, ray_dir)
+            })
+            .reduce(f32::min)
+    }
+
+    /// all the hits `(t, i_quad)` of the ray against the quads
+    pub fn intersections_ray(
+        &self,
+        hits: &mut Vec<(f32, usize)>,
+        ray_org: &[f32; 3],
+        ray_dir: &[f32; 3],
+        i_bvhnode: usize,
+    ) {
+        intersections_ray_elem(
+            hits,
+            ray_org,
+            ray_dir,
+            self.bvhnodes,
+            self.bvhnode2aabb,
+            i_bvhnode,
+            &|i_quad| self.hit_quad(ray_org, ray_dir, i_quad),
+        );
+    }
+
+    /// the nearest hit `(t, i_quad)` of the ray closer than `dis`
+    pub fn first_intersection_ray(
+        &self,
+        ray_org: &[f32; 3],
+        ray_dir: &[f32; 3],
+        i_bvhnode: usize,
+        dis: f32,
+    ) -> Option<(f32, usize)> {
+        first_intersection_ray_elem(
+            ray_org,
+            ray_dir,
+            self.bvhnodes,
+            self.bvhnode2aabb,
+            i_bvhnode,
+            dis,
+            &|i_quad| self.hit_quad(ray_org, ray_dir, i_quad),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::Rng;
+    use rand::SeedableRng;
+
+    fn random_ray(reng: &mut rand_chacha::ChaChaRng) -> ([f32; 3], [f32; 3]) {
+        let org: [f32; 3] = std::array::from_fn(|_| reng.random::<f32>() * 4.0 - 2.0);
+        let dir: [f32; 3] = std::array::from_fn(|_| reng.random::<f32>() * 2.0 - 1.0);
+        (org, dir)
+    }
+
+    fn sorted(mut hits: Vec<(f32, usize)>) -> Vec<(f32, usize)> {
+        hits.sort_by_key(|a| a.1);
+        hits
+    }
+
+    #[test]
+    fn test_capsules() {
+        let mut reng = rand_chacha::ChaChaRng::seed_from_u64(0u64);
+        let vtx2xyz = crate::polyline3::helix(300, 0.05, 1.0, 0.03);
+        let num_vtx = vtx2xyz.len() / 3;
+        let edge2vtx = crate::edge2vtx::from_polyline(num_vtx);
+        let num_edge = edge2vtx.len() / 2;
+        let radius = 0.05;
+        let edge2cntr = crate::elem2center::from_uniform_mesh_as_points(&edge2vtx, 2, &vtx2xyz, 3);
+        let bvhnodes = crate::bvhnodes_morton::from_vtx2xyz::<usize>(&edge2cntr, 3);
+        let bvhnode2aabb = crate::bvhnode2aabb3::from_uniform_mesh_with_bvh_and_radius(
+            0,
+            &bvhnodes,
+            Some((&edge2vtx, 2)),
+            &vtx2xyz,
+            &vec![radius; num_vtx],
+        );
+        let capsules = super::CapsulesWithBvh {
+            edge2vtx: &edge2vtx,
+            vtx2xyz: &vtx2xyz,
+            radius,
+            bvhnodes: &bvhnodes,
+            bvhnode2aabb: &bvhnode2aabb,
+        };
+        let mut num_hit = 0;
+        for _itr in 0..300 {
+            let (org, dir) = random_ray(&mut reng);
+            let mut hits0 = vec![];
+            capsules.intersections_ray(&mut hits0, &org, &dir, 0);
+            let hits1: Vec<(f32, usize)> = (0..num_edge)
+                .filter_map(|i_edge| {
+                    let (p0, p1) = capsules.edge(i_edge);
+                    super::intersection_ray_capsule(&org, &dir, p0, p1, radius).map(|t| (t, i_edge))
+                })
+                .collect();
+            assert_eq!(sorted(hits0), hits1);
+            let first = capsules.first_intersection_ray(&org, &dir, 0, f32::INFINITY);
+            let first1 = hits1.iter().map(|a| a.0).reduce(f32::min);
+            assert_eq!(first.map(|a| a.0), first1);
+            if let Some((t, i_edge)) = first {
+                num_hit += 1;
+                // the hit point is on the surface of the capsule
+                let (p0, p1) = capsules.edge(i_edge);
+                let q = del_geo_core::vec3::axpy(t, &dir, &org);
+                let (dist, _) = del_geo_core::edge3::nearest_to_point3(p0, p1, &q);
+                assert!((dist - radius).abs() < 1.0e-4, "{dist}");
+            }
+            // nearest segment
+            let (dist, _i_edge, _ratio) = capsules.nearest_to_point(&org, 0).unwrap();
+            let dist1 = (0..num_edge)
+                .map(|i_edge| {
+                    let (p0, p1) = capsules.edge(i_edge);
+                    del_geo_core::edge3::nearest_to_point3(p0, p1, &org).0
+                })
+                .fold(f32::INFINITY, f32::min);
+            assert!((dist - dist1).abs() < 1.0e-6);
+        }
+        assert!(num_hit > 0);
+    }
+
+    #[test]
+    fn test_spheres() {
+        let mut reng = rand_chacha::ChaChaRng::seed_from_u64(0u64);
+        let num_vtx = 1000;
+        let vtx2xyz: Vec<f32> = (0..num_vtx * 3)
+            .map(|_| reng.random::<f32>() * 2.0 - 1.0)
+            .collect();
+        let vtx2rad: Vec<f32> = (0..num_vtx)
+            .map(|_| reng.random::<f32>() * 0.05 + 0.01)
+            .collect();
+        let bvhnodes = crate::bvhnodes_morton::from_vtx2xyz::<usize>(&vtx2xyz, 3);
+        let bvhnode2aabb = crate::bvhnode2aabb3::from_uniform_mesh_with_bvh_and_radius(
+            0, &bvhnodes, None, &vtx2xyz, &vtx2rad,
+        );
+        let spheres = super::SpheresWithBvh {
+            vtx2xyz: &vtx2xyz,
+            vtx2rad: &vtx2rad,
+            bvhnodes: &bvhnodes,
+            bvhnode2aabb: &bvhnode2aabb,
+        };
+        for _itr in 0..300 {
+            let (org, dir) = random_ray(&mut reng);
+            let mut hits0 = vec![];
+            spheres.intersections_ray(&mut hits0, &org, &dir, 0);
+            let hits1: Vec<(f32, usize)> = (0..num_vtx)
+                .filter_map(|i_vtx| {
+                    let (c, r) = spheres.sphere(i_vtx);
+                    super::intersection_ray_sphere(&org, &dir, c, r).map(|t| (t, i_vtx))
+                })
+                .collect();
+            assert_eq!(sorted(hits0), hits1);
+            let first = spheres.first_intersection_ray(&org, &dir, 0, f32::INFINITY);
+            let first1 = hits1.iter().map(|a| a.0).reduce(f32::min);
+            assert_eq!(first.map(|a| a.0), first1);
+            let (dist, _i_vtx) = spheres.nearest_to_point(&org, 0).unwrap();
+            let dist1 = (0..num_vtx)
+                .map(|i_vtx| {
+                    let (c, r) = spheres.sphere(i_vtx);
+                    del_geo_core::vec3::distance(c, &org) - r
+                })
+                .fold(f32::INFINITY, f32::min);
+            assert_eq!(dist, dist1);
+        }
+    }
+
+    #[test]
+    fn test_nearest_inside() {
+        let mut reng = rand_chacha::ChaChaRng::seed_from_u64(0u64);
+        // query points inside the overlapping capsules at the joints of the strand
+        let vtx2xyz = crate::polyline3::helix(300, 0.05, 1.0, 0.03);
+        let num_vtx = vtx2xyz.len() / 3;
+        let edge2vtx = crate::edge2vtx::from_polyline(num_vtx);
+        let num_edge = edge2vtx.len() / 2;
+        let radius = 0.05;
+        let edge2cntr = crate::elem2center::from_uniform_mesh_as_points(&edge2vtx, 2, &vtx2xyz, 3);
+        let bvhnodes = crate::bvhnodes_morton::from_vtx2xyz::<usize>(&edge2cntr, 3);
+        let bvhnode2aabb = crate::bvhnode2aabb3::from_uniform_mesh_with_bvh_and_radius(
+            0,
+            &bvhnodes,
+            Some((&edge2vtx, 2)),
+            &vtx2xyz,
+            &vec![radius; num_vtx],
+        );
+        let capsules = super::CapsulesWithBvh {
+            edge2vtx: &edge2vtx,
+            vtx2xyz: &vtx2xyz,
+            radius,
+            bvhnodes: &bvhnodes,
+            bvhnode2aabb: &bvhnode2aabb,
+        };
+        for i_vtx in 1..num_vtx - 1 {
+            let d: [f32; 3] = std::array::from_fn(|_| (reng.random::<f32>() * 2.0 - 1.0) * 0.02);
+            let p = crate::vtx2xyz::to_vec3(&vtx2xyz, i_vtx);
+            let q = del_geo_core::vec3::add(p, &d);
+            let (dist, i_edge, _ratio) = capsules.nearest_to_point(&q, 0).unwrap();
+            let dist1 = (0..num_edge)
+                .map(|j_edge| {
+                    let (p0, p1) = capsules.edge(j_edge);
+                    del_geo_core::edge3::nearest_to_point3(p0, p1, &q).0
+                })
+                .fold(f32::INFINITY, f32::min);
+            assert!(dist < radius);
+            assert_eq!(dist, dist1, "{i_vtx} {i_edge}");
+        }
+        // query points inside the overlapping spheres
+        let num_vtx = 1000;
+        let vtx2xyz: Vec<f32> = (0..num_vtx * 3)
+            .map(|_| reng.random::<f32>() * 2.0 - 1.0)
+            .collect();
+        let vtx2rad: Vec<f32> = (0..num_vtx)
+            .map(|_| reng.random::<f32>() * 0.2 + 0.1)
+            .collect();
+        let bvhnodes = crate::bvhnodes_morton::from_vtx2xyz::<usize>(&vtx2xyz, 3);
+        let bvhnode2aabb = crate::bvhnode2aabb3::from_uniform_mesh_with_bvh_and_radius(
+            0, &bvhnodes, None, &vtx2xyz, &vtx2rad,
+        );
+        let spheres = super::SpheresWithBvh {
+            vtx2xyz: &vtx2xyz,
+            vtx2rad: &vtx2rad,
+            bvhnodes: &bvhnodes,
+            bvhnode2aabb: &bvhnode2aabb,
+        };
+        for i_vtx in 0..num_vtx {
+            let q = *crate::vtx2xyz::to_vec3(&vtx2xyz, i_vtx);
+            let (dist, _i_vtx) = spheres.nearest_to_point(&q, 0).unwrap();
+            let dist1 = (0..num_vtx)
+                .map(|j_vtx| {
+                    let (c, r) = spheres.sphere(j_vtx);
+                    del_geo_core::vec3::distance(c, &q) - r
+                })
+                .fold(f32::INFINITY, f32::min);
+            assert!(dist < 0.);
+            assert_eq!(dist, dist1);
+        }
+    }
+
+    #[test]
+    fn test_quads() {
+        let mut reng = rand_chacha::ChaChaRng::seed_from_u64(0u64);
+        let (quad2vtx, vtx2xy) = crate::quadmesh::from_grid::<f32>(16, 16);
+        // bumpy height field
+        let vtx2xyz: Vec<f32> = vtx2xy
+            .chunks(2)
+            .flat_map(|xy| {
+                let (x, y) = (xy[0] / 8.0 - 1.0, xy[1] / 8.0 - 1.0);
+                [x, y, 0.2 * (3.0 * x).sin() * (2.0 * y).cos()]
+            })
+            .collect();
+        let num_quad = quad2vtx.len() / 4;
+        let quad2cntr = crate::elem2center::from_uniform_mesh_as_points(&quad2vtx, 4, &vtx2xyz, 3);
+        let bvhnodes = crate::bvhnodes_morton::from_vtx2xyz::<usize>(&quad2cntr, 3);
+        let bvhnode2aabb = crate::bvhnode2aabb3::from_uniform_mesh_with_bvh(
+            0,
+            &bvhnodes,
+            Some((&quad2vtx, 4)),
+            &vtx2xyz,
+            None,
+        );
+        let quadmesh = super::QuadMeshWithBvh {
+            quad2vtx: &quad2vtx,
+            vtx2xyz: &vtx2xyz,
+            bvhnodes: &bvhnodes,
+            bvhnode2aabb: &bvhnode2aabb,
+        };
+        for _itr in 0..300 {
+            let org = [
+                reng.random::<f32>() * 1.8 - 0.9,
+                reng.random::<f32>() * 1.8 - 0.9,
+                1.0,
+            ];
+            let dir = [
+                reng.random::<f32>() * 0.2 - 0.1,
+                reng.random::<f32>() * 0.2 - 0.1,
+                -1.0,
+            ];
+            let mut hits0 = vec![];
+            quadmesh.intersections_ray(&mut hits0, &org, &dir, 0);
+            let hits1: Vec<(f32, usize)> = (0..num_quad)
+                .filter_map(|i_quad| quadmesh.hit_quad(&org, &dir, i_quad).map(|t| (t, i_quad)))
+                .collect();
+            assert_eq!(sorted(hits0), hits1);
+            // a ray from above always hits the height field
+            let (t, _i_quad) = quadmesh
+                .first_intersection_ray(&org, &dir, 0, f32::INFINITY)
+                .unwrap();
+            assert_eq!(Some(t), hits1.iter().map(|a| a.0).reduce(f32::min));
+        }
+    }
+}
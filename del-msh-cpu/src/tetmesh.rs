@@ -0,0 +1,259 @@
+//! methods for tetrahedral mesh
+
+use num_traits::AsPrimitive;
+
+/// local indices of the face opposite to each node of a tetrahedron.
+/// The faces are oriented outward if the volume of the tetrahedron is positive.
+pub const TET2FACE_NODES: [[usize; 3]; 4] = [[1, 2, 3], [0, 3, 2], [0, 1, 3], [0, 2, 1]];
+
+/// adjacent tetrahedron opposite to each node. `usize::MAX` if the face is on the boundary
+pub fn tet2tet(tet2vtx: &[usize], num_vtx: usize) -> Vec<usize> {
+    let (face2idx, idx2node) = crate::elem2elem::face2node_of_simplex_element(4);
+    crate::uniform_mesh::elem2elem(tet2vtx, 4, &face2idx, &idx2node, num_vtx)
+}
+
+/// triangles on the boundary of the tetrahedral mesh.
+/// The triangles are oriented outward if the volumes of the tetrahedra are positive (see `fix_orientation`).
+///
+/// # Returns
+/// `(tri2vtx, tri2tet)` where `tri2tet` is the tetrahedron that has the triangle
+pub fn boundary_tri2vtx(tet2vtx: &[usize], tet2tet: &[usize]) -> (Vec<usize>, Vec<usize>) {
+    assert_eq!(tet2vtx.len(), tet2tet.len());
+    let mut tri2vtx = vec![];
+    let mut tri2tet = vec![];
+    for i_tet in 0..tet2vtx.len() / 4 {
+        for i_node in 0..4 {
+            if tet2tet[i_tet * 4 + i_node] != usize::MAX {
+                continue;
+            }
+            tri2vtx.extend(TET2FACE_NODES[i_node].map(|j_node| tet2vtx[i_tet * 4 + j_node]));
+            tri2tet.push(i_tet);
+        }
+    }
+    (tri2vtx, tri2tet)
+}
+
+/// corner points of the `i_tet`-th tetrahedron
+pub fn to_corner_points<Index, Real>(
+    tet2vtx: &[Index],
+    vtx2xyz: &[Real],
+    i_tet: usize,
+) -> ([Real; 3], [Real; 3], [Real; 3], [Real; 3])
+where
+    Real: Copy,
+    Index: num_traits::PrimInt + AsPrimitive<usize>,
+{
+    let p = |i_node: usize| {
+        let i_vtx: usize = tet2vtx[i_tet * 4 + i_node].as_();
+        [
+            vtx2xyz[i_vtx * 3],
+            vtx2xyz[i_vtx * 3 + 1],
+            vtx2xyz[i_vtx * 3 + 2],
+        ]
+    };
+    (p(0), p(1), p(2), p(3))
+}
+
+/// signed volume of a tetrahedron. Positive if `p3` is on the side where the normal of `(p0, p1, p2)` points
+pub fn volume<Real>(p0: &[Real; 3], p1: &[Real; 3], p2: &[Real; 3], p3: &[Real; 3]) -> Real
+where
+    Real: num_traits::Float + 'static,
+    f64: AsPrimitive<Real>,
+{
+    use del_geo_core::vec3;
+    let n = vec3::cross(&vec3::sub(p1, p0), &vec3::sub(p2, p0));
+    vec3::dot(&n, &vec3::sub(p3, p0)) / 6f64.as_()
+}
+
+pub fn tet2volume<Real>(tet2vtx: &[usize], vtx2xyz: &[Real]) -> Vec<Real>
+where
+    Real: num_traits::Float + 'static,
+    f64: AsPrimitive<Real>,
+{
+    (0..tet2vtx.len() / 4)
+        .map(|i_tet| {
+            let (p0, p1, p2, p3) = to_corner_points(tet2vtx, vtx2xyz, i_tet);
+            volume(&p0, &p1, &p2, &p3)
+        })
+        .collect()
+}
+
+/// volume of the barycentric dual cell of each vertex (i.e., a quarter of the volume of the tetrahedra around it).
+/// This can be used as the lumped mass.
+pub fn vtx2volume<Real>(tet2vtx: &[usize], vtx2xyz: &[Real]) -> Vec<Real>
+where
+    Real: num_traits::Float + 'static,
+    f64: AsPrimitive<Real>,
+{
+    let mut vtx2volume = vec![Real::zero(); vtx2xyz.len() / 3];
+    for (i_tet, &vol) in tet2volume(tet2vtx, vtx2xyz).iter().enumerate() {
+        for &i_vtx in &tet2vtx[i_tet * 4..i_tet * 4 + 4] {
+            vtx2volume[i_vtx] = vtx2volume[i_vtx] + vol * 0.25f64.as_();
+        }
+    }
+    vtx2volume
+}
+
+/// radius ratio `3 * r_in / r_circum` of a tetrahedron.
+/// This is one for the regular tetrahedron and approaches zero as the tetrahedron degenerates.
+pub fn radius_ratio<Real>(p0: &[Real; 3], p1: &[Real; 3], p2: &[Real; 3], p3: &[Real; 3]) -> Real
+where
+    Real: num_traits::Float + 'static,
+    f64: AsPrimitive<Real>,
+{
+    use del_geo_core::vec3;
+    let vol = volume(p0, p1, p2, p3);
+    let area = del_geo_core::tri3::area(p1, p2, p3)
+        + del_geo_core::tri3::area(p0, p2, p3)
+        + del_geo_core::tri3::area(p0, p1, p3)
+        + del_geo_core::tri3::area(p0, p1, p2);
+    let r_in = vol * 3f64.as_() / area;
+    let (a, b, c) = (vec3::sub(p1, p0), vec3::sub(p2, p0), vec3::sub(p3, p0));
+    let (bc, ca, ab) = (
+        vec3::cross(&b, &c),
+        vec3::cross(&c, &a),
+        vec3::cross(&a, &b),
+    );
+    let (la, lb, lc) = (vec3::dot(&a, &a), vec3::dot(&b, &b), vec3::dot(&c, &c));
+    // position of the circumcenter relative to p0
+    let d: [Real; 3] = std::array::from_fn(|i| la * bc[i] + lb * ca[i] + lc * ab[i]);
+    let r_circum = vec3::norm(&d) / (vec3::dot(&a, &bc) * 2f64.as_()).abs();
+    r_in * 3f64.as_() / r_circum
+}
+
+/// dihedral angles (in radian) at the edges `(0,1)`, `(0,2)`, `(0,3)`, `(1,2)`, `(1,3)` and `(2,3)`
+pub fn dihedral_angles<Real>(
+    p0: &[Real; 3],
+    p1: &[Real; 3],
+    p2: &[Real; 3],
+    p3: &[Real; 3],
+) -> [Real; 6]
+where
+    Real: num_traits::Float + 'static + num_traits::FloatConst,
+{
+    use del_geo_core::vec3;
+    let ps = [p0, p1, p2, p3];
+    // outward normal of the face opposite to each node
+    let normals: [[Real; 3]; 4] = std::array::from_fn(|i_node| {
+        let [j0, j1, j2] = TET2FACE_NODES[i_node];
+        del_geo_core::tri3::normal(ps[j0], ps[j1], ps[j2])
+    });
+    let mut angles = [Real::zero(); 6];
+    for (i_edge, (i0, i1)) in [(0, 1), (0, 2), (0, 3), (1, 2), (1, 3), (2, 3)]
+        .into_iter()
+        .enumerate()
+    {
+        // the two faces sharing the edge are opposite to the other two nodes
+        let (k0, k1) = match (i0, i1) {
+            (0, 1) => (2, 3),
+            (0, 2) => (1, 3),
+            (0, 3) => (1, 2),
+            (1, 2) => (0, 3),
+            (1, 3) => (0, 2),
+            _ => (0, 1),
+        };
+        let (n0, n1) = (&normals[k0], &normals[k1]);
+        let cos = vec3::dot(n0, n1) / (vec3::norm(n0) * vec3::norm(n1));
+        angles[i_edge] = Real::PI() - cos.max(-Real::one()).min(Real::one()).acos();
+    }
+    angles
+}
+
+/// make the volumes of the tetrahedra positive by swapping their last two nodes.
+/// The adjacency (e.g., `tet2tet`) needs to be computed again after this.
+///
+/// # Returns
+/// the number of the tetrahedra flipped
+pub fn fix_orientation<Real>(tet2vtx: &mut [usize], vtx2xyz: &[Real]) -> usize
+where
+    Real: num_traits::Float + 'static,
+    f64: AsPrimitive<Real>,
+{
+    let mut num_flip = 0;
+    for i_tet in 0..tet2vtx.len() / 4 {
+        let (p0, p1, p2, p3) = to_corner_points(tet2vtx, vtx2xyz, i_tet);
+        if volume(&p0, &p1, &p2, &p3) < Real::zero() {
+            tet2vtx.swap(i_tet * 4 + 2, i_tet * 4 + 3);
+            num_flip += 1;
+        }
+    }
+    num_flip
+}
+
+#[test]
+fn test_cube() {
+    // lattice points in [0, 2]^3
+    let vtx2xyz: Vec<f64> = (0..27)
+        .flat_map(|i| [(i % 3) as f64, ((i / 3) % 3) as f64, (i / 9) as f64])
+        .collect();
+    let mut tet2vtx = crate::tetmesh_delaunay::tetrahedralize_points(&vtx2xyz);
+    let num_tet = tet2vtx.len() / 4;
+    // flip some of the tetrahedra
+    for i_tet in (0..num_tet).step_by(3) {
+        tet2vtx.swap(i_tet * 4, i_tet * 4 + 1);
+    }
+    assert_eq!(fix_orientation(&mut tet2vtx, &vtx2xyz), num_tet.div_ceil(3));
+    assert_eq!(fix_orientation(&mut tet2vtx, &vtx2xyz), 0);
+    let tet2volume = tet2volume(&tet2vtx, &vtx2xyz);
+    assert!(tet2volume.iter().all(|&v| v > 0.));
+    assert!((tet2volume.iter().sum::<f64>() - 8.).abs() < 1.0e-10);
+    let vtx2volume = vtx2volume(&tet2vtx, &vtx2xyz);
+    assert!((vtx2volume.iter().sum::<f64>() - 8.).abs() < 1.0e-10);
+    // the corner of the cube has a smaller dual volume than the center
+    assert!(vtx2volume[0] < vtx2volume[13]);
+    let tet2tet = tet2tet(&tet2vtx, 27);
+    for i_tet in 0..num_tet {
+        for i_node in 0..4 {
+            let j_tet = tet2tet[i_tet * 4 + i_node];
+            if j_tet == usize::MAX {
+                continue;
+            }
+            assert!(tet2tet[j_tet * 4..j_tet * 4 + 4].contains(&i_tet));
+            assert!(!tet2vtx[j_tet * 4..j_tet * 4 + 4].contains(&tet2vtx[i_tet * 4 + i_node]));
+        }
+    }
+    let (tri2vtx, tri2tet) = boundary_tri2vtx(&tet2vtx, &tet2tet);
+    assert_eq!(tri2vtx.len() / 3, 48);
+    assert_eq!(tri2tet.len(), 48);
+    // outward normals: the volume by the divergence theorem is positive
+    let volume_surf = (0..tri2vtx.len() / 3)
+        .map(|i_tri| {
+            let p = crate::trimesh3::to_corner_points(&tri2vtx, &vtx2xyz, i_tri);
+            volume(&[0.; 3], &p.0, &p.1, &p.2)
+        })
+        .sum::<f64>();
+    assert!((volume_surf - 8.).abs() < 1.0e-10);
+}
+
+#[test]
+fn test_quality() {
+    let sqrt2 = 2f64.sqrt();
+    let p0 = [1., 0., -1. / sqrt2];
+    let p1 = [-1., 0., -1. / sqrt2];
+    let p2 = [0., 1., 1. / sqrt2];
+    let p3 = [0., -1., 1. / sqrt2];
+    // regular tetrahedron
+    let (p0, p1) = if volume(&p0, &p1, &p2, &p3) > 0. {
+        (p0, p1)
+    } else {
+        (p1, p0)
+    };
+    assert!((radius_ratio(&p0, &p1, &p2, &p3) - 1.).abs() < 1.0e-10);
+    for angle in dihedral_angles(&p0, &p1, &p2, &p3) {
+        assert!((angle - (1f64 / 3.).acos()).abs() < 1.0e-10);
+    }
+    // corner of a cube
+    let (q0, q1, q2, q3) = ([0.; 3], [1., 0., 0.], [0., 1., 0.], [0., 0., 1.]);
+    let angles = dihedral_angles(&q0, &q1, &q2, &q3);
+    for angle in &angles[0..3] {
+        assert!((angle - std::f64::consts::FRAC_PI_2).abs() < 1.0e-10);
+    }
+    for angle in &angles[3..6] {
+        assert!((angle - (1f64 / 3f64.sqrt()).acos()).abs() < 1.0e-10);
+    }
+    let r = radius_ratio(&q0, &q1, &q2, &q3);
+    assert!(r > 0. && r < 1.);
+    // nearly flat
+    let r = radius_ratio(&q0, &q1, &q2, &[0.3, 0.3, 1.0e-5]);
+    assert!(r < 1.0e-3);
+}
@@ -0,0 +1,844 @@
+//! 3D Delaunay tetrahedralization of points (Bowyer–Watson algorithm)
+//! and tetrahedral mesh generation of the interior of a closed triangle mesh.
+//!
+//! The orientation and in-sphere predicates are first evaluated with the floating point arithmetic
+//! and re-evaluated with the exact arithmetic (floating-point expansion) only when the sign is ambiguous.
+//!
+//! The surface is recovered by the conforming Delaunay approach, i.e., the edges of the surface are bisected
+//! until the split triangles appear as the faces of the Delaunay tetrahedralization.
+//! This is not a constrained Delaunay tetrahedralization, so the surface is refined, and `None` is returned
+//! for some valid closed surfaces (e.g., with very sharp dihedral angles) where the bisection does not converge.
+
+use num_traits::AsPrimitive;
+
+// ---------------------------------
+// floating-point expansion for the exact predicates (J. R. Shewchuk 1997)
+
+fn two_sum<T: num_traits::Float>(a: T, b: T) -> (T, T) {
+    let s = a + b;
+    let bv = s - a;
+    let av = s - bv;
+    (s, (a - av) + (b - bv))
+}
+
+fn two_product<T: num_traits::Float>(a: T, b: T) -> (T, T) {
+    let p = a * b;
+    (p, a.mul_add(b, -p))
+}
+
+/// exact sum of the expansion and a number. The components are in the increasing order of the magnitude.
+fn expansion_grow<T: num_traits::Float>(e: &[T], b: T) -> Vec<T> {
+    let mut h = Vec::with_capacity(e.len() + 1);
+    let mut q = b;
+    for &e_i in e {
+        let (s, err) = two_sum(q, e_i);
+        if err != T::zero() {
+            h.push(err);
+        }
+        q = s;
+    }
+    if q != T::zero() || h.is_empty() {
+        h.push(q);
+    }
+    h
+}
+
+fn expansion_add<T: num_traits::Float>(e: &[T], f: &[T]) -> Vec<T> {
+    f.iter().fold(e.to_vec(), |h, &f_i| expansion_grow(&h, f_i))
+}
+
+fn expansion_sub<T: num_traits::Float>(e: &[T], f: &[T]) -> Vec<T> {
+    f.iter()
+        .fold(e.to_vec(), |h, &f_i| expansion_grow(&h, -f_i))
+}
+
+fn expansion_mul<T: num_traits::Float>(e: &[T], f: &[T]) -> Vec<T> {
+    let mut h = vec![T::zero()];
+    for &e_i in e {
+        for &f_j in f {
+            let (p, err) = two_product(e_i, f_j);
+            h = expansion_grow(&expansion_grow(&h, err), p);
+        }
+    }
+    h
+}
+
+/// exact difference `a - b` as an expansion
+fn expansion_diff<T: num_traits::Float>(a: T, b: T) -> Vec<T> {
+    let (s, err) = two_sum(a, -b);
+    expansion_grow(&[err], s)
+}
+
+fn expansion_sign<T: num_traits::Float>(e: &[T]) -> i32 {
+    let v = *e.last().unwrap();
+    if v > T::zero() {
+        1
+    } else if v < T::zero() {
+        -1
+    } else {
+        0
+    }
+}
+
+fn expansion_det3<T: num_traits::Float>(r: &[[Vec<T>; 3]; 3]) -> Vec<T> {
+    let minor = |i: usize, j: usize| {
+        expansion_sub(
+            &expansion_mul(&r[1][i], &r[2][j]),
+            &expansion_mul(&r[1][j], &r[2][i]),
+        )
+    };
+    let d0 = expansion_mul(&r[0][0], &minor(1, 2));
+    let d1 = expansion_mul(&r[0][1], &minor(0, 2));
+    let d2 = expansion_mul(&r[0][2], &minor(0, 1));
+    expansion_add(&expansion_sub(&d0, &d1), &d2)
+}
+
+fn det3<T: num_traits::Float>(r: &[[T; 3]; 3]) -> (T, T) {
+    let m0 = r[1][1] * r[2][2] - r[1][2] * r[2][1];
+    let m1 = r[1][0] * r[2][2] - r[1][2] * r[2][0];
+    let m2 = r[1][0] * r[2][1] - r[1][1] * r[2][0];
+    let det = r[0][0] * m0 - r[0][1] * m1 + r[0][2] * m2;
+    let pm0 = (r[1][1] * r[2][2]).abs() + (r[1][2] * r[2][1]).abs();
+    let pm1 = (r[1][0] * r[2][2]).abs() + (r[1][2] * r[2][0]).abs();
+    let pm2 = (r[1][0] * r[2][1]).abs() + (r[1][1] * r[2][0]).abs();
+    let permanent = r[0][0].abs() * pm0 + r[0][1].abs() * pm1 + r[0][2].abs() * pm2;
+    (det, permanent)
+}
+
+/// sign of the volume of the tetrahedron `(a, b, c, d)`.
+/// Positive if `d` is on the side where the normal of the triangle `(a, b, c)` points.
+pub fn orient3d<T>(a: &[T; 3], b: &[T; 3], c: &[T; 3], d: &[T; 3]) -> i32
+where
+    T: num_traits::Float + 'static,
+    f64: AsPrimitive<T>,
+{
+    let r: [[T; 3]; 3] = std::array::from_fn(|i| {
+        let p = [b, c, d][i];
+        std::array::from_fn(|j| p[j] - a[j])
+    });
+    let (det, permanent) = det3(&r);
+    let err_bound = permanent * T::epsilon() * 16f64.as_();
+    if det > err_bound {
+        return 1;
+    }
+    if det < -err_bound {
+        return -1;
+    }
+    let r: [[Vec<T>; 3]; 3] = std::array::from_fn(|i| {
+        let p = [b, c, d][i];
+        std::array::from_fn(|j| expansion_diff(p[j], a[j]))
+    });
+    expansion_sign(&expansion_det3(&r))
+}
+
+/// sign of the in-sphere test of the point `e` against the tetrahedron `(a, b, c, d)` with the positive volume.
+/// Positive if `e` is inside the circumscribed sphere.
+pub fn insphere<T>(a: &[T; 3], b: &[T; 3], c: &[T; 3], d: &[T; 3], e: &[T; 3]) -> i32
+where
+    T: num_traits::Float + 'static,
+    f64: AsPrimitive<T>,
+{
+    let ps = [a, b, c, d];
+    let r: [[T; 3]; 4] = std::array::from_fn(|i| std::array::from_fn(|j| ps[i][j] - e[j]));
+    let lift: [T; 4] =
+        std::array::from_fn(|i| r[i][0] * r[i][0] + r[i][1] * r[i][1] + r[i][2] * r[i][2]);
+    // cofactor expansion along the lifted column (sign flipped so that inside is positive)
+    let mut det = T::zero();
+    let mut permanent = T::zero();
+    for i in 0..4 {
+        let rows: [[T; 3]; 3] = std::array::from_fn(|k| r[(i + 1 + k) % 4]);
+        let (d, p) = det3(&rows);
+        // cyclic rotation of the rows changes the sign for the odd index
+        det = if i % 2 == 0 {
+            det + lift[i] * d
+        } else {
+            det - lift[i] * d
+        };
+        permanent = permanent + lift[i] * p;
+    }
+    let err_bound = permanent * T::epsilon() * 64f64.as_();
+    if det > err_bound {
+        return 1;
+    }
+    if det < -err_bound {
+        return -1;
+    }
+    let r: [[Vec<T>; 3]; 4] =
+        std::array::from_fn(|i| std::array::from_fn(|j| expansion_diff(ps[i][j], e[j])));
+    let mut det = vec![T::zero()];
+    for i in 0..4 {
+        let lift = (0..3).fold(vec![T::zero()], |h, j| {
+            expansion_add(&h, &expansion_mul(&r[i][j], &r[i][j]))
+        });
+        let rows: [[Vec<T>; 3]; 3] = std::array::from_fn(|k| r[(i + 1 + k) % 4].clone());
+        let term = expansion_mul(&lift, &expansion_det3(&rows));
+        det = if i % 2 == 0 {
+            expansion_add(&det, &term)
+        } else {
+            expansion_sub(&det, &term)
+        };
+    }
+    expansion_sign(&det)
+}
+
+// ---------------------------------
+
+/// index of the vertex at infinity. The tetrahedra having it (ghost tetrahedra) cover the outside of the convex hull
+const INF: usize = usize::MAX - 1;
+
+/// Delaunay tetrahedralization under construction.
+/// The faces of the convex hull are connected to the vertex at infinity by the ghost tetrahedra
+struct Delaunay3<T> {
+    vtx2xyz: Vec<[T; 3]>,
+    /// `[usize::MAX; 4]` for the deleted tetrahedron
+    tet2vtx: Vec<[usize; 4]>,
+    /// adjacent tetrahedron opposite to each node
+    tet2tet: Vec<[usize; 4]>,
+    /// deleted tetrahedra to be reused
+    free_tets: Vec<usize>,
+    tet2flag: Vec<bool>,
+    /// finite tetrahedron to start the walk
+    i_tet_hint: usize,
+    random_state: u64,
+    /// faces of the finite tetrahedra with the sorted vertex indices. Maintained if `Some`
+    face_set: Option<std::collections::HashSet<[usize; 3]>>,
+    /// faces removed from `face_set` by the insertions
+    removed_faces: Vec<[usize; 3]>,
+}
+
+fn sorted_face(f: &[usize; 3]) -> [usize; 3] {
+    let mut f = *f;
+    f.sort();
+    f
+}
+
+impl<T> Delaunay3<T>
+where
+    T: num_traits::Float + 'static,
+    f64: AsPrimitive<T>,
+{
+    /// tetrahedralization of four points in general position and the ghost tetrahedra around it.
+    /// `None` if all the points are on a plane
+    fn new(vtx2xyz: Vec<[T; 3]>) -> Option<Self> {
+        let tet = crate::convexhull3::initial_tetrahedron(&vtx2xyz)?;
+        let mut dt = Delaunay3 {
+            vtx2xyz,
+            tet2vtx: vec![tet],
+            tet2tet: vec![[1, 2, 3, 4]],
+            free_tets: vec![],
+            tet2flag: vec![false; 5],
+            i_tet_hint: 0,
+            random_state: 0x2545_f491_4f6c_dd1d,
+            face_set: None,
+            removed_faces: vec![],
+        };
+        for i_node in 0..4 {
+            let [a, b, c] = dt.face(0, i_node);
+            dt.tet2vtx.push([a, b, c, INF]);
+            dt.tet2tet.push([usize::MAX, usize::MAX, usize::MAX, 0]);
+        }
+        // the ghost tetrahedra share the faces including the vertex at infinity
+        for i_tet in 1..5 {
+            for i_node in 0..3 {
+                let f = dt.face(i_tet, i_node);
+                dt.tet2tet[i_tet][i_node] = (1..5)
+                    .find(|&j_tet| {
+                        j_tet != i_tet && f.iter().all(|i_vtx| dt.tet2vtx[j_tet].contains(i_vtx))
+                    })
+                    .unwrap();
+            }
+        }
+        Some(dt)
+    }
+
+    fn face(&self, i_tet: usize, i_node: usize) -> [usize; 3] {
+        crate::tetmesh::TET2FACE_NODES[i_node].map(|j_node| self.tet2vtx[i_tet][j_node])
+    }
+
+    fn is_ghost(&self, i_tet: usize) -> bool {
+        self.tet2vtx[i_tet].contains(&INF)
+    }
+
+    /// whether the point is inside the circumscribed sphere of the tetrahedron.
+    /// For a ghost tetrahedron, whether the point is in front of the face of the convex hull
+    /// or inside the circumscribed circle of the face on its plane
+    fn is_conflict(&self, i_tet: usize, p: &[T; 3]) -> bool {
+        let x = &self.vtx2xyz;
+        if let Some(i_node) = self.tet2vtx[i_tet].iter().position(|&i_vtx| i_vtx == INF) {
+            // face of the convex hull oriented to the inside
+            let [a, b, c] = self.face(i_tet, i_node);
+            match orient3d(&x[a], &x[b], &x[c], p) {
+                o if o < 0 => true,
+                0 => {
+                    let j_tet = self.tet2tet[i_tet][i_node];
+                    let [a, b, c, d] = self.tet2vtx[j_tet].map(|j_vtx| &x[j_vtx]);
+                    insphere(a, b, c, d, p) > 0
+                }
+                _ => false,
+            }
+        } else {
+            let [a, b, c, d] = self.tet2vtx[i_tet].map(|i_vtx| &x[i_vtx]);
+            insphere(a, b, c, d, p) > 0
+        }
+    }
+
+    /// stochastic visibility walk to the finite tetrahedron including `p`,
+    /// or to a ghost tetrahedron in front of `p` if `p` is outside the convex hull
+    fn locate(&mut self, p: &[T; 3]) -> usize {
+        let mut i_tet = self.i_tet_hint;
+        if self.tet2vtx[i_tet][0] == usize::MAX || self.is_ghost(i_tet) {
+            i_tet = (0..self.tet2vtx.len())
+                .find(|&i_tet| self.tet2vtx[i_tet][0] != usize::MAX && !self.is_ghost(i_tet))
+                .unwrap();
+        }
+        'walk: loop {
+            // xorshift to choose the face to test first
+            self.random_state ^= self.random_state << 13;
+            self.random_state ^= self.random_state >> 7;
+            self.random_state ^= self.random_state << 17;
+            let i_start = (self.random_state % 4) as usize;
+            for k in 0..4 {
+                let i_node = (i_start + k) % 4;
+                let [a, b, c] = self.face(i_tet, i_node);
+                let x = &self.vtx2xyz;
+                if orient3d(&x[a], &x[b], &x[c], p) > 0 {
+                    i_tet = self.tet2tet[i_tet][i_node];
+                    if self.is_ghost(i_tet) {
+                        return i_tet;
+                    }
+                    continue 'walk;
+                }
+            }
+            return i_tet;
+        }
+    }
+
+    /// insert the point `i_vtx`. Nothing is done for the points of the initial tetrahedron.
+    /// Returns the point at the position of `i_vtx`, i.e., `i_vtx` itself or the existing point overlapping with it
+    fn insert(&mut self, i_vtx: usize) -> usize {
+        let p = self.vtx2xyz[i_vtx];
+        let i_tet0 = self.locate(&p);
+        if let Some(&j_vtx) = self.tet2vtx[i_tet0]
+            .iter()
+            .find(|&&j_vtx| j_vtx != INF && self.vtx2xyz[j_vtx] == p)
+        {
+            return j_vtx;
+        }
+        // cavity: tetrahedra whose circumscribed sphere contains the point
+        let mut cavity = vec![i_tet0];
+        self.tet2flag[i_tet0] = true;
+        let mut i_cur = 0;
+        while i_cur < cavity.len() {
+            let i_tet = cavity[i_cur];
+            i_cur += 1;
+            for j_tet in self.tet2tet[i_tet] {
+                if self.tet2flag[j_tet] {
+                    continue;
+                }
+                if self.is_conflict(j_tet, &p) {
+                    self.tet2flag[j_tet] = true;
+                    cavity.push(j_tet);
+                }
+            }
+        }
+        // enlarge the cavity until it becomes star-shaped from the point (for the degenerate inputs)
+        loop {
+            let mut is_updated = false;
+            for i_cav in 0..cavity.len() {
+                let i_tet = cavity[i_cav];
+                for i_node in 0..4 {
+                    let j_tet = self.tet2tet[i_tet][i_node];
+                    if self.tet2flag[j_tet] {
+                        continue;
+                    }
+                    let [a, b, c] = self.face(i_tet, i_node);
+                    if [a, b, c].contains(&INF) {
+                        continue;
+                    }
+                    let x = &self.vtx2xyz;
+                    if orient3d(&x[a], &x[b], &x[c], &p) < 0 {
+                        continue;
+                    }
+                    self.tet2flag[j_tet] = true;
+                    cavity.push(j_tet);
+                    is_updated = true;
+                }
+            }
+            if !is_updated {
+                break;
+            }
+        }
+        // boundary faces of the cavity and the tetrahedra outside
+        let mut faces = vec![];
+        for &i_tet in cavity.iter() {
+            for i_node in 0..4 {
+                let j_tet = self.tet2tet[i_tet][i_node];
+                if !self.tet2flag[j_tet] {
+                    faces.push((self.face(i_tet, i_node), j_tet));
+                    continue;
+                }
+                // the face inside the cavity is removed
+                let f = sorted_face(&self.face(i_tet, i_node));
+                if let Some(face_set) = self.face_set.as_mut() {
+                    if face_set.remove(&f) {
+                        self.removed_faces.push(f);
+                    }
+                }
+            }
+        }
+        for &i_tet in cavity.iter() {
+            self.tet2flag[i_tet] = false;
+            self.tet2vtx[i_tet] = [usize::MAX; 4];
+            self.tet2tet[i_tet] = [usize::MAX; 4];
+            self.free_tets.push(i_tet);
+        }
+        // connect the boundary faces to the point
+        let mut edge2tet = std::collections::HashMap::<(usize, usize), (usize, usize)>::new();
+        for ([a, b, c], j_tet) in faces {
+            let i_tet = self.free_tets.pop().unwrap_or_else(|| {
+                self.tet2vtx.push([usize::MAX; 4]);
+                self.tet2tet.push([usize::MAX; 4]);
+                self.tet2flag.push(false);
+                self.tet2vtx.len() - 1
+            });
+            self.tet2vtx[i_tet] = [a, c, b, i_vtx];
+            self.tet2tet[i_tet][3] = j_tet;
+            let j_node = (0..4)
+                .find(|&j_node| ![a, b, c].contains(&self.tet2vtx[j_tet][j_node]))
+                .unwrap();
+            self.tet2tet[j_tet][j_node] = i_tet;
+            for (i_node, [v0, v1]) in [(0, [c, b]), (1, [a, b]), (2, [a, c])] {
+                let key = (v0.min(v1), v0.max(v1));
+                if let Some((k_tet, k_node)) = edge2tet.remove(&key) {
+                    self.tet2tet[i_tet][i_node] = k_tet;
+                    self.tet2tet[k_tet][k_node] = i_tet;
+                } else {
+                    edge2tet.insert(key, (i_tet, i_node));
+                }
+            }
+            if ![a, b, c].contains(&INF) {
+                self.i_tet_hint = i_tet;
+                if let Some(face_set) = self.face_set.as_mut() {
+                    for f in [[a, b, i_vtx], [b, c, i_vtx], [c, a, i_vtx]] {
+                        face_set.insert(sorted_face(&f));
+                    }
+                }
+            }
+        }
+        assert!(edge2tet.is_empty());
+        i_vtx
+    }
+
+    /// finite tetrahedra that are not deleted
+    fn alive_tets(&self) -> impl Iterator<Item = usize> + '_ {
+        (0..self.tet2vtx.len())
+            .filter(|&i_tet| self.tet2vtx[i_tet][0] != usize::MAX && !self.is_ghost(i_tet))
+    }
+
+    /// start maintaining the faces of the finite tetrahedra
+    fn build_face_set(&mut self) {
+        let face_set = self
+            .alive_tets()
+            .flat_map(|i_tet| (0..4).map(move |i_node| (i_tet, i_node)))
+            .map(|(i_tet, i_node)| sorted_face(&self.face(i_tet, i_node)))
+            .collect();
+        self.face_set = Some(face_set);
+        self.removed_faces.clear();
+    }
+}
+
+/// Delaunay tetrahedralization of the convex hull of points.
+/// The overlapping points are not used.
+/// The outside of the convex hull is handled by the vertex at infinity,
+/// so the tetrahedra cover the convex hull even if it has nearly flat faces.
+///
+/// # Returns
+/// `tet2vtx` with the positive volume for each tetrahedron.
+/// Empty if all the points are on a plane
+pub fn tetrahedralize_points<Real>(vtx2xyz: &[Real]) -> Vec<usize>
+where
+    Real: num_traits::Float + 'static,
+    f64: AsPrimitive<Real>,
+{
+    let num_vtx = vtx2xyz.len() / 3;
+    let Some(mut dt) = Delaunay3::new(vtx2xyz.chunks(3).map(|p| [p[0], p[1], p[2]]).collect())
+    else {
+        return vec![];
+    };
+    for i_vtx in 0..num_vtx {
+        dt.insert(i_vtx);
+    }
+    dt.alive_tets()
+        .flat_map(|i_tet| dt.tet2vtx[i_tet])
+        .collect()
+}
+
+/// tetrahedral mesh generated by `tetrahedralize_closed_surface`
+pub struct TetMeshOfClosedSurface<Real> {
+    pub tet2vtx: Vec<usize>,
+    /// the vertices of the input come first with the same indices, followed by the points added on the surface
+    pub vtx2xyz: Vec<Real>,
+    /// triangles on the boundary of the tetrahedra, i.e., the input triangles split by the points added on their edges.
+    /// The orientation is the same as the input triangle, and the duplicated vertices are replaced by the merged ones
+    pub tri2vtx: Vec<usize>,
+    /// index of the input triangle that includes each boundary triangle
+    pub tri2parent: Vec<usize>,
+}
+
+/// tetrahedral mesh of the interior of a closed triangle mesh (conforming Delaunay tetrahedralization).
+/// The edges of the triangles that are not in the Delaunay tetrahedralization are split at their middle points
+/// until all the (split) triangles appear as the faces of the tetrahedra.
+/// The triangles need to be oriented consistently, either outward or inward.
+///
+/// This is not a constrained tetrahedralization: the boundary of the output is not the input triangles
+/// but their subdivision, which is returned with the parent input triangles.
+/// The vertices of the input at exactly the same position are merged, and the duplicated ones are not used.
+///
+/// # Returns
+/// `None` if the triangles cannot be recovered by the bisection. This happens not only for the self-intersecting
+/// meshes but also for some valid closed surfaces (e.g., with very sharp dihedral angles),
+/// where the split edges become shorter than the tolerance before the triangles are recovered.
+pub fn tetrahedralize_closed_surface<Real>(
+    tri2vtx: &[usize],
+    vtx2xyz: &[Real],
+) -> Option<TetMeshOfClosedSurface<Real>>
+where
+    Real: num_traits::Float + 'static,
+    f64: AsPrimitive<Real>,
+{
+    use std::collections::HashMap;
+    let num_vtx = vtx2xyz.len() / 3;
+    let mut dt = Delaunay3::new(vtx2xyz.chunks(3).map(|p| [p[0], p[1], p[2]]).collect())?;
+    // the duplicated vertex is merged to the vertex in the tetrahedralization
+    let vtx2merged: Vec<usize> = (0..num_vtx).map(|i_vtx| dt.insert(i_vtx)).collect();
+    dt.build_face_set();
+    let len_min = {
+        let aabb = crate::vtx2xyz::aabb3(vtx2xyz, Real::zero());
+        del_geo_core::edge3::length(
+            arrayref::array_ref![aabb, 0, 3],
+            arrayref::array_ref![aabb, 3, 3],
+        ) * Real::epsilon().sqrt()
+    };
+    let edge = |a: usize, b: usize| (a.min(b), a.max(b));
+    // triangles of the surface split by the points added, and their parent input triangles.
+    // The triangles degenerated by merging the vertices are skipped
+    let (mut subfaces, mut sub2tri): (Vec<[usize; 3]>, Vec<usize>) = tri2vtx
+        .chunks(3)
+        .enumerate()
+        .map(|(i_tri, f)| ([f[0], f[1], f[2]].map(|i_vtx| vtx2merged[i_vtx]), i_tri))
+        .filter(|(f, _)| f[0] != f[1] && f[1] != f[2] && f[2] != f[0])
+        .unzip();
+    let mut face2sub: HashMap<[usize; 3], usize> = HashMap::new();
+    let mut edge2subs: HashMap<(usize, usize), Vec<usize>> = HashMap::new();
+    for (i_sub, f) in subfaces.iter().enumerate() {
+        face2sub.insert(sorted_face(f), i_sub);
+        for i in 0..3 {
+            edge2subs
+                .entry(edge(f[i], f[(i + 1) % 3]))
+                .or_default()
+                .push(i_sub);
+        }
+    }
+    // subfaces that may be missing in the tetrahedralization
+    let mut stack: Vec<usize> = (0..subfaces.len()).collect();
+    while let Some(i_sub) = stack.pop() {
+        let f = subfaces[i_sub];
+        if dt.face_set.as_ref().unwrap().contains(&sorted_face(&f)) {
+            continue;
+        }
+        // split the longest edge
+        let (a, b) = (0..3)
+            .map(|i| (f[i], f[(i + 1) % 3]))
+            .max_by(|&(a0, b0), &(a1, b1)| {
+                let l0 = del_geo_core::edge3::squared_length(&dt.vtx2xyz[a0], &dt.vtx2xyz[b0]);
+                let l1 = del_geo_core::edge3::squared_length(&dt.vtx2xyz[a1], &dt.vtx2xyz[b1]);
+                l0.partial_cmp(&l1).unwrap()
+            })
+            .unwrap();
+        let (pa, pb) = (dt.vtx2xyz[a], dt.vtx2xyz[b]);
+        if del_geo_core::edge3::length(&pa, &pb) < len_min {
+            return None;
+        }
+        let i_vtx = dt.vtx2xyz.len();
+        dt.vtx2xyz
+            .push(std::array::from_fn(|i| (pa[i] + pb[i]) * 0.5f64.as_()));
+        for j_sub in edge2subs.remove(&edge(a, b)).unwrap() {
+            let g = subfaces[j_sub];
+            let i = (0..3)
+                .find(|&i| edge(g[i], g[(i + 1) % 3]) == edge(a, b))
+                .unwrap();
+            let (v0, v1, v2) = (g[i], g[(i + 1) % 3], g[(i + 2) % 3]);
+            let k_sub = subfaces.len();
+            face2sub.remove(&sorted_face(&g));
+            subfaces[j_sub] = [v0, i_vtx, v2];
+            subfaces.push([i_vtx, v1, v2]);
+            sub2tri.push(sub2tri[j_sub]);
+            face2sub.insert(sorted_face(&subfaces[j_sub]), j_sub);
+            face2sub.insert(sorted_face(&subfaces[k_sub]), k_sub);
+            let subs = edge2subs.get_mut(&edge(v1, v2)).unwrap();
+            *subs.iter_mut().find(|s| **s == j_sub).unwrap() = k_sub;
+            edge2subs.entry(edge(v0, i_vtx)).or_default().push(j_sub);
+            edge2subs.entry(edge(i_vtx, v1)).or_default().push(k_sub);
+            edge2subs
+                .entry(edge(i_vtx, v2))
+                .or_default()
+                .extend([j_sub, k_sub]);
+            stack.extend([j_sub, k_sub]);
+        }
+        if dt.insert(i_vtx) != i_vtx {
+            return None;
+        }
+        // the subfaces destroyed by the insertion
+        for f in std::mem::take(&mut dt.removed_faces) {
+            if let Some(&j_sub) = face2sub.get(&f) {
+                stack.push(j_sub);
+            }
+        }
+    }
+    // label the regions separated by the surface. The side of a tetrahedron against the adjacent subface is decided
+    // by the exact predicate, so the thin tetrahedra between the subfaces and the convex hull are labeled correctly
+    let is_outward = tri2vtx
+        .chunks(3)
+        .map(|f| det3(&[f[0], f[1], f[2]].map(|i_vtx| dt.vtx2xyz[i_vtx])).0)
+        .fold(Real::zero(), |a, b| a + b)
+        > Real::zero();
+    let mut tet2inside = vec![false; dt.tet2vtx.len()];
+    let mut tet2visited = vec![false; dt.tet2vtx.len()];
+    let tets: Vec<usize> = dt.alive_tets().collect();
+    for &i_tet_ker in tets.iter() {
+        if tet2visited[i_tet_ker] {
+            continue;
+        }
+        tet2visited[i_tet_ker] = true;
+        let mut stack = vec![i_tet_ker];
+        let mut region = vec![];
+        // the region not bounded by the surface is outside
+        let mut is_inside = false;
+        while let Some(i_tet) = stack.pop() {
+            region.push(i_tet);
+            for i_node in 0..4 {
+                let j_tet = dt.tet2tet[i_tet][i_node];
+                if let Some(&j_sub) = face2sub.get(&sorted_face(&dt.face(i_tet, i_node))) {
+                    let [a, b, c] = subfaces[j_sub].map(|i_vtx| &dt.vtx2xyz[i_vtx]);
+                    let d = &dt.vtx2xyz[dt.tet2vtx[i_tet][i_node]];
+                    is_inside = (orient3d(a, b, c, d) < 0) == is_outward;
+                    continue;
+                }
+                if dt.is_ghost(j_tet) || tet2visited[j_tet] {
+                    continue;
+                }
+                tet2visited[j_tet] = true;
+                stack.push(j_tet);
+            }
+        }
+        for i_tet in region {
+            tet2inside[i_tet] = is_inside;
+        }
+    }
+    let tet2vtx: Vec<usize> = tets
+        .iter()
+        .filter(|&&i_tet| tet2inside[i_tet])
+        .flat_map(|&i_tet| dt.tet2vtx[i_tet])
+        .collect();
+    let vtx2xyz: Vec<Real> = dt.vtx2xyz.iter().flat_map(|p| *p).collect();
+    Some(TetMeshOfClosedSurface {
+        tet2vtx,
+        vtx2xyz,
+        tri2vtx: subfaces.into_iter().flatten().collect(),
+        tri2parent: sub2tri,
+    })
+}
+
+#[cfg(test)]
+fn volume_of_tet<Real: num_traits::Float>(
+    tet2vtx: &[usize],
+    vtx2xyz: &[Real],
+    i_tet: usize,
+) -> Real {
+    let p = |i: usize| arrayref::array_ref![vtx2xyz, tet2vtx[i_tet * 4 + i] * 3, 3];
+    let r: [[Real; 3]; 3] = std::array::from_fn(|i| std::array::from_fn(|j| p(i + 1)[j] - p(0)[j]));
+    det3(&r).0 / Real::from(6).unwrap()
+}
+
+#[test]
+fn test_predicates() {
+    let a = [0., 0., 0.];
+    let b = [1., 0., 0.];
+    let c = [0., 1., 0.];
+    let d = [0., 0., 1.];
+    assert_eq!(orient3d(&a, &b, &c, &d), 1);
+    assert_eq!(orient3d(&a, &c, &b, &d), -1);
+    assert_eq!(insphere(&a, &b, &c, &d, &[0.25, 0.25, 0.25]), 1);
+    assert_eq!(insphere(&a, &b, &c, &d, &[5., 5., 5.]), -1);
+    // exactly on the plane / sphere
+    assert_eq!(orient3d(&a, &b, &c, &[0.1, 0.7, 0.]), 0);
+    assert_eq!(insphere(&a, &b, &c, &d, &[1., 1., 1.]), 0);
+    // nearly degenerate cases where the floating point evaluation is not reliable
+    let e = [0.1, 0.7, 1.0e-30];
+    assert_eq!(orient3d(&a, &b, &c, &e), 1);
+    let s = 1.0e+10;
+    let (a, b, c) = ([s, s, s], [s + 1., s, s], [s, s + 1., s]);
+    assert_eq!(orient3d(&a, &b, &c, &[s + 0.5, s + 0.5, s]), 0);
+    assert_eq!(orient3d(&a, &b, &c, &[s + 0.5, s + 0.5, s + 1.0e-5]), 1);
+}
+
+#[test]
+fn test_tetrahedralize_points() {
+    use rand::Rng;
+    use rand::SeedableRng;
+    let mut rng = rand_chacha::ChaChaRng::seed_from_u64(0);
+    // lattice points (many co-spherical points) and random points in a cube
+    let mut vtx2xyz: Vec<f64> = vec![];
+    for i in 0..27 {
+        vtx2xyz.extend([
+            (i % 3) as f64 - 1.,
+            ((i / 3) % 3) as f64 - 1.,
+            (i / 9) as f64 - 1.,
+        ]);
+    }
+    for _ in 0..200 {
+        vtx2xyz.extend([0; 3].map(|_| rng.random::<f64>() * 2. - 1.));
+    }
+    vtx2xyz.extend([0., 0., 0.]); // duplicated point
+    let tet2vtx = tetrahedralize_points(&vtx2xyz);
+    let num_tet = tet2vtx.len() / 4;
+    let mut volume = 0.;
+    for i_tet in 0..num_tet {
+        let v = volume_of_tet(&tet2vtx, &vtx2xyz, i_tet);
+        assert!(v > 0.);
+        volume += v;
+        let [a, b, c, d] =
+            std::array::from_fn(|i| arrayref::array_ref![vtx2xyz, tet2vtx[i_tet * 4 + i] * 3, 3]);
+        for p in vtx2xyz.chunks(3) {
+            let p = arrayref::array_ref![p, 0, 3];
+            assert!(insphere(a, b, c, d, p) <= 0);
+        }
+    }
+    assert!((volume - 8.).abs() < 1.0e-10, "{volume}");
+}
+
+#[test]
+fn test_tetrahedralize_points_flat_hull() {
+    use rand::Rng;
+    use rand::SeedableRng;
+    let mut reng = rand_chacha::ChaChaRng::seed_from_u64(0);
+    // points in a thin slab, so the circumspheres of the tetrahedra are huge
+    let vtx2xyz: Vec<f64> = (0..300)
+        .flat_map(|_| {
+            [
+                reng.random::<f64>() * 2. - 1.,
+                reng.random::<f64>() * 2. - 1.,
+                reng.random::<f64>() * 1.0e-4,
+            ]
+        })
+        .collect();
+    let tet2vtx = tetrahedralize_points(&vtx2xyz);
+    let mut volume = 0.;
+    for i_tet in 0..tet2vtx.len() / 4 {
+        let [a, b, c, d] =
+            std::array::from_fn(|i| arrayref::array_ref![vtx2xyz, tet2vtx[i_tet * 4 + i] * 3, 3]);
+        assert_eq!(orient3d(a, b, c, d), 1);
+        volume += volume_of_tet(&tet2vtx, &vtx2xyz, i_tet);
+    }
+    // the tetrahedra cover the convex hull
+    let tri2vtx = crate::convexhull3::convex_hull3(&vtx2xyz);
+    let volume_hull = tri2vtx
+        .chunks(3)
+        .map(|tri| {
+            let p = |i: usize| arrayref::array_ref![vtx2xyz, tri[i] * 3, 3];
+            det3(&[*p(0), *p(1), *p(2)]).0 / 6.
+        })
+        .sum::<f64>();
+    assert!(
+        (volume - volume_hull).abs() < 1.0e-10,
+        "{volume} {volume_hull}"
+    );
+    // the boundary of the tetrahedra is convex, i.e., no point is in front of a boundary face
+    let tet2tet = crate::tetmesh::tet2tet(&tet2vtx, vtx2xyz.len() / 3);
+    let (tri2vtx_bnd, _) = crate::tetmesh::boundary_tri2vtx(&tet2vtx, &tet2tet);
+    for tri in tri2vtx_bnd.chunks(3) {
+        let p = |i: usize| arrayref::array_ref![vtx2xyz, i * 3, 3];
+        for i_vtx in 0..vtx2xyz.len() / 3 {
+            assert!(orient3d(p(tri[0]), p(tri[1]), p(tri[2]), p(i_vtx)) <= 0);
+        }
+    }
+    // all the points are coplanar
+    let vtx2xyz: Vec<f64> = (0..9)
+        .flat_map(|i| [(i % 3) as f64, (i / 3) as f64, 0.])
+        .collect();
+    assert!(tetrahedralize_points(&vtx2xyz).is_empty());
+}
+
+#[test]
+fn test_tetrahedralize_closed_surface() {
+    let (tri2vtx_sphere, vtx2xyz_sphere) =
+        crate::trimesh3_primitive::sphere_yup::<usize, f64>(1.0, 8, 16);
+    // each triangle has its own vertices as in the STL format
+    let (tri2vtx_stl, vtx2xyz_stl) = {
+        let vtx2xyz: Vec<f64> = tri2vtx_sphere
+            .iter()
+            .flat_map(|&i_vtx| crate::vtx2xyz::to_vec3(&vtx2xyz_sphere, i_vtx))
+            .copied()
+            .collect();
+        ((0..tri2vtx_sphere.len()).collect::<Vec<usize>>(), vtx2xyz)
+    };
+    for (i_shape, (tri2vtx, vtx2xyz)) in [
+        (tri2vtx_sphere, vtx2xyz_sphere),
+        crate::trimesh3_primitive::torus_zup::<usize, f64>(1.0, 0.3, 24, 8),
+        (tri2vtx_stl, vtx2xyz_stl),
+    ]
+    .into_iter()
+    .enumerate()
+    {
+        let tm = tetrahedralize_closed_surface(&tri2vtx, &vtx2xyz).unwrap();
+        let (tet2vtx, vtx2xyz_tet) = (&tm.tet2vtx, &tm.vtx2xyz);
+        assert_eq!(vtx2xyz_tet[..vtx2xyz.len()], vtx2xyz[..]);
+        let mut volume = 0.;
+        for i_tet in 0..tet2vtx.len() / 4 {
+            let [a, b, c, d] = std::array::from_fn(|i| {
+                arrayref::array_ref![vtx2xyz_tet, tet2vtx[i_tet * 4 + i] * 3, 3]
+            });
+            // the points added on a flat part of the surface may make a very thin tetrahedron
+            assert_eq!(orient3d(a, b, c, d), 1);
+            volume += volume_of_tet(tet2vtx, vtx2xyz_tet, i_tet);
+        }
+        // volume enclosed by the surface
+        let volume_of_tri = |tri2vtx: &[usize], vtx2xyz: &[f64], i_tri: usize| {
+            let p = |i: usize| arrayref::array_ref![vtx2xyz, tri2vtx[i_tri * 3 + i] * 3, 3];
+            det3(&[*p(0), *p(1), *p(2)]).0 / 6.
+        };
+        let volume_surf = (0..tri2vtx.len() / 3)
+            .map(|i_tri| volume_of_tri(&tri2vtx, &vtx2xyz, i_tri))
+            .sum::<f64>()
+            .abs();
+        assert!(
+            (volume - volume_surf).abs() < 1.0e-10,
+            "{volume} {volume_surf}"
+        );
+        // the boundary triangles are the faces on the boundary of the tetrahedra
+        let tet2tet = crate::tetmesh::tet2tet(tet2vtx, vtx2xyz_tet.len() / 3);
+        let (tri2vtx_bnd, _) = crate::tetmesh::boundary_tri2vtx(tet2vtx, &tet2tet);
+        let sorted_faces = |tri2vtx: &[usize]| {
+            let mut faces: Vec<[usize; 3]> = tri2vtx
+                .chunks(3)
+                .map(|f| sorted_face(&[f[0], f[1], f[2]]))
+                .collect();
+            faces.sort();
+            faces
+        };
+        assert_eq!(sorted_faces(&tri2vtx_bnd), sorted_faces(&tm.tri2vtx));
+        // the boundary triangles subdivide their parents with the same orientation
+        let mut tri2volume = vec![0.; tri2vtx.len() / 3];
+        for (i_sub, &i_tri) in tm.tri2parent.iter().enumerate() {
+            tri2volume[i_tri] += volume_of_tri(&tm.tri2vtx, vtx2xyz_tet, i_sub);
+        }
+        for (i_tri, v) in tri2volume.iter().enumerate() {
+            assert!((v - volume_of_tri(&tri2vtx, &vtx2xyz, i_tri)).abs() < 1.0e-10);
+        }
+        let mut file =
+            std::fs::File::create(format!("../target/tetmesh_delaunay_{i_shape}.vtk")).unwrap();
+        crate::io_vtk::write_vtk_points(&mut file, "hoge", vtx2xyz_tet, 3).unwrap();
+        crate::io_vtk::write_vtk_cells(&mut file, crate::io_vtk::VtkElementType::TETRA, tet2vtx)
+            .unwrap();
+    }
+}
@@ -1,3 +1,5 @@
+use num_traits::AsPrimitive;
+
 pub fn position_from_barycentric_coordinate<Real, const N: usize>(
     tri2vtx: &[usize],
     vtx2xyz: &[Real],
@@ -79,3 +81,203 @@ where
     }
     (i_tri_l, r0, val01_b)
 }
+
+// ---------------------------------
+// below: quality
+
+/// distribution of a per-triangle measure
+#[derive(Debug, Clone)]
+pub struct Histogram<Real> {
+    pub min: Real,
+    pub max: Real,
+    /// the triangle with the minimum value
+    pub i_tri_min: usize,
+    /// the triangle with the maximum value
+    pub i_tri_max: usize,
+    /// number of the triangles in the uniform bins between `min` and the largest finite value.
+    /// Non-finite values (e.g., the aspect ratio of a degenerate triangle) are counted in the last bin
+    pub bin2count: Vec<usize>,
+}
+
+fn histogram<Real>(tri2val: &[Real], num_bin: usize) -> Histogram<Real>
+where
+    Real: num_traits::Float + 'static,
+    usize: AsPrimitive<Real>,
+{
+    assert!(num_bin > 0);
+    let mut h = Histogram {
+        min: Real::infinity(),
+        max: Real::neg_infinity(),
+        i_tri_min: usize::MAX,
+        i_tri_max: usize::MAX,
+        bin2count: vec![0; num_bin],
+    };
+    let mut max_finite = Real::neg_infinity();
+    for (i_tri, &val) in tri2val.iter().enumerate() {
+        if val < h.min || h.i_tri_min == usize::MAX {
+            (h.min, h.i_tri_min) = (val, i_tri);
+        }
+        if val > h.max || h.i_tri_max == usize::MAX {
+            (h.max, h.i_tri_max) = (val, i_tri);
+        }
+        if val.is_finite() {
+            max_finite = max_finite.max(val);
+        }
+    }
+    for &val in tri2val {
+        let i_bin = if !val.is_finite() {
+            num_bin - 1
+        } else if max_finite > h.min {
+            let r = (val - h.min) / (max_finite - h.min) * num_bin.as_();
+            r.to_usize().unwrap_or(0).min(num_bin - 1)
+        } else {
+            0
+        };
+        h.bin2count[i_bin] += 1;
+    }
+    h
+}
+
+/// quality of a triangle mesh
+#[derive(Debug, Clone)]
+pub struct Quality<Real> {
+    /// the minimum inner angle (in radian) of each triangle
+    pub min_angle: Histogram<Real>,
+    /// the maximum inner angle (in radian) of each triangle
+    pub max_angle: Histogram<Real>,
+    /// `r_circum / (2 * r_in)`. This is one for the equilateral triangle and infinity for a degenerate triangle
+    pub aspect_ratio: Histogram<Real>,
+    /// the longest edge length divided by the shortest edge length
+    pub edge_length_ratio: Histogram<Real>,
+    /// signed area in 2D and unsigned area in 3D
+    pub area: Histogram<Real>,
+    /// the triangles with the smallest minimum angles, in ascending order of the minimum angle
+    pub worst_tris: Vec<usize>,
+}
+
+/// evaluate the quality of the triangles. See `trimesh2::quality` and `trimesh3::quality`
+/// * `num_bin` - number of the bins of the histograms
+/// * `num_worst` - number of the worst triangles to report
+pub fn quality<Real>(
+    tri2vtx: &[usize],
+    vtx2xyz: &[Real],
+    num_dim: usize,
+    num_bin: usize,
+    num_worst: usize,
+) -> Quality<Real>
+where
+    Real: num_traits::Float + 'static,
+    usize: AsPrimitive<Real>,
+    f64: AsPrimitive<Real>,
+{
+    assert!(num_dim == 2 || num_dim == 3);
+    let num_tri = tri2vtx.len() / 3;
+    let mut tri2min_angle = Vec::<Real>::with_capacity(num_tri);
+    let mut tri2max_angle = Vec::<Real>::with_capacity(num_tri);
+    let mut tri2aspect_ratio = Vec::<Real>::with_capacity(num_tri);
+    let mut tri2edge_length_ratio = Vec::<Real>::with_capacity(num_tri);
+    let mut tri2area = Vec::<Real>::with_capacity(num_tri);
+    for i_tri in 0..num_tri {
+        let area = if num_dim == 2 {
+            crate::trimesh2::to_tri2(i_tri, tri2vtx, vtx2xyz).area()
+        } else {
+            crate::trimesh3::to_tri3(tri2vtx, vtx2xyz, i_tri).area()
+        };
+        // length of the edge opposite to each node
+        let lens: [Real; 3] = std::array::from_fn(|i_node| {
+            let i0 = tri2vtx[i_tri * 3 + (i_node + 1) % 3];
+            let i1 = tri2vtx[i_tri * 3 + (i_node + 2) % 3];
+            (0..num_dim)
+                .map(|i_dim| vtx2xyz[i0 * num_dim + i_dim] - vtx2xyz[i1 * num_dim + i_dim])
+                .fold(Real::zero(), |s, d| s + d * d)
+                .sqrt()
+        });
+        let angles: [Real; 3] = std::array::from_fn(|i_node| {
+            let (a, b, c) = (lens[i_node], lens[(i_node + 1) % 3], lens[(i_node + 2) % 3]);
+            if b * c == Real::zero() {
+                return Real::zero();
+            }
+            let cos = (b * b + c * c - a * a) / (b * c * 2f64.as_());
+            cos.max(-Real::one()).min(Real::one()).acos()
+        });
+        let len_min = lens[0].min(lens[1]).min(lens[2]);
+        let len_max = lens[0].max(lens[1]).max(lens[2]);
+        // r_circum = abc / 4A, r_in = 2A / (a+b+c)
+        let area_abs = area.abs();
+        let aspect_ratio = if area_abs == Real::zero() {
+            Real::infinity()
+        } else {
+            lens[0] * lens[1] * lens[2] * (lens[0] + lens[1] + lens[2])
+                / (area_abs * area_abs * 16f64.as_())
+        };
+        tri2min_angle.push(angles[0].min(angles[1]).min(angles[2]));
+        tri2max_angle.push(angles[0].max(angles[1]).max(angles[2]));
+        tri2aspect_ratio.push(aspect_ratio);
+        tri2edge_length_ratio.push(if len_min == Real::zero() {
+            Real::infinity()
+        } else {
+            len_max / len_min
+        });
+        tri2area.push(area);
+    }
+    let mut worst_tris: Vec<usize> = (0..num_tri).collect();
+    worst_tris.sort_by(|&i, &j| tri2min_angle[i].partial_cmp(&tri2min_angle[j]).unwrap());
+    worst_tris.truncate(num_worst);
+    Quality {
+        min_angle: histogram(&tri2min_angle, num_bin),
+        max_angle: histogram(&tri2max_angle, num_bin),
+        aspect_ratio: histogram(&tri2aspect_ratio, num_bin),
+        edge_length_ratio: histogram(&tri2edge_length_ratio, num_bin),
+        area: histogram(&tri2area, num_bin),
+        worst_tris,
+    }
+}
+
+#[test]
+fn test_quality() {
+    // right isosceles triangles in 2D
+    let (tri2vtx, vtx2xy) = {
+        let (quad2vtx, vtx2xy) = crate::quadmesh::from_grid::<f64>(4, 3);
+        (crate::tri2vtx::from_quad_mesh(&quad2vtx), vtx2xy)
+    };
+    let q = crate::trimesh2::quality(&tri2vtx, &vtx2xy, 10, 3);
+    let pi = std::f64::consts::PI;
+    assert!((q.min_angle.min - pi / 4.).abs() < 1.0e-10);
+    assert!((q.min_angle.max - pi / 4.).abs() < 1.0e-10);
+    assert!((q.max_angle.max - pi / 2.).abs() < 1.0e-10);
+    // r_circum = sqrt(2)/2, r_in = (2-sqrt(2))/2
+    let ar = 2f64.sqrt() / (2. * (2. - 2f64.sqrt()));
+    assert!((q.aspect_ratio.min - ar).abs() < 1.0e-10);
+    assert!((q.edge_length_ratio.max - 2f64.sqrt()).abs() < 1.0e-10);
+    assert!((q.area.min - 0.5).abs() < 1.0e-10);
+    assert_eq!(q.min_angle.bin2count[0], tri2vtx.len() / 3);
+    assert_eq!(q.worst_tris.len(), 3);
+    // the area is signed in 2D
+    let mut tri2vtx = tri2vtx;
+    tri2vtx.swap(7 * 3 + 1, 7 * 3 + 2);
+    let q = crate::trimesh2::quality(&tri2vtx, &vtx2xy, 10, 3);
+    assert!((q.area.min + 0.5).abs() < 1.0e-10);
+    assert_eq!(q.area.i_tri_min, 7);
+    assert_eq!(q.area.bin2count[0], 1);
+    assert_eq!(q.area.bin2count[9], tri2vtx.len() / 3 - 1);
+    // sphere in 3D with a sliver and a degenerate triangle
+    let (mut tri2vtx, mut vtx2xyz) = crate::trimesh3_primitive::sphere_yup::<usize, f64>(1., 16, 8);
+    let num_vtx = vtx2xyz.len() / 3;
+    vtx2xyz.extend([0., 0., 0., 1., 0., 0., 0.5, 0.01, 0., 2., 0., 0.]);
+    tri2vtx.extend([num_vtx, num_vtx + 1, num_vtx + 2]);
+    tri2vtx.extend([num_vtx, num_vtx + 1, num_vtx + 3]);
+    let num_tri = tri2vtx.len() / 3;
+    let q = crate::trimesh3::quality(&tri2vtx, &vtx2xyz, 8, 2);
+    assert_eq!(q.worst_tris, vec![num_tri - 1, num_tri - 2]);
+    assert_eq!(q.min_angle.min, 0.);
+    assert!((q.max_angle.max - pi).abs() < 1.0e-10);
+    assert_eq!(q.aspect_ratio.max, f64::INFINITY);
+    assert_eq!(q.aspect_ratio.i_tri_max, num_tri - 1);
+    assert_eq!(q.area.i_tri_min, num_tri - 1);
+    assert_eq!(q.area.bin2count.iter().sum::<usize>(), num_tri);
+    assert_eq!(q.aspect_ratio.bin2count[7], 2);
+    // the sliver is the worst among the non-degenerate triangles
+    let q = crate::trimesh3::quality(&tri2vtx[..(num_tri - 1) * 3], &vtx2xyz, 8, 1);
+    assert_eq!(q.worst_tris, vec![num_tri - 2]);
+    assert_eq!(q.aspect_ratio.i_tri_max, num_tri - 2);
+}
@@ -53,6 +53,22 @@ pub fn tri2circumcenter(tri2vtx: &[usize], vtx2xyz: &[f32]) -> Vec<f32> {
     tri2cc
 }
 
+/// histograms of the angles, aspect ratios, edge-length ratios and signed areas of the triangles.
+/// See `trimesh::quality` for the detail
+pub fn quality<Real>(
+    tri2vtx: &[usize],
+    vtx2xy: &[Real],
+    num_bin: usize,
+    num_worst: usize,
+) -> crate::trimesh::Quality<Real>
+where
+    Real: num_traits::Float + 'static,
+    usize: AsPrimitive<Real>,
+    f64: AsPrimitive<Real>,
+{
+    crate::trimesh::quality(tri2vtx, vtx2xy, 2, num_bin, num_worst)
+}
+
 pub fn search_bruteforce_one_triangle_include_input_point<Index, Real>(
     q: &[Real; 2],
     tri2vtx: &[Index],
@@ -0,0 +1,1183 @@
+//! constrained Delaunay triangulation of a planar straight-line graph (PSLG)
+//! that consists of closed loops (outer boundaries and holes), internal constraint edges and isolated points.
+//!
+//! As in `trimesh2_dynamic::enforce_edge`, the adjacency across a constrained edge is cut
+//! (i.e., `tri2tri` is `usize::MAX` there) so that the later topological changes never flip it.
+//! The triangulation can be refined with `refine` to guarantee the quality of the triangles.
+
+use num_traits::AsPrimitive;
+
+/// PSLG triangulated by `triangulate_pslg`
+pub struct ConstrainedDelaunay {
+    pub tri2vtx: Vec<usize>,
+    /// adjacent triangle for each triangle edge. `usize::MAX` on the boundary and on the constrained edges
+    pub tri2tri: Vec<usize>,
+    /// one of the triangle around the vertex. `usize::MAX` if the vertex is not used (e.g., isolated point outside)
+    pub vtx2tri: Vec<usize>,
+    /// index of the region (part of the domain enclosed by the constrained edges) for each triangle
+    pub tri2region: Vec<usize>,
+    /// index of the innermost loop that contains the region.
+    /// `usize::MAX` if there is no loop (i.e., triangulation of the convex hull)
+    pub region2loop: Vec<usize>,
+}
+
+/// if the point is inside the loop using the crossing number
+fn is_inside_loop<Real>(
+    p: &[Real; 2],
+    i_loop: usize,
+    loop2idx: &[usize],
+    idx2vtx: &[usize],
+    vtx2xy: &[[Real; 2]],
+) -> bool
+where
+    Real: num_traits::Float,
+{
+    let num_idx = loop2idx[i_loop + 1] - loop2idx[i_loop];
+    let mut is_inside = false;
+    for iidx in 0..num_idx {
+        let a = vtx2xy[idx2vtx[loop2idx[i_loop] + iidx]];
+        let b = vtx2xy[idx2vtx[loop2idx[i_loop] + (iidx + 1) % num_idx]];
+        if (a[1] > p[1]) == (b[1] > p[1]) {
+            continue;
+        }
+        let x = a[0] + (p[1] - a[1]) * (b[0] - a[0]) / (b[1] - a[1]);
+        if p[0] < x {
+            is_inside = !is_inside;
+        }
+    }
+    is_inside
+}
+
+fn area_of_loop<Real>(
+    i_loop: usize,
+    loop2idx: &[usize],
+    idx2vtx: &[usize],
+    vtx2xy: &[[Real; 2]],
+) -> Real
+where
+    Real: num_traits::Float,
+{
+    let num_idx = loop2idx[i_loop + 1] - loop2idx[i_loop];
+    let p0 = vtx2xy[idx2vtx[loop2idx[i_loop]]];
+    let mut area = Real::zero();
+    for iidx in 1..num_idx {
+        let p1 = vtx2xy[idx2vtx[loop2idx[i_loop] + iidx]];
+        let p2 = vtx2xy[idx2vtx[loop2idx[i_loop] + (iidx + 1) % num_idx]];
+        area = area + del_geo_core::tri2::area(&p0, &p1, &p2);
+    }
+    area
+}
+
+/// constrained segments of the PSLG without duplication
+fn constrained_edges(loop2idx: &[usize], idx2vtx: &[usize], edge2vtx: &[usize]) -> Vec<[usize; 2]> {
+    let mut edges = Vec::<[usize; 2]>::new();
+    for i_loop in 0..loop2idx.len().saturating_sub(1) {
+        let num_idx = loop2idx[i_loop + 1] - loop2idx[i_loop];
+        for iidx in 0..num_idx {
+            edges.push([
+                idx2vtx[loop2idx[i_loop] + iidx],
+                idx2vtx[loop2idx[i_loop] + (iidx + 1) % num_idx],
+            ]);
+        }
+    }
+    edges.extend(edge2vtx.chunks(2).map(|e| [e[0], e[1]]));
+    let mut edges: Vec<[usize; 2]> = edges
+        .iter()
+        .filter(|&&[i0, i1]| i0 != i1)
+        .map(|&[i0, i1]| if i0 < i1 { [i0, i1] } else { [i1, i0] })
+        .collect();
+    edges.sort();
+    edges.dedup();
+    edges
+}
+
+/// if the edge `(a, b)` properly intersects with the segment `(p0, p1)`
+fn is_crossing<Real>(p0: &[Real; 2], p1: &[Real; 2], a: &[Real; 2], b: &[Real; 2]) -> bool
+where
+    Real: num_traits::Float,
+{
+    use del_geo_core::tri2::area;
+    let zero = Real::zero();
+    area(p0, p1, a) * area(p0, p1, b) < zero && area(a, b, p0) * area(a, b, p1) < zero
+}
+
+/// triangle and the index of its edge (as in `trimesh_topology::find_edge_by_looking_all_triangles`)
+/// between the two vertices in either direction, found by rotating around `i0_vtx`
+fn find_edge_around_point(
+    i0_vtx: usize,
+    i1_vtx: usize,
+    tri2vtx: &[usize],
+    tri2tri: &[usize],
+    vtx2tri: &[usize],
+) -> Option<(usize, usize)> {
+    use crate::trimesh_topology::find_edge_by_looking_around_point;
+    find_edge_by_looking_around_point(i0_vtx, i1_vtx, tri2vtx, tri2tri, vtx2tri)
+        .or_else(|| find_edge_by_looking_around_point(i1_vtx, i0_vtx, tri2vtx, tri2tri, vtx2tri))
+        .map(|(i_tri, i_node, _)| (i_tri, i_node))
+}
+
+/// walk from `i0_vtx` toward `i1_vtx` along the segment between them.
+/// The walk stops at `i1_vtx` or at the first point on the segment.
+/// Returns the point where the walk stops and the edges crossing the segment before it
+fn edges_crossing_segment<Real>(
+    i0_vtx: usize,
+    i1_vtx: usize,
+    tri2vtx: &[usize],
+    tri2tri: &[usize],
+    vtx2tri: &[usize],
+    vtx2xy: &[[Real; 2]],
+) -> (usize, Vec<[usize; 2]>)
+where
+    Real: num_traits::Float + 'static,
+    f64: AsPrimitive<Real>,
+{
+    use del_geo_core::tri2::area;
+    let p0 = vtx2xy[i0_vtx];
+    let p1 = vtx2xy[i1_vtx];
+    let d = [p1[0] - p0[0], p1[1] - p0[1]];
+    let eps: Real = 1.0e-10f64.as_();
+    let tol = (d[0] * d[0] + d[1] * d[1]) * eps;
+    // the point is on the segment ahead of `p0`
+    let is_on_segment = |i_vtx: usize| {
+        let p = &vtx2xy[i_vtx];
+        i_vtx == i1_vtx
+            || (area(&p0, &p1, p).abs() <= tol
+                && (p[0] - p0[0]) * d[0] + (p[1] - p0[1]) * d[1] > Real::zero())
+    };
+    // triangle around `i0_vtx` whose opposite edge crosses the segment
+    let (mut i_tri, mut i_node) = {
+        let i_tri0 = vtx2tri[i0_vtx];
+        let i_node0 = crate::trimesh_topology::find_node(i0_vtx, tri2vtx, i_tri0);
+        let mut res = None;
+        for is_cw in [true, false] {
+            let (mut i_tri, mut i_node) = (i_tri0, i_node0);
+            loop {
+                let i1 = tri2vtx[i_tri * 3 + (i_node + 1) % 3];
+                let i2 = tri2vtx[i_tri * 3 + (i_node + 2) % 3];
+                if is_on_segment(i1) {
+                    return (i1, vec![]);
+                }
+                if is_on_segment(i2) {
+                    return (i2, vec![]);
+                }
+                if area(&p0, &p1, &vtx2xy[i1]) < Real::zero()
+                    && area(&p0, &p1, &vtx2xy[i2]) > Real::zero()
+                {
+                    res = Some((i_tri, i_node));
+                    break;
+                }
+                let is_moved = if is_cw {
+                    crate::trimesh_topology::move_cw(
+                        &mut i_tri,
+                        &mut i_node,
+                        usize::MAX,
+                        tri2vtx,
+                        tri2tri,
+                    )
+                } else {
+                    crate::trimesh_topology::move_ccw(
+                        &mut i_tri,
+                        &mut i_node,
+                        usize::MAX,
+                        tri2vtx,
+                        tri2tri,
+                    )
+                };
+                if !is_moved || i_tri == i_tri0 {
+                    break;
+                }
+            }
+            if res.is_some() {
+                break;
+            }
+        }
+        res.expect("the segment goes outside")
+    };
+    let mut edges = vec![];
+    loop {
+        // the edge `(i_right, i_left)` opposite to `i_node` crosses the segment
+        let i_right = tri2vtx[i_tri * 3 + (i_node + 1) % 3];
+        let i_left = tri2vtx[i_tri * 3 + (i_node + 2) % 3];
+        edges.push([i_right, i_left]);
+        let j_tri = tri2tri[i_tri * 3 + i_node];
+        assert_ne!(j_tri, usize::MAX, "the constrained edges are crossing");
+        let j_node = crate::trimesh_topology::find_adjacent_edge_index(
+            arrayref::array_ref!(tri2vtx, i_tri * 3, 3),
+            arrayref::array_ref!(tri2tri, i_tri * 3, 3),
+            i_node,
+            tri2vtx,
+        );
+        let i_opp = tri2vtx[j_tri * 3 + j_node];
+        if is_on_segment(i_opp) {
+            return (i_opp, edges);
+        }
+        // the next crossing edge is opposite to the vertex on the same side as `i_opp`
+        let i_vtx_behind = if area(&p0, &p1, &vtx2xy[i_opp]) < Real::zero() {
+            i_right
+        } else {
+            i_left
+        };
+        i_tri = j_tri;
+        i_node = crate::trimesh_topology::find_node(i_vtx_behind, tri2vtx, j_tri);
+    }
+}
+
+/// make the edge between `i0_vtx` and `i1_vtx` by flipping the edges crossing it.
+/// The edge is split at the points on it, and the parts of the edge are returned.
+/// Unlike `trimesh2_dynamic::enforce_edge`, an edge is flipped only when the quadrilateral around it is convex
+/// so that no inverted triangle is made. The adjacency is not cut here so that the rotation around a point
+/// in the later recoveries is not blocked.
+fn recover_edge<Real>(
+    tri2vtx: &mut [usize],
+    tri2tri: &mut [usize],
+    vtx2tri: &mut [usize],
+    i0_vtx: usize,
+    i1_vtx: usize,
+    vtx2xy: &[[Real; 2]],
+) -> Vec<[usize; 2]>
+where
+    Real: num_traits::Float + 'static,
+    f64: AsPrimitive<Real>,
+{
+    let mut edges_recovered = vec![];
+    let mut i_start = i0_vtx;
+    while i_start != i1_vtx {
+        let (i_end, edges) =
+            edges_crossing_segment(i_start, i1_vtx, tri2vtx, tri2tri, vtx2tri, vtx2xy);
+        let p0 = vtx2xy[i_start];
+        let p1 = vtx2xy[i_end];
+        // flip the edges crossing the segment (Sloan's method)
+        let mut queue = std::collections::VecDeque::<[usize; 2]>::from(edges);
+        let mut num_skip = 0;
+        while let Some([j0_vtx, j1_vtx]) = queue.pop_front() {
+            let (i_tri, i_edge) =
+                find_edge_around_point(j0_vtx, j1_vtx, tri2vtx, tri2tri, vtx2tri).unwrap();
+            let i_node = (i_edge + 2) % 3;
+            let j_tri = tri2tri[i_tri * 3 + i_node];
+            assert_ne!(j_tri, usize::MAX, "the constrained edges are crossing");
+            let j_node = crate::trimesh_topology::find_adjacent_edge_index(
+                arrayref::array_ref!(tri2vtx, i_tri * 3, 3),
+                arrayref::array_ref!(tri2tri, i_tri * 3, 3),
+                i_node,
+                tri2vtx,
+            );
+            let k0_vtx = tri2vtx[i_tri * 3 + i_node];
+            let k1_vtx = tri2vtx[j_tri * 3 + j_node];
+            let q0 = vtx2xy[k0_vtx];
+            let q1 = vtx2xy[tri2vtx[i_tri * 3 + (i_node + 1) % 3]];
+            let q2 = vtx2xy[tri2vtx[i_tri * 3 + (i_node + 2) % 3]];
+            let r0 = vtx2xy[k1_vtx];
+            let is_convex = del_geo_core::tri2::area(&q0, &q1, &r0) > Real::zero()
+                && del_geo_core::tri2::area(&q0, &r0, &q2) > Real::zero();
+            if !is_convex {
+                queue.push_back([j0_vtx, j1_vtx]);
+                num_skip += 1;
+                assert!(
+                    num_skip <= queue.len(),
+                    "cannot recover the constrained edge"
+                );
+                continue;
+            }
+            num_skip = 0;
+            crate::trimesh_topology::flip_edge(i_tri, i_node, tri2vtx, tri2tri, vtx2tri);
+            if is_crossing(&p0, &p1, &q0, &r0) {
+                queue.push_back([k0_vtx, k1_vtx]);
+            }
+        }
+        edges_recovered.push([i_start, i_end]);
+        i_start = i_end;
+    }
+    edges_recovered
+}
+
+/// `trimesh2_dynamic::should_flip` that is robust against the co-circular points (e.g., the corners of a square)
+/// by requiring the opposite point to be clearly inside the circumcircle
+fn should_flip_strictly<Real>(
+    i_tri: usize,
+    i_node: usize,
+    tri2vtx: &[usize],
+    tri2tri: &[usize],
+    vtx2xy: &[[Real; 2]],
+) -> bool
+where
+    Real: num_traits::Float + std::fmt::Display + std::fmt::Debug,
+{
+    if !crate::trimesh2_dynamic::should_flip(i_tri, i_node, tri2vtx, tri2tri, vtx2xy) {
+        return false;
+    }
+    let j_tri = tri2tri[i_tri * 3 + i_node];
+    let j_node = crate::trimesh_topology::find_adjacent_edge_index(
+        arrayref::array_ref!(tri2vtx, i_tri * 3, 3),
+        arrayref::array_ref!(tri2tri, i_tri * 3, 3),
+        i_node,
+        tri2vtx,
+    );
+    let q = vtx2xy[tri2vtx[j_tri * 3 + j_node]];
+    let d: [[Real; 3]; 3] = std::array::from_fn(|i| {
+        let p = vtx2xy[tri2vtx[i_tri * 3 + i]];
+        let (x, y) = (p[0] - q[0], p[1] - q[1]);
+        [x, y, x * x + y * y]
+    });
+    // in-circle determinant
+    let det = d[0][0] * (d[1][1] * d[2][2] - d[1][2] * d[2][1])
+        - d[0][1] * (d[1][0] * d[2][2] - d[1][2] * d[2][0])
+        + d[0][2] * (d[1][0] * d[2][1] - d[1][1] * d[2][0]);
+    let scale = d[0][2].max(d[1][2]).max(d[2][2]);
+    let sixteen = Real::from(16).unwrap();
+    det > scale * scale * Real::epsilon() * sixteen
+}
+
+/// flip the non-constrained edges until the mesh become Delaunay
+pub fn make_delaunay_by_flipping_edges<Real>(
+    tri2vtx: &mut [usize],
+    tri2tri: &mut [usize],
+    vtx2tri: &mut [usize],
+    vtx2xy: &[[Real; 2]],
+) where
+    Real: num_traits::Float + std::fmt::Display + std::fmt::Debug,
+{
+    // the triangles whose edges may need to be flipped
+    let mut stack: Vec<usize> = (0..tri2vtx.len() / 3).collect();
+    while let Some(i_tri) = stack.pop() {
+        for i_node in 0..3 {
+            if should_flip_strictly(i_tri, i_node, tri2vtx, tri2tri, vtx2xy) {
+                let j_tri = tri2tri[i_tri * 3 + i_node];
+                crate::trimesh_topology::flip_edge(i_tri, i_node, tri2vtx, tri2tri, vtx2tri);
+                stack.push(i_tri);
+                stack.push(j_tri);
+                break;
+            }
+        }
+    }
+}
+
+/// constrained Delaunay triangulation of a planar straight-line graph
+/// * `vtx2xy` - coordinates of the points. The points that are not referenced by the loops
+///   nor the edges are inserted as the isolated points. The points need to be distinct.
+/// * `loop2idx`, `idx2vtx` - closed loops in the jagged array format. The orientation of the loops does not matter;
+///   the domain is decided by the even-odd rule (e.g., a loop inside an outer loop is a hole).
+///   If there is no loop, the convex hull of the points is triangulated.
+/// * `edge2vtx` - internal constraint edges
+///
+/// The loops and the edges must not cross each other except at their end points.
+/// The index of the vertices are the same as the input.
+pub fn triangulate_pslg<Real>(
+    vtx2xy: &[[Real; 2]],
+    loop2idx: &[usize],
+    idx2vtx: &[usize],
+    edge2vtx: &[usize],
+) -> ConstrainedDelaunay
+where
+    Real: num_traits::Float + std::fmt::Display + std::fmt::Debug + 'static,
+    f64: AsPrimitive<Real>,
+{
+    let num_vtx = vtx2xy.len();
+    let num_loop = loop2idx.len().saturating_sub(1);
+    let edges = constrained_edges(loop2idx, idx2vtx, edge2vtx);
+    let mut vtx2xy = vtx2xy.to_vec();
+    let aabb = {
+        use slice_of_array::SliceFlatExt;
+        crate::vtx2xy::aabb2(vtx2xy.flat())
+    };
+    let (mut tri2vtx, mut tri2tri, mut vtx2tri) = crate::trimesh2_dynamic::make_super_triangle(
+        &mut vtx2xy,
+        aabb[0..2].try_into().unwrap(),
+        aabb[2..4].try_into().unwrap(),
+    );
+    // insert the points in the Morton order and locate each point by the walk from the previous one,
+    // so that the walks are short
+    let idx2vtx_insert = {
+        let size = (aabb[2] - aabb[0])
+            .max(aabb[3] - aabb[1])
+            .max(Real::min_positive_value());
+        let vtx2morton: Vec<u64> = vtx2xy[0..num_vtx]
+            .iter()
+            .map(|p| {
+                let x = ((p[0] - aabb[0]) / size).to_f32().unwrap();
+                let y = ((p[1] - aabb[1]) / size).to_f32().unwrap();
+                <u64 as crate::bvhnodes_morton::MortonCode>::from_xy(x, y)
+            })
+            .collect();
+        let mut idx2vtx: Vec<usize> = (0..num_vtx).collect();
+        idx2vtx.sort_by_key(|&i_vtx| vtx2morton[i_vtx]);
+        idx2vtx
+    };
+    let mut i_tri_hint = 0;
+    for i_vtx in idx2vtx_insert {
+        let res = crate::trimesh2_dynamic::add_point_to_mesh_by_walk(
+            &mut tri2vtx,
+            &mut tri2tri,
+            &mut vtx2tri,
+            &vtx2xy,
+            i_vtx,
+            i_tri_hint,
+        );
+        assert!(res, "the points need to be distinct");
+        crate::trimesh2_dynamic::delaunay_around_point(
+            i_vtx,
+            &mut tri2vtx,
+            &mut tri2tri,
+            &mut vtx2tri,
+            &vtx2xy,
+        );
+        i_tri_hint = vtx2tri[i_vtx];
+    }
+    let edges: Vec<[usize; 2]> = edges
+        .iter()
+        .flat_map(|&[i0_vtx, i1_vtx]| {
+            recover_edge(
+                &mut tri2vtx,
+                &mut tri2tri,
+                &mut vtx2tri,
+                i0_vtx,
+                i1_vtx,
+                &vtx2xy,
+            )
+        })
+        .collect();
+    // cut the adjacency across the constrained edges after locating all of them
+    let edge2tri: Vec<(usize, usize)> = edges
+        .iter()
+        .map(|&[i0_vtx, i1_vtx]| {
+            let (i_tri, i_edge) =
+                find_edge_around_point(i0_vtx, i1_vtx, &tri2vtx, &tri2tri, &vtx2tri).unwrap();
+            (i_tri, (i_edge + 2) % 3)
+        })
+        .collect();
+    for (i_tri, i_node) in edge2tri {
+        let j_tri = tri2tri[i_tri * 3 + i_node];
+        if j_tri == usize::MAX {
+            continue;
+        }
+        let j_node = crate::trimesh_topology::find_adjacent_edge_index(
+            arrayref::array_ref!(tri2vtx, i_tri * 3, 3),
+            arrayref::array_ref!(tri2tri, i_tri * 3, 3),
+            i_node,
+            &tri2vtx,
+        );
+        tri2tri[i_tri * 3 + i_node] = usize::MAX;
+        tri2tri[j_tri * 3 + j_node] = usize::MAX;
+    }
+    make_delaunay_by_flipping_edges(&mut tri2vtx, &mut tri2tri, &mut vtx2tri, &vtx2xy);
+    // label the regions separated by the constrained edges
+    let num_tri = tri2vtx.len() / 3;
+    let mut tri2region = vec![usize::MAX; num_tri];
+    let mut region2loop = Vec::<usize>::new();
+    let mut tri2flag = vec![0; num_tri];
+    for i_tri_ker in 0..num_tri {
+        if tri2region[i_tri_ker] != usize::MAX {
+            continue;
+        }
+        let i_region = region2loop.len();
+        let mut stack = vec![i_tri_ker];
+        let mut tris = vec![];
+        tri2region[i_tri_ker] = i_region;
+        while let Some(i_tri) = stack.pop() {
+            tris.push(i_tri);
+            for &j_tri in &tri2tri[i_tri * 3..i_tri * 3 + 3] {
+                if j_tri == usize::MAX || tri2region[j_tri] != usize::MAX {
+                    continue;
+                }
+                tri2region[j_tri] = i_region;
+                stack.push(j_tri);
+            }
+        }
+        let is_inside = if num_loop == 0 {
+            region2loop.push(usize::MAX);
+            true
+        } else {
+            let p = del_geo_core::vec2::add_three(
+                &vtx2xy[tri2vtx[i_tri_ker * 3]],
+                &vtx2xy[tri2vtx[i_tri_ker * 3 + 1]],
+                &vtx2xy[tri2vtx[i_tri_ker * 3 + 2]],
+            );
+            let third = Real::one() / (Real::one() + Real::one() + Real::one());
+            let p = [p[0] * third, p[1] * third];
+            let loops: Vec<usize> = (0..num_loop)
+                .filter(|&i_loop| is_inside_loop(&p, i_loop, loop2idx, idx2vtx, &vtx2xy))
+                .collect();
+            let i_loop_innermost = loops
+                .iter()
+                .map(|&i_loop| {
+                    let area = area_of_loop(i_loop, loop2idx, idx2vtx, &vtx2xy).abs();
+                    (area, i_loop)
+                })
+                .min_by(|a, b| a.0.partial_cmp(&b.0).unwrap())
+                .map_or(usize::MAX, |v| v.1);
+            region2loop.push(i_loop_innermost);
+            loops.len() % 2 == 1
+        };
+        for &i_tri in tris.iter() {
+            let is_super = tri2vtx[i_tri * 3..i_tri * 3 + 3]
+                .iter()
+                .any(|&i_vtx| i_vtx >= num_vtx);
+            tri2flag[i_tri] = if is_inside && !is_super { 1 } else { 0 };
+        }
+    }
+    // compact the regions
+    let (region2loop, tri2region) = {
+        let mut region2new = vec![usize::MAX; region2loop.len()];
+        let mut region2loop_new = vec![];
+        for i_tri in 0..num_tri {
+            if tri2flag[i_tri] == 0 {
+                continue;
+            }
+            let i_region = tri2region[i_tri];
+            if region2new[i_region] == usize::MAX {
+                region2new[i_region] = region2loop_new.len();
+                region2loop_new.push(region2loop[i_region]);
+            }
+        }
+        let tri2region: Vec<usize> = (0..num_tri)
+            .filter(|&i_tri| tri2flag[i_tri] != 0)
+            .map(|i_tri| region2new[tri2region[i_tri]])
+            .collect();
+        (region2loop_new, tri2region)
+    };
+    // the triangles on the convex hull are adjacent to the deleted triangles
+    for i_tri in 0..num_tri {
+        for i_node in 0..3 {
+            let j_tri = tri2tri[i_tri * 3 + i_node];
+            if j_tri != usize::MAX && tri2flag[j_tri] == 0 {
+                tri2tri[i_tri * 3 + i_node] = usize::MAX;
+            }
+        }
+    }
+    (tri2vtx, tri2tri, _) =
+        crate::trimesh_topology::delete_tri_flag(&tri2vtx, &tri2tri, &tri2flag, 0);
+    let vtx2tri = {
+        let mut vtx2tri = vec![usize::MAX; num_vtx];
+        for (i_tri, tri) in tri2vtx.chunks(3).enumerate() {
+            for &i_vtx in tri {
+                assert!(i_vtx < num_vtx);
+                vtx2tri[i_vtx] = i_tri;
+            }
+        }
+        vtx2tri
+    };
+    ConstrainedDelaunay {
+        tri2vtx,
+        tri2tri,
+        vtx2tri,
+        tri2region,
+        region2loop,
+    }
+}
+
+/// cut the triangle `i_tri` by the point `i_vtx` on its constrained edge opposite to the node `i_node`.
+/// The two halves of the edge remain constrained. The index of the new triangle is returned.
+fn split_triangle_on_constrained_edge(
+    i_vtx: usize,
+    i_tri: usize,
+    i_node: usize,
+    cdt: &mut ConstrainedDelaunay,
+) -> usize {
+    let tri2vtx = &mut cdt.tri2vtx;
+    let tri2tri = &mut cdt.tri2tri;
+    assert_eq!(tri2tri[i_tri * 3 + i_node], usize::MAX);
+    let old_v: [usize; 3] = arrayref::array_ref!(tri2vtx, i_tri * 3, 3).to_owned();
+    let old_s: [usize; 3] = arrayref::array_ref!(tri2tri, i_tri * 3, 3).to_owned();
+    let (ino1, ino2) = ((i_node + 1) % 3, (i_node + 2) % 3);
+    let (i0_vtx, i1_vtx, i2_vtx) = (old_v[i_node], old_v[ino1], old_v[ino2]);
+    let j_tri = tri2vtx.len() / 3;
+    tri2vtx[i_tri * 3..i_tri * 3 + 3].copy_from_slice(&[i0_vtx, i1_vtx, i_vtx]);
+    tri2tri[i_tri * 3..i_tri * 3 + 3].copy_from_slice(&[usize::MAX, j_tri, old_s[ino2]]);
+    tri2vtx.extend_from_slice(&[i0_vtx, i_vtx, i2_vtx]);
+    tri2tri.extend_from_slice(&[usize::MAX, old_s[ino1], i_tri]);
+    if old_s[ino1] != usize::MAX {
+        let k_node =
+            crate::trimesh_topology::find_adjacent_edge_index(&old_v, &old_s, ino1, tri2vtx);
+        tri2tri[old_s[ino1] * 3 + k_node] = j_tri;
+    }
+    cdt.vtx2tri[i_vtx] = i_tri;
+    cdt.vtx2tri[i0_vtx] = i_tri;
+    cdt.vtx2tri[i1_vtx] = i_tri;
+    cdt.vtx2tri[i2_vtx] = j_tri;
+    cdt.tri2region.push(cdt.tri2region[i_tri]);
+    j_tri
+}
+
+/// register the constrained edges of the triangle to `halfedge2tri`, the map from a directed constrained edge
+/// to the triangle having it. The rotation around a point cannot find the triangle on the other side
+/// of a constrained edge because the adjacency is cut there
+fn register_constrained_edges(
+    i_tri: usize,
+    cdt: &ConstrainedDelaunay,
+    halfedge2tri: &mut std::collections::HashMap<(usize, usize), usize>,
+) {
+    for i_node in 0..3 {
+        if cdt.tri2tri[i_tri * 3 + i_node] == usize::MAX {
+            let i1_vtx = cdt.tri2vtx[i_tri * 3 + (i_node + 1) % 3];
+            let i2_vtx = cdt.tri2vtx[i_tri * 3 + (i_node + 2) % 3];
+            halfedge2tri.insert((i1_vtx, i2_vtx), i_tri);
+        }
+    }
+}
+
+/// flip the edges opposite to the point `i_vtx` until the triangles around it become Delaunay.
+/// The triangles that are modified are appended to `touched` and their constrained edges are registered
+fn legalize_around_point<Real>(
+    i_vtx: usize,
+    mut stack: Vec<usize>,
+    cdt: &mut ConstrainedDelaunay,
+    vtx2xy: &[[Real; 2]],
+    touched: &mut Vec<usize>,
+    halfedge2tri: &mut std::collections::HashMap<(usize, usize), usize>,
+) where
+    Real: num_traits::Float + std::fmt::Display + std::fmt::Debug,
+{
+    while let Some(i_tri) = stack.pop() {
+        touched.push(i_tri);
+        register_constrained_edges(i_tri, cdt, halfedge2tri);
+        let i_node = crate::trimesh_topology::find_node(i_vtx, &cdt.tri2vtx, i_tri);
+        if i_node == usize::MAX
+            || !should_flip_strictly(i_tri, i_node, &cdt.tri2vtx, &cdt.tri2tri, vtx2xy)
+        {
+            continue;
+        }
+        let j_tri = cdt.tri2tri[i_tri * 3 + i_node];
+        crate::trimesh_topology::flip_edge(
+            i_tri,
+            i_node,
+            &mut cdt.tri2vtx,
+            &mut cdt.tri2tri,
+            &mut cdt.vtx2tri,
+        );
+        stack.push(i_tri);
+        stack.push(j_tri);
+    }
+}
+
+/// split the constrained edge between `i0_vtx` and `i1_vtx` at its middle point
+fn split_constrained_edge<Real>(
+    i0_vtx: usize,
+    i1_vtx: usize,
+    cdt: &mut ConstrainedDelaunay,
+    vtx2xy: &mut Vec<[Real; 2]>,
+    touched: &mut Vec<usize>,
+    halfedge2tri: &mut std::collections::HashMap<(usize, usize), usize>,
+) where
+    Real: num_traits::Float + std::fmt::Display + std::fmt::Debug,
+{
+    let half = Real::one() / (Real::one() + Real::one());
+    let (p0, p1) = (vtx2xy[i0_vtx], vtx2xy[i1_vtx]);
+    let i_vtx = vtx2xy.len();
+    vtx2xy.push([(p0[0] + p1[0]) * half, (p0[1] + p1[1]) * half]);
+    cdt.vtx2tri.push(usize::MAX);
+    let mut stack = vec![];
+    // an internal constrained edge has the triangles on the both sides
+    for (j0_vtx, j1_vtx) in [(i0_vtx, i1_vtx), (i1_vtx, i0_vtx)] {
+        let Some(i_tri) = halfedge2tri.remove(&(j0_vtx, j1_vtx)) else {
+            continue;
+        };
+        let i_node = crate::trimesh_topology::find_node(j0_vtx, &cdt.tri2vtx, i_tri);
+        let i_node = (i_node + 2) % 3;
+        assert_eq!(cdt.tri2vtx[i_tri * 3 + (i_node + 2) % 3], j1_vtx);
+        let j_tri = split_triangle_on_constrained_edge(i_vtx, i_tri, i_node, cdt);
+        stack.extend_from_slice(&[i_tri, j_tri]);
+    }
+    legalize_around_point(i_vtx, stack, cdt, vtx2xy, touched, halfedge2tri);
+}
+
+/// constrained edges of the triangles in the cavity of `p` (triangles whose circumcircle contains `p`)
+/// such that `p` is inside their diametral circles
+fn encroached_edges_by_point<Real>(
+    p: &[Real; 2],
+    i_tri_start: usize,
+    tri2vtx: &[usize],
+    tri2tri: &[usize],
+    vtx2xy: &[[Real; 2]],
+) -> Vec<[usize; 2]>
+where
+    Real: num_traits::Float,
+{
+    let mut edges = vec![];
+    let mut visited = vec![i_tri_start];
+    let mut stack = vec![i_tri_start];
+    while let Some(i_tri) = stack.pop() {
+        for i_node in 0..3 {
+            let i1_vtx = tri2vtx[i_tri * 3 + (i_node + 1) % 3];
+            let i2_vtx = tri2vtx[i_tri * 3 + (i_node + 2) % 3];
+            let j_tri = tri2tri[i_tri * 3 + i_node];
+            if j_tri == usize::MAX {
+                if is_encroached(p, &vtx2xy[i1_vtx], &vtx2xy[i2_vtx]) {
+                    edges.push([i1_vtx, i2_vtx]);
+                }
+                continue;
+            }
+            if visited.contains(&j_tri) {
+                continue;
+            }
+            let q0 = vtx2xy[tri2vtx[j_tri * 3]];
+            let q1 = vtx2xy[tri2vtx[j_tri * 3 + 1]];
+            let q2 = vtx2xy[tri2vtx[j_tri * 3 + 2]];
+            let cc = del_geo_core::tri2::circumcenter(&q0, &q1, &q2);
+            let rad = del_geo_core::edge2::squared_length(&cc, &q0);
+            if del_geo_core::edge2::squared_length(&cc, p) < rad {
+                visited.push(j_tri);
+                stack.push(j_tri);
+            }
+        }
+    }
+    edges
+}
+
+/// if the point `p` is inside the diametral circle of the segment `(a, b)`
+fn is_encroached<Real>(p: &[Real; 2], a: &[Real; 2], b: &[Real; 2]) -> bool
+where
+    Real: num_traits::Float,
+{
+    (a[0] - p[0]) * (b[0] - p[0]) + (a[1] - p[1]) * (b[1] - p[1]) < Real::zero()
+}
+
+/// Delaunay refinement (Ruppert's algorithm with Chew's circumcenter insertion) of the triangulation
+/// made by `triangulate_pslg`. The points are inserted at the circumcenters of the bad triangles
+/// and at the middle points of the encroached constrained edges until
+/// * the minimum angle of each triangle is larger than `min_angle_degree`, and
+/// * the longest edge of each triangle is shorter than `target_len` evaluated at its centroid.
+///
+/// The angles between two constrained edges are left as they are.
+/// Termination is guaranteed for `min_angle_degree` up to around 20.7 if the input angles are larger than 60 degree.
+/// The new points are appended to `vtx2xy` and the constrained edges are kept as the chains of the edges.
+pub fn refine<Real, F>(
+    cdt: &mut ConstrainedDelaunay,
+    vtx2xy: &mut Vec<[Real; 2]>,
+    min_angle_degree: Real,
+    target_len: F,
+) where
+    Real: num_traits::Float + std::fmt::Display + std::fmt::Debug + 'static,
+    F: Fn(&[Real; 2]) -> Real,
+{
+    let sin_min = min_angle_degree.to_radians().sin();
+    // the edges shorter than this are not split anymore
+    let min_len = {
+        use slice_of_array::SliceFlatExt;
+        let aabb = crate::vtx2xy::aabb2(vtx2xy.flat());
+        let diag = del_geo_core::edge2::length(
+            aabb[0..2].try_into().unwrap(),
+            aabb[2..4].try_into().unwrap(),
+        );
+        diag * Real::epsilon().sqrt()
+    };
+    let sq = |a: &[Real; 2], b: &[Real; 2]| del_geo_core::edge2::squared_length(a, b);
+    let two = Real::one() + Real::one();
+    let third = Real::one() / (two + Real::one());
+    let mut queue: std::collections::VecDeque<usize> = (0..cdt.tri2vtx.len() / 3).collect();
+    let mut touched = vec![];
+    let mut halfedge2tri = std::collections::HashMap::<(usize, usize), usize>::new();
+    for i_tri in 0..cdt.tri2vtx.len() / 3 {
+        register_constrained_edges(i_tri, cdt, &mut halfedge2tri);
+    }
+    while let Some(i_tri) = queue.pop_front() {
+        let vtx: [usize; 3] = arrayref::array_ref!(cdt.tri2vtx, i_tri * 3, 3).to_owned();
+        let p: [[Real; 2]; 3] = vtx.map(|i_vtx| vtx2xy[i_vtx]);
+        // squared length of the edge opposite to each node
+        let len2: [Real; 3] = std::array::from_fn(|i| sq(&p[(i + 1) % 3], &p[(i + 2) % 3]));
+        // constrained edges encroached by the opposite node
+        let encroached = (0..3).find(|&i_node| {
+            cdt.tri2tri[i_tri * 3 + i_node] == usize::MAX
+                && len2[i_node] > min_len * min_len * two * two
+                && is_encroached(&p[i_node], &p[(i_node + 1) % 3], &p[(i_node + 2) % 3])
+        });
+        if let Some(i_node) = encroached {
+            split_constrained_edge(
+                vtx[(i_node + 1) % 3],
+                vtx[(i_node + 2) % 3],
+                cdt,
+                vtx2xy,
+                &mut touched,
+                &mut halfedge2tri,
+            );
+            queue.extend(touched.drain(..));
+            continue;
+        }
+        // check the quality of the triangle
+        let i_node_min = (0..3)
+            .min_by(|&a, &b| len2[a].partial_cmp(&len2[b]).unwrap())
+            .unwrap();
+        if len2[i_node_min] < min_len * min_len {
+            continue;
+        }
+        let is_bad_angle = {
+            let (ino1, ino2) = ((i_node_min + 1) % 3, (i_node_min + 2) % 3);
+            let is_input_angle = cdt.tri2tri[i_tri * 3 + ino1] == usize::MAX
+                && cdt.tri2tri[i_tri * 3 + ino2] == usize::MAX;
+            // sine of the smallest angle that is opposite to the shortest edge
+            let sin = two * del_geo_core::tri2::area(&p[0], &p[1], &p[2])
+                / (len2[ino1] * len2[ino2]).sqrt();
+            !is_input_angle && sin < sin_min
+        };
+        let is_bad_size = {
+            let len_max = len2[0].max(len2[1]).max(len2[2]).sqrt();
+            let centroid = del_geo_core::vec2::add_three(&p[0], &p[1], &p[2]);
+            len_max > target_len(&[centroid[0] * third, centroid[1] * third])
+        };
+        if !is_bad_angle && !is_bad_size {
+            continue;
+        }
+        let cc = del_geo_core::tri2::circumcenter(&p[0], &p[1], &p[2]);
+        let j_tri = match crate::trimesh2_dynamic::search_triangle_include_input_point_by_walk(
+            &cc,
+            i_tri,
+            &cdt.tri2vtx,
+            &cdt.tri2tri,
+            vtx2xy,
+        ) {
+            Ok(j_tri) => j_tri,
+            Err((j_tri, j_node)) => {
+                // the circumcenter is hidden behind a constrained edge
+                let j1_vtx = cdt.tri2vtx[j_tri * 3 + (j_node + 1) % 3];
+                let j2_vtx = cdt.tri2vtx[j_tri * 3 + (j_node + 2) % 3];
+                if sq(&vtx2xy[j1_vtx], &vtx2xy[j2_vtx]) > min_len * min_len * two * two {
+                    split_constrained_edge(
+                        j1_vtx,
+                        j2_vtx,
+                        cdt,
+                        vtx2xy,
+                        &mut touched,
+                        &mut halfedge2tri,
+                    );
+                    queue.extend(touched.drain(..));
+                    queue.push_back(i_tri);
+                }
+                continue;
+            }
+        };
+        let edges = encroached_edges_by_point(&cc, j_tri, &cdt.tri2vtx, &cdt.tri2tri, vtx2xy);
+        if !edges.is_empty() {
+            // split the encroached edges instead of inserting the circumcenter
+            let mut is_split = false;
+            for [j0_vtx, j1_vtx] in edges {
+                if sq(&vtx2xy[j0_vtx], &vtx2xy[j1_vtx]) > min_len * min_len * two * two {
+                    split_constrained_edge(
+                        j0_vtx,
+                        j1_vtx,
+                        cdt,
+                        vtx2xy,
+                        &mut touched,
+                        &mut halfedge2tri,
+                    );
+                    is_split = true;
+                }
+            }
+            if is_split {
+                queue.extend(touched.drain(..));
+                queue.push_back(i_tri);
+            }
+            continue;
+        }
+        let q: [[Real; 2]; 3] = std::array::from_fn(|i| vtx2xy[cdt.tri2vtx[j_tri * 3 + i]]);
+        if q.iter().any(|q| sq(q, &cc) < min_len * min_len) {
+            continue;
+        }
+        let i_vtx = vtx2xy.len();
+        vtx2xy.push(cc);
+        cdt.vtx2tri.push(usize::MAX);
+        let num_tri = cdt.tri2vtx.len() / 3;
+        // the circumcenter may be on an edge of the triangle
+        let area = del_geo_core::tri2::area(&q[0], &q[1], &q[2]);
+        let on_edge = (0..3).find(|&i_node| {
+            del_geo_core::tri2::area(&cc, &q[(i_node + 1) % 3], &q[(i_node + 2) % 3])
+                < area * Real::epsilon().sqrt()
+        });
+        let mut stack = vec![j_tri, num_tri, num_tri + 1];
+        if let Some(j_node) = on_edge {
+            let k_tri = cdt.tri2tri[j_tri * 3 + j_node];
+            crate::trimesh_topology::insert_point_on_elem_edge(
+                i_vtx,
+                j_tri,
+                j_node,
+                &mut cdt.tri2vtx,
+                &mut cdt.tri2tri,
+                &mut cdt.vtx2tri,
+            );
+            cdt.tri2region.push(cdt.tri2region[k_tri]);
+            cdt.tri2region.push(cdt.tri2region[k_tri]);
+            stack.push(k_tri);
+        } else {
+            crate::trimesh_topology::insert_a_point_inside_an_element(
+                i_vtx,
+                j_tri,
+                &mut cdt.tri2vtx,
+                &mut cdt.tri2tri,
+                &mut cdt.vtx2tri,
+            );
+            cdt.tri2region.push(cdt.tri2region[j_tri]);
+            cdt.tri2region.push(cdt.tri2region[j_tri]);
+        }
+        legalize_around_point(i_vtx, stack, cdt, vtx2xy, &mut touched, &mut halfedge2tri);
+        queue.extend(touched.drain(..));
+    }
+}
+
+/// target edge length for `refine` sampled from a single channel texture with the bilinear interpolation
+/// * `transform_xy2pix` - homogeneous transformation from the coordinate of the mesh to that of the pixel
+///   where (0., 0.) is the center of the first texel. The coordinate is clamped inside the texture.
+pub fn target_length_from_grid2<'a>(
+    tex_shape: &'a (usize, usize),
+    tex_data: &'a [f32],
+    transform_xy2pix: &'a [f32; 9],
+) -> impl Fn(&[f32; 2]) -> f32 + 'a {
+    assert!(tex_shape.0 >= 2 && tex_shape.1 >= 2);
+    assert_eq!(tex_data.len(), tex_shape.0 * tex_shape.1);
+    move |xy: &[f32; 2]| {
+        let pix =
+            del_geo_core::mat3_col_major::transform_homogeneous(transform_xy2pix, xy).unwrap();
+        // the bilinear interpolation refers to the next texel
+        let pix = [
+            pix[0].clamp(0., (tex_shape.0 - 1) as f32 * (1. - f32::EPSILON * 4.)),
+            pix[1].clamp(0., (tex_shape.1 - 1) as f32 * (1. - f32::EPSILON * 4.)),
+        ];
+        crate::grid2::bilinear_integer_center::<1>(&pix, tex_shape, tex_data)[0]
+    }
+}
+
+#[test]
+fn test_pslg() {
+    let vtx2xy: Vec<[f64; 2]> = vec![
+        // loop0: outer boundary
+        [-1.0, -1.0],
+        [0.0, -1.0],
+        [1.0, -1.0],
+        [1.0, 1.0],
+        [-1.0, 1.0],
+        // loop1: hole
+        [-0.5, -0.5],
+        [-0.5, 0.5],
+        [0.5, 0.5],
+        [0.5, -0.5],
+        // loop2: island inside the hole
+        [-0.2, -0.2],
+        [0.2, -0.2],
+        [0.2, 0.2],
+        [-0.2, 0.2],
+        // loop3: another outer boundary
+        [2.0, 0.0],
+        [3.0, 0.0],
+        [3.0, 1.0],
+        [2.0, 1.0],
+        // internal constraint edge
+        [-0.8, -0.8],
+        [-0.8, 0.8],
+        // isolated points inside and outside
+        [0.75, 0.75],
+        [5.0, 5.0],
+        // on the constraint edge
+        [-0.8, 0.0],
+    ];
+    let loop2idx = [0, 5, 9, 13, 17];
+    let idx2vtx: Vec<usize> = (0..17).collect();
+    let edge2vtx = [17, 18];
+    let cdt = triangulate_pslg(&vtx2xy, &loop2idx, &idx2vtx, &edge2vtx);
+    let num_tri = cdt.tri2vtx.len() / 3;
+    assert_eq!(cdt.tri2region.len(), num_tri);
+    assert_eq!(cdt.region2loop.len(), 3);
+    let mut region2area = vec![0f64; cdt.region2loop.len()];
+    for i_tri in 0..num_tri {
+        let area = crate::trimesh2::area_of_a_triangle(&cdt.tri2vtx, &vtx2xy, i_tri);
+        assert!(area > 0.);
+        region2area[cdt.tri2region[i_tri]] += area;
+    }
+    for (i_region, &i_loop) in cdt.region2loop.iter().enumerate() {
+        let area = match i_loop {
+            0 => 3.,
+            2 => 0.16,
+            3 => 1.,
+            _ => panic!(),
+        };
+        assert!((region2area[i_region] - area).abs() < 1.0e-10);
+    }
+    // the isolated point inside is used but the one outside is not
+    assert_ne!(cdt.vtx2tri[19], usize::MAX);
+    assert_eq!(cdt.vtx2tri[20], usize::MAX);
+    // constrained edges are in the mesh and the adjacency is cut there
+    for [i0_vtx, i1_vtx] in [[17, 21], [21, 18], [0, 1], [5, 6], [9, 10]] {
+        let (i_tri, i_edge) = crate::trimesh_topology::find_edge_by_looking_all_triangles(
+            i0_vtx,
+            i1_vtx,
+            &cdt.tri2vtx,
+        )
+        .or_else(|| {
+            crate::trimesh_topology::find_edge_by_looking_all_triangles(
+                i1_vtx,
+                i0_vtx,
+                &cdt.tri2vtx,
+            )
+        })
+        .unwrap();
+        assert_eq!(cdt.tri2tri[i_tri * 3 + (i_edge + 2) % 3], usize::MAX);
+    }
+    // constrained Delaunay
+    for i_tri in 0..num_tri {
+        for i_node in 0..3 {
+            assert!(!crate::trimesh2_dynamic::should_flip(
+                i_tri,
+                i_node,
+                &cdt.tri2vtx,
+                &cdt.tri2tri,
+                &vtx2xy
+            ));
+        }
+    }
+    // convex hull of the points
+    let cdt = triangulate_pslg(&vtx2xy[0..5], &[], &[], &[]);
+    assert_eq!(cdt.tri2vtx.len() / 3, 3);
+    assert_eq!(cdt.region2loop, vec![usize::MAX]);
+}
+
+#[test]
+fn test_pslg_random() {
+    use rand::Rng;
+    use rand::SeedableRng;
+    let mut rng = rand_chacha::ChaChaRng::seed_from_u64(0);
+    // square, long horizontal segments below the diagonal and points on the diagonal
+    let mut vtx2xy: Vec<[f64; 2]> = vec![[0., 0.], [1., 0.], [1., 1.], [0., 1.]];
+    let mut edge2vtx = vec![0, 2];
+    for i in 1..10 {
+        let y = i as f64 * 0.1 + 0.05;
+        edge2vtx.extend([vtx2xy.len(), vtx2xy.len() + 1]);
+        vtx2xy.extend([[y + 0.02, y], [0.99, y]]);
+        vtx2xy.push([y - 0.05, y - 0.05]);
+    }
+    for _ in 0..500 {
+        vtx2xy.push([rng.random::<f64>(), rng.random::<f64>()]);
+    }
+    let cdt = triangulate_pslg(&vtx2xy, &[0, 4], &[0, 1, 2, 3], &edge2vtx);
+    let num_tri = cdt.tri2vtx.len() / 3;
+    let mut area = 0.;
+    for i_tri in 0..num_tri {
+        let a = crate::trimesh2::area_of_a_triangle(&cdt.tri2vtx, &vtx2xy, i_tri);
+        assert!(a > 0.);
+        area += a;
+        for i_node in 0..3 {
+            assert!(!crate::trimesh2_dynamic::should_flip(
+                i_tri,
+                i_node,
+                &cdt.tri2vtx,
+                &cdt.tri2tri,
+                &vtx2xy
+            ));
+        }
+    }
+    assert!((area - 1.).abs() < 1.0e-10);
+    // the length of the constrained edges, where the diagonal is split at the points on it
+    let mut len_constrained = 0.;
+    for i_tri in 0..num_tri {
+        for i_node in 0..3 {
+            if cdt.tri2tri[i_tri * 3 + i_node] != usize::MAX {
+                continue;
+            }
+            let i1_vtx = cdt.tri2vtx[i_tri * 3 + (i_node + 1) % 3];
+            let i2_vtx = cdt.tri2vtx[i_tri * 3 + (i_node + 2) % 3];
+            len_constrained += del_geo_core::edge2::length(&vtx2xy[i1_vtx], &vtx2xy[i2_vtx]);
+        }
+    }
+    let len_expected = 4. + 2. * (2f64.sqrt() + 3.78);
+    assert!((len_constrained - len_expected).abs() < 1.0e-10);
+}
+
+#[test]
+fn test_pslg_many_points() {
+    use rand::Rng;
+    use rand::SeedableRng;
+    let mut rng = rand_chacha::ChaChaRng::seed_from_u64(1);
+    let mut vtx2xy: Vec<[f64; 2]> = vec![[0., 0.], [1., 0.], [1., 1.], [0., 1.]];
+    for _ in 0..20000 {
+        vtx2xy.push([rng.random::<f64>(), rng.random::<f64>()]);
+    }
+    let cdt = triangulate_pslg(&vtx2xy, &[0, 4], &[0, 1, 2, 3], &[]);
+    let num_tri = cdt.tri2vtx.len() / 3;
+    // Euler's formula for the points inside a square
+    assert_eq!(num_tri, 2 * vtx2xy.len() - 4 - 2);
+    let mut area = 0.;
+    for i_tri in 0..num_tri {
+        area += crate::trimesh2::area_of_a_triangle(&cdt.tri2vtx, &vtx2xy, i_tri);
+        for i_node in 0..3 {
+            assert!(!crate::trimesh2_dynamic::should_flip(
+                i_tri,
+                i_node,
+                &cdt.tri2vtx,
+                &cdt.tri2tri,
+                &vtx2xy
+            ));
+        }
+    }
+    assert!((area - 1.).abs() < 1.0e-10);
+}
+
+#[test]
+fn test_refine() {
+    let vtx2xy: Vec<[f64; 2]> = vec![
+        [-1.0, -1.0],
+        [1.0, -1.0],
+        [1.0, 1.0],
+        [-1.0, 1.0],
+        [-0.3, -0.3],
+        [0.3, -0.3],
+        [0.3, 0.3],
+        [-0.3, 0.3],
+        [-0.8, -0.8],
+        [-0.8, 0.8],
+    ];
+    let loop2idx = [0, 4, 8];
+    let idx2vtx: Vec<usize> = (0..8).collect();
+    let mut cdt = triangulate_pslg(&vtx2xy, &loop2idx, &idx2vtx, &[8, 9]);
+    let mut vtx2xy = vtx2xy;
+    refine(&mut cdt, &mut vtx2xy, 25.0, |_p| 0.15);
+    let num_tri = cdt.tri2vtx.len() / 3;
+    assert_eq!(cdt.tri2region.len(), num_tri);
+    assert_eq!(cdt.vtx2tri.len(), vtx2xy.len());
+    let mut area = 0.;
+    let mut len_constrained = 0.;
+    for i_tri in 0..num_tri {
+        area += crate::trimesh2::area_of_a_triangle(&cdt.tri2vtx, &vtx2xy, i_tri);
+        let p: [[f64; 2]; 3] = std::array::from_fn(|i| vtx2xy[cdt.tri2vtx[i_tri * 3 + i]]);
+        for i_node in 0..3 {
+            let (p0, p1, p2) = (&p[i_node], &p[(i_node + 1) % 3], &p[(i_node + 2) % 3]);
+            let len = del_geo_core::edge2::length(p1, p2);
+            assert!(len < 0.15 + 1.0e-10);
+            let angle = del_geo_core::tri2::area(p0, p1, p2) * 2.
+                / (del_geo_core::edge2::length(p0, p1) * del_geo_core::edge2::length(p0, p2));
+            assert!(angle.asin().to_degrees() > 25.0 - 1.0e-10);
+            if cdt.tri2tri[i_tri * 3 + i_node] == usize::MAX {
+                len_constrained += len;
+            }
+            assert!(!crate::trimesh2_dynamic::should_flip(
+                i_tri,
+                i_node,
+                &cdt.tri2vtx,
+                &cdt.tri2tri,
+                &vtx2xy
+            ));
+        }
+    }
+    assert!((area - 3.64).abs() < 1.0e-10);
+    // the internal edge has the triangles on the both sides
+    assert!((len_constrained - (8.0 + 2.4 + 1.6 * 2.)).abs() < 1.0e-10);
+    {
+        use slice_of_array::SliceFlatExt;
+        crate::io_obj::save_tri2vtx_vtx2xyz(
+            "../target/trimesh2_constrained_delaunay_refine.obj",
+            &cdt.tri2vtx,
+            vtx2xy.flat(),
+            2,
+        )
+        .unwrap();
+    }
+    // sizing field given as a texture: finer on the left
+    let vtx2xy: Vec<[f32; 2]> = vec![[-1.0, -1.0], [1.0, -1.0], [1.0, 1.0], [-1.0, 1.0]];
+    let mut cdt = triangulate_pslg(&vtx2xy, &[0, 4], &[0, 1, 2, 3], &[]);
+    let mut vtx2xy = vtx2xy;
+    let tex_shape = (2, 2);
+    let tex_data = [0.05, 0.3, 0.05, 0.3];
+    let transform_xy2pix = [0.5, 0., 0., 0., 0.5, 0., 0.5, 0.5, 1.];
+    refine(
+        &mut cdt,
+        &mut vtx2xy,
+        20.0,
+        target_length_from_grid2(&tex_shape, &tex_data, &transform_xy2pix),
+    );
+    let num_left = vtx2xy.iter().filter(|p| p[0] < 0.).count();
+    let num_right = vtx2xy.iter().filter(|p| p[0] > 0.).count();
+    assert!(num_left > num_right * 3, "{num_left} {num_right}");
+}
@@ -281,10 +281,10 @@ where
                 break;
             }
             let j_node = crate::trimesh_topology::find_adjacent_edge_index(
-                tri2vtx[i_tri_nex * 3..i_tri_nex * 3 + 3]
+                tri2vtx[i_tri_cur * 3..i_tri_cur * 3 + 3]
                     .try_into()
                     .unwrap(),
-                tri2tri[i_tri_nex * 3..i_tri_nex * 3 + 3]
+                tri2tri[i_tri_cur * 3..i_tri_cur * 3 + 3]
                     .try_into()
                     .unwrap(),
                 i2_node,
@@ -329,6 +329,9 @@ where
         {
             let i2_node = (i_node_cur + 2) % 3;
             let i_tri_nex = tri2tri[i_tri_cur * 3 + i2_node];
+            if i_tri_nex == usize::MAX {
+                return None;
+            }
             let j_node = crate::trimesh_topology::find_adjacent_edge_index(
                 &tri2vtx[i_tri_cur * 3..i_tri_cur * 3 + 3]
                     .try_into()
@@ -339,7 +342,7 @@ where
                 i2_node,
                 tri2vtx,
             );
-            let i3_node = (j_node + 1) % 3;
+            let i3_node = (j_node + 2) % 3;
             assert_eq!(tri2vtx[i_tri_nex * 3 + i3_node], ipo0);
             if i_tri_nex == i_tri_ini {
                 panic!();
@@ -617,6 +620,343 @@ where
     true
 }
 
+/// find the triangle that includes the point `p` by walking from the hint triangle `i_tri_hint`
+/// along the line segment from its centroid to `p`. The cost is proportional to the number of the triangles visited.
+/// The point may be slightly outside the returned triangle when it is on the edge.
+///
+/// # Returns
+/// * `Err((i_tri, i_node))` - the walk is blocked by the edge opposite to the node `i_node` of the triangle `i_tri`
+///   that has no adjacent triangle. The point is outside the mesh if the mesh is convex.
+pub fn search_triangle_include_input_point_by_walk<Real>(
+    p: &[Real; 2],
+    i_tri_hint: usize,
+    tri2vtx: &[usize],
+    tri2tri: &[usize],
+    vtx2xy: &[[Real; 2]],
+) -> Result<usize, (usize, usize)>
+where
+    Real: num_traits::Float,
+{
+    use del_geo_core::tri2::area;
+    // walk along the segment from the centroid of the starting triangle
+    let o = {
+        let o = del_geo_core::vec2::add_three(
+            &vtx2xy[tri2vtx[i_tri_hint * 3]],
+            &vtx2xy[tri2vtx[i_tri_hint * 3 + 1]],
+            &vtx2xy[tri2vtx[i_tri_hint * 3 + 2]],
+        );
+        let third = Real::one() / (Real::one() + Real::one() + Real::one());
+        [o[0] * third, o[1] * third]
+    };
+    let mut i_tri = i_tri_hint;
+    for _i_step in 0..tri2vtx.len() {
+        let q: [[Real; 2]; 3] = std::array::from_fn(|i| vtx2xy[tri2vtx[i_tri * 3 + i]]);
+        // tolerance for the point on the edge
+        let eps = area(&q[0], &q[1], &q[2]) * Real::epsilon();
+        let is_beyond = |i_node: usize| area(&q[(i_node + 1) % 3], &q[(i_node + 2) % 3], p) < -eps;
+        let is_crossing = |i_node: usize| {
+            area(&o, p, &q[(i_node + 1) % 3]) * area(&o, p, &q[(i_node + 2) % 3]) <= Real::zero()
+        };
+        let i_node = (0..3)
+            .find(|&i_node| is_beyond(i_node) && is_crossing(i_node))
+            .or_else(|| (0..3).find(|&i_node| is_beyond(i_node)));
+        let Some(i_node) = i_node else {
+            return Ok(i_tri);
+        };
+        let j_tri = tri2tri[i_tri * 3 + i_node];
+        if j_tri == usize::MAX {
+            return Err((i_tri, i_node));
+        }
+        i_tri = j_tri;
+    }
+    panic!("the walk did not reach the point");
+}
+
+/// add the point `i_vtx` into the triangle found by `search_triangle_include_input_point_by_walk`.
+/// This is the same as `add_points_to_mesh` but the point is located from the hint triangle `i_tri_hint`
+/// instead of looking all the triangles. Call `delaunay_around_point` afterward to make the mesh Delaunay.
+///
+/// # Returns
+/// `false` if the point cannot be located (e.g., outside the mesh) or it overlaps with an existing point
+pub fn add_point_to_mesh_by_walk<T>(
+    tri2vtx: &mut Vec<usize>,
+    tri2tri: &mut Vec<usize>,
+    vtx2tri: &mut [usize],
+    vtx2xy: &[[T; 2]],
+    i_vtx: usize,
+    i_tri_hint: usize,
+) -> bool
+where
+    T: num_traits::Float + 'static,
+    f64: AsPrimitive<T>,
+{
+    assert_eq!(vtx2xy.len(), vtx2tri.len());
+    assert_eq!(vtx2tri[i_vtx], usize::MAX);
+    let p = vtx2xy[i_vtx];
+    let Ok(i_tri) =
+        search_triangle_include_input_point_by_walk(&p, i_tri_hint, tri2vtx, tri2tri, vtx2xy)
+    else {
+        return false;
+    };
+    let q: [[T; 2]; 3] = std::array::from_fn(|i| vtx2xy[tri2vtx[i_tri * 3 + i]]);
+    if q.iter().any(|q| q[0] == p[0] && q[1] == p[1]) {
+        return false;
+    }
+    let areas: [T; 3] =
+        std::array::from_fn(|i| del_geo_core::tri2::area(&p, &q[(i + 1) % 3], &q[(i + 2) % 3]));
+    let area_sum = areas[0] + areas[1] + areas[2];
+    let (&area_min, i_edge) = areas
+        .iter()
+        .zip(0..)
+        .min_by(|a, b| a.0.partial_cmp(b.0).expect("NaN area"))
+        .unwrap();
+    // the edge can be split only if the four triangles around the point are not inverted.
+    // A point close to the edge of a large triangle can be far from it in a small adjacent triangle.
+    let is_splittable = {
+        let j_tri = tri2tri[i_tri * 3 + i_edge];
+        j_tri != usize::MAX && {
+            let j_node = crate::trimesh_topology::find_adjacent_edge_index(
+                arrayref::array_ref!(tri2vtx, i_tri * 3, 3),
+                arrayref::array_ref!(tri2tri, i_tri * 3, 3),
+                i_edge,
+                tri2vtx,
+            );
+            let r = vtx2xy[tri2vtx[j_tri * 3 + j_node]];
+            let (q1, q2) = (&q[(i_edge + 1) % 3], &q[(i_edge + 2) % 3]);
+            areas[(i_edge + 1) % 3] > T::zero()
+                && areas[(i_edge + 2) % 3] > T::zero()
+                && del_geo_core::tri2::area(&p, &r, q2) > T::zero()
+                && del_geo_core::tri2::area(&p, q1, &r) > T::zero()
+        }
+    };
+    if area_min > area_sum * 1.0e-3f64.as_() {
+        crate::trimesh_topology::insert_a_point_inside_an_element(
+            i_vtx, i_tri, tri2vtx, tri2tri, vtx2tri,
+        );
+    } else if is_splittable {
+        crate::trimesh_topology::insert_point_on_elem_edge(
+            i_vtx, i_tri, i_edge, tri2vtx, tri2tri, vtx2tri,
+        );
+    } else if area_min > T::zero() {
+        // close to the edge but splitting it makes an inverted triangle
+        crate::trimesh_topology::insert_a_point_inside_an_element(
+            i_vtx, i_tri, tri2vtx, tri2tri, vtx2tri,
+        );
+    } else {
+        return false;
+    }
+    true
+}
+
+/// triangles around the point and the index of the node for the point in the counter-clockwise order.
+/// `None` if the point is on the boundary
+fn triangles_around_interior_point(
+    i_vtx: usize,
+    tri2vtx: &[usize],
+    tri2tri: &[usize],
+    vtx2tri: &[usize],
+) -> Option<Vec<(usize, usize)>> {
+    let mut i_tri = vtx2tri[i_vtx];
+    if i_tri == usize::MAX {
+        return None;
+    }
+    let mut i_node = crate::trimesh_topology::find_node(i_vtx, tri2vtx, i_tri);
+    let mut fan = vec![];
+    loop {
+        fan.push((i_tri, i_node));
+        if !crate::trimesh_topology::move_ccw(&mut i_tri, &mut i_node, usize::MAX, tri2vtx, tri2tri)
+        {
+            return None;
+        }
+        if i_tri == vtx2tri[i_vtx] {
+            return Some(fan);
+        }
+    }
+}
+
+/// remove the triangle by moving the last triangle to its place.
+/// The triangle to remove must not be referenced from the other triangles and the vertices.
+fn swap_remove_triangle(
+    i_tri: usize,
+    tri2vtx: &mut Vec<usize>,
+    tri2tri: &mut Vec<usize>,
+    vtx2tri: &mut [usize],
+) {
+    let i_tri_last = tri2vtx.len() / 3 - 1;
+    if i_tri != i_tri_last {
+        for i_node in 0..3 {
+            tri2vtx[i_tri * 3 + i_node] = tri2vtx[i_tri_last * 3 + i_node];
+            tri2tri[i_tri * 3 + i_node] = tri2tri[i_tri_last * 3 + i_node];
+            let j_tri = tri2tri[i_tri * 3 + i_node];
+            if j_tri != usize::MAX {
+                tri2tri[j_tri * 3..j_tri * 3 + 3]
+                    .iter_mut()
+                    .filter(|k_tri| **k_tri == i_tri_last)
+                    .for_each(|k_tri| *k_tri = i_tri);
+            }
+            let j_vtx = tri2vtx[i_tri * 3 + i_node];
+            if vtx2tri[j_vtx] == i_tri_last {
+                vtx2tri[j_vtx] = i_tri;
+            }
+        }
+    }
+    tri2vtx.truncate(i_tri_last * 3);
+    tri2tri.truncate(i_tri_last * 3);
+}
+
+/// delete an interior point while keeping the mesh Delaunay.
+/// The edges around the point are flipped so that the Delaunay ears of the surrounding polygon are cut off
+/// until three triangles remain, and then they are merged into one triangle.
+/// The point stays in `vtx2xy` with `vtx2tri[i_vtx] == usize::MAX` (use `delete_unreferenced_points` to compact).
+/// The indices of the triangles change as the last triangles are moved to the deleted ones.
+///
+/// # Returns
+/// `false` if the point is on the boundary (including the edges whose adjacency is cut) or cannot be deleted.
+/// The mesh is not changed in that case
+pub fn delete_point<T>(
+    i_vtx: usize,
+    tri2vtx: &mut Vec<usize>,
+    tri2tri: &mut Vec<usize>,
+    vtx2tri: &mut [usize],
+    vtx2xy: &[[T; 2]],
+) -> bool
+where
+    T: num_traits::Float,
+{
+    use del_geo_core::tri2::area;
+    let Some(fan) = triangles_around_interior_point(i_vtx, tri2vtx, tri2tri, vtx2tri) else {
+        return false;
+    };
+    // the fan triangles are (i_vtx, ring[i], ring[i+1])
+    let mut ring: Vec<usize> = fan
+        .iter()
+        .map(|&(i_tri, i_node)| tri2vtx[i_tri * 3 + (i_node + 1) % 3])
+        .collect();
+    // the ears are decided before flipping any edge so that the mesh is unchanged on failure.
+    // Each ear separates a point of the ring from `i_vtx`
+    let p = vtx2xy[i_vtx];
+    let mut ears = vec![];
+    while ring.len() > 3 {
+        let num_ring = ring.len();
+        let i_ear = (0..num_ring).find(|&i| {
+            let a = vtx2xy[ring[i]];
+            let b = vtx2xy[ring[(i + 1) % num_ring]];
+            let c = vtx2xy[ring[(i + 2) % num_ring]];
+            if area(&a, &b, &c) <= T::zero() || area(&p, &a, &c) <= T::zero() {
+                return false;
+            }
+            let cc = del_geo_core::tri2::circumcenter(&a, &b, &c);
+            let rad = del_geo_core::edge2::squared_length(&cc, &a);
+            (3..num_ring).all(|j| {
+                let d = vtx2xy[ring[(i + j) % num_ring]];
+                del_geo_core::edge2::squared_length(&cc, &d) >= rad * (T::one() - T::epsilon())
+            })
+        });
+        let Some(i_ear) = i_ear else {
+            return false;
+        };
+        ears.push(ring.remove((i_ear + 1) % num_ring));
+    }
+    for j_vtx in ears {
+        // flip the edge between i_vtx and j_vtx to make the ear
+        let fan = triangles_around_interior_point(i_vtx, tri2vtx, tri2tri, vtx2tri).unwrap();
+        let &(i_tri, i_node) = fan
+            .iter()
+            .find(|&&(i_tri, i_node)| tri2vtx[i_tri * 3 + (i_node + 2) % 3] == j_vtx)
+            .unwrap();
+        crate::trimesh_topology::flip_edge(i_tri, (i_node + 1) % 3, tri2vtx, tri2tri, vtx2tri);
+    }
+    // merge the three triangles around the point
+    let fan = triangles_around_interior_point(i_vtx, tri2vtx, tri2tri, vtx2tri).unwrap();
+    let i_tri0 = fan[0].0;
+    let old: Vec<([usize; 3], [usize; 3])> = fan
+        .iter()
+        .map(|&(i_tri, _)| {
+            (
+                arrayref::array_ref!(tri2vtx, i_tri * 3, 3).to_owned(),
+                arrayref::array_ref!(tri2tri, i_tri * 3, 3).to_owned(),
+            )
+        })
+        .collect();
+    let tri_new: [usize; 3] = std::array::from_fn(|i| old[i].0[(fan[i].1 + 1) % 3]);
+    tri2vtx[i_tri0 * 3..i_tri0 * 3 + 3].copy_from_slice(&tri_new);
+    for (i, &(i_tri, i_node)) in fan.iter().enumerate() {
+        // the outer edge of the fan triangle is opposite to the node `(i + 2) % 3` of the new triangle
+        let j_tri = old[i].1[i_node];
+        tri2tri[i_tri0 * 3 + (i + 2) % 3] = j_tri;
+        if j_tri != usize::MAX {
+            tri2tri[j_tri * 3..j_tri * 3 + 3]
+                .iter_mut()
+                .filter(|k_tri| **k_tri == i_tri)
+                .for_each(|k_tri| *k_tri = i_tri0);
+        }
+        vtx2tri[tri_new[i]] = i_tri0;
+    }
+    vtx2tri[i_vtx] = usize::MAX;
+    let mut tris_to_remove = [fan[1].0, fan[2].0];
+    tris_to_remove.sort();
+    for &i_tri in tris_to_remove.iter().rev() {
+        swap_remove_triangle(i_tri, tri2vtx, tri2tri, vtx2tri);
+    }
+    true
+}
+
+/// move an interior point to `xy_new` while keeping the mesh Delaunay
+/// by deleting the point and adding it again at the new position.
+///
+/// # Returns
+/// `false` if the point is not moved because it is on the boundary,
+/// `xy_new` is not found by the walk from the point (e.g., outside the mesh) or it overlaps with an existing point
+pub fn move_point<T>(
+    i_vtx: usize,
+    xy_new: &[T; 2],
+    tri2vtx: &mut Vec<usize>,
+    tri2tri: &mut Vec<usize>,
+    vtx2tri: &mut [usize],
+    vtx2xy: &mut [[T; 2]],
+) -> bool
+where
+    T: num_traits::Float + std::fmt::Debug + std::fmt::Display + 'static,
+    f64: AsPrimitive<T>,
+{
+    let Some(fan) = triangles_around_interior_point(i_vtx, tri2vtx, tri2tri, vtx2tri) else {
+        return false;
+    };
+    let i_tri_new = match search_triangle_include_input_point_by_walk(
+        xy_new,
+        vtx2tri[i_vtx],
+        tri2vtx,
+        tri2tri,
+        vtx2xy,
+    ) {
+        Ok(i_tri) => i_tri,
+        Err(_) => return false,
+    };
+    let is_overlap = tri2vtx[i_tri_new * 3..i_tri_new * 3 + 3]
+        .iter()
+        .any(|&j_vtx| j_vtx != i_vtx && vtx2xy[j_vtx] == *xy_new);
+    if is_overlap {
+        return false;
+    }
+    // a point on the ring stays in the mesh
+    let j_vtx = tri2vtx[fan[0].0 * 3 + (fan[0].1 + 1) % 3];
+    if !delete_point(i_vtx, tri2vtx, tri2tri, vtx2tri, vtx2xy) {
+        return false;
+    }
+    let xy_old = vtx2xy[i_vtx];
+    vtx2xy[i_vtx] = *xy_new;
+    if !add_point_to_mesh_by_walk(tri2vtx, tri2tri, vtx2tri, vtx2xy, i_vtx, vtx2tri[j_vtx]) {
+        vtx2xy[i_vtx] = xy_old;
+        let res =
+            add_point_to_mesh_by_walk(tri2vtx, tri2tri, vtx2tri, vtx2xy, i_vtx, vtx2tri[j_vtx]);
+        assert!(res);
+        delaunay_around_point(i_vtx, tri2vtx, tri2tri, vtx2tri, vtx2xy);
+        return false;
+    }
+    delaunay_around_point(i_vtx, tri2vtx, tri2tri, vtx2tri, vtx2xy);
+    true
+}
+
 pub struct MeshForTopologicalChange<'a, T> {
     pub tri2vtx: &'a mut Vec<usize>,
     pub tri2tri: &'a mut Vec<usize>,
@@ -849,3 +1189,138 @@ fn test_shape_with_hole() {
         crate::io_obj::save_tri2vtx_vtx2vecn("../target/d.obj", &tri2vtx, &vtx2xy0).unwrap();
     }
 }
+
+#[test]
+fn test_delete_and_move_point() {
+    use rand::Rng;
+    use rand::SeedableRng;
+    let mut rng = rand_chacha::ChaChaRng::seed_from_u64(0);
+    let num_vtx = 300;
+    let mut vtx2xy: Vec<[f64; 2]> = (0..num_vtx)
+        .map(|_| [rng.random::<f64>(), rng.random::<f64>()])
+        .collect();
+    let (mut tri2vtx, mut tri2tri, mut vtx2tri) =
+        make_super_triangle(&mut vtx2xy, &[0., 0.], &[1., 1.]);
+    for i_vtx in 0..num_vtx {
+        let i_tri_hint = if i_vtx == 0 { 0 } else { vtx2tri[i_vtx - 1] };
+        assert!(add_point_to_mesh_by_walk(
+            &mut tri2vtx,
+            &mut tri2tri,
+            &mut vtx2tri,
+            &vtx2xy,
+            i_vtx,
+            i_tri_hint
+        ));
+        delaunay_around_point(i_vtx, &mut tri2vtx, &mut tri2tri, &mut vtx2tri, &vtx2xy);
+    }
+    let check = |tri2vtx: &[usize], tri2tri: &[usize], vtx2tri: &[usize], vtx2xy: &[[f64; 2]]| {
+        for i_tri in 0..tri2vtx.len() / 3 {
+            assert!(crate::trimesh2::area_of_a_triangle(tri2vtx, vtx2xy, i_tri) > 0.);
+            for i_node in 0..3 {
+                assert!(!should_flip(i_tri, i_node, tri2vtx, tri2tri, vtx2xy));
+                let j_tri = tri2tri[i_tri * 3 + i_node];
+                if j_tri != usize::MAX {
+                    assert!(tri2tri[j_tri * 3..j_tri * 3 + 3].contains(&i_tri));
+                }
+            }
+        }
+        for (i_vtx, &i_tri) in vtx2tri.iter().enumerate() {
+            if i_tri != usize::MAX {
+                assert_ne!(
+                    crate::trimesh_topology::find_node(i_vtx, tri2vtx, i_tri),
+                    usize::MAX
+                );
+            }
+        }
+    };
+    check(&tri2vtx, &tri2tri, &vtx2tri, &vtx2xy);
+    assert_eq!(tri2vtx.len() / 3, num_vtx * 2 + 1);
+    // the corner of the super triangle is on the boundary
+    assert!(!delete_point(
+        num_vtx,
+        &mut tri2vtx,
+        &mut tri2tri,
+        &mut vtx2tri,
+        &vtx2xy
+    ));
+    for i_vtx in 0..num_vtx / 2 {
+        assert!(delete_point(
+            i_vtx,
+            &mut tri2vtx,
+            &mut tri2tri,
+            &mut vtx2tri,
+            &vtx2xy
+        ));
+        assert_eq!(vtx2tri[i_vtx], usize::MAX);
+        assert_eq!(tri2vtx.len() / 3, (num_vtx - i_vtx - 1) * 2 + 1);
+    }
+    check(&tri2vtx, &tri2tri, &vtx2tri, &vtx2xy);
+    assert!(tri2vtx.iter().all(|&i_vtx| i_vtx >= num_vtx / 2));
+    for i_vtx in num_vtx / 2..num_vtx {
+        let xy_new = [rng.random::<f64>(), rng.random::<f64>()];
+        assert!(move_point(
+            i_vtx,
+            &xy_new,
+            &mut tri2vtx,
+            &mut tri2tri,
+            &mut vtx2tri,
+            &mut vtx2xy
+        ));
+        assert_eq!(vtx2xy[i_vtx], xy_new);
+    }
+    check(&tri2vtx, &tri2tri, &vtx2tri, &vtx2xy);
+    // outside the super triangle
+    assert!(!move_point(
+        num_vtx - 1,
+        &[100., 100.],
+        &mut tri2vtx,
+        &mut tri2tri,
+        &mut vtx2tri,
+        &mut vtx2xy
+    ));
+}
+
+#[test]
+fn test_enforce_edge() {
+    use rand::Rng;
+    use rand::SeedableRng;
+    let mut rng = rand_chacha::ChaChaRng::seed_from_u64(0);
+    let num_vtx = 100;
+    let mut vtx2xy: Vec<[f64; 2]> = (0..num_vtx)
+        .map(|_| [rng.random::<f64>(), rng.random::<f64>()])
+        .collect();
+    let (mut tri2vtx, mut tri2tri, mut vtx2tri) =
+        make_super_triangle(&mut vtx2xy, &[0., 0.], &[1., 1.]);
+    for i_vtx in 0..num_vtx {
+        add_points_to_mesh(&mut tri2vtx, &mut tri2tri, &mut vtx2tri, &vtx2xy, i_vtx);
+        delaunay_around_point(i_vtx, &mut tri2vtx, &mut tri2tri, &mut vtx2tri, &vtx2xy);
+    }
+    for _ in 0..100 {
+        let i0_vtx = rng.random_range(0..num_vtx);
+        let i1_vtx = rng.random_range(0..num_vtx);
+        if i0_vtx == i1_vtx {
+            continue;
+        }
+        // the edge crosses some triangles, so the walk around `i0_vtx` is needed
+        let (mut tri2vtx, mut tri2tri, mut vtx2tri) =
+            (tri2vtx.clone(), tri2tri.clone(), vtx2tri.clone());
+        enforce_edge(
+            &mut tri2vtx,
+            &mut tri2tri,
+            &mut vtx2tri,
+            i0_vtx,
+            i1_vtx,
+            &vtx2xy,
+        );
+        let (i_tri, i_node) =
+            crate::trimesh_topology::find_edge_by_looking_all_triangles(i0_vtx, i1_vtx, &tri2vtx)
+                .or_else(|| {
+                    crate::trimesh_topology::find_edge_by_looking_all_triangles(
+                        i1_vtx, i0_vtx, &tri2vtx,
+                    )
+                })
+                .unwrap();
+        // the adjacency across the edge is cut
+        assert_eq!(tri2tri[i_tri * 3 + (i_node + 2) % 3], usize::MAX);
+    }
+}
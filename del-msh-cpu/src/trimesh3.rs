@@ -156,6 +156,22 @@ pub fn tri2area(tri2vtx: &[usize], vtx2xyz: &[f32]) -> Vec<f32> {
     tri2area
 }
 
+/// histograms of the angles, aspect ratios, edge-length ratios and areas of the triangles.
+/// See `trimesh::quality` for the detail
+pub fn quality<Real>(
+    tri2vtx: &[usize],
+    vtx2xyz: &[Real],
+    num_bin: usize,
+    num_worst: usize,
+) -> crate::trimesh::Quality<Real>
+where
+    Real: num_traits::Float + 'static,
+    usize: AsPrimitive<Real>,
+    f64: AsPrimitive<Real>,
+{
+    crate::trimesh::quality(tri2vtx, vtx2xyz, 3, num_bin, num_worst)
+}
+
 // above: elem2*** methods
 // ---------------------------
 
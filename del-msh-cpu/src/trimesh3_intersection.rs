@@ -1,4 +1,4 @@
-//! method for finding self-intersection using BVH
+//! method for finding self-intersection and intersection between two meshes using BVH
 
 pub struct IntersectingPair<T> {
     pub i_tri: usize,
@@ -203,6 +203,211 @@ where
     pairs
 }
 
+// ------------------------------------
+// intersection between two different meshes
+
+/// find intersecting triangle pairs between two different meshes using two BVHs
+/// * `i_tri` of the output pair is the triangle index of mesh 0, `j_tri` is of mesh 1
+pub fn search_with_two_bvhs<T>(
+    pairs: &mut Vec<IntersectingPair<T>>,
+    (tri2vtx0, vtx2xyz0, bvhnodes0, aabbs0): (&[usize], &[T], &[usize], &[T]),
+    (tri2vtx1, vtx2xyz1, bvhnodes1, aabbs1): (&[usize], &[T], &[usize], &[T]),
+    ibvh0: usize,
+    ibvh1: usize,
+) where
+    T: num_traits::Float,
+{
+    use crate::search_bvh3_pair::BvhWithTransform;
+    crate::search_bvh3_pair::overlapping_leaves(
+        &mut |i_tri, j_tri| {
+            if let Some((p0, p1)) = intersection_of_triangles_in_two_meshes(
+                tri2vtx0, vtx2xyz0, i_tri, tri2vtx1, vtx2xyz1, j_tri,
+            ) {
+                pairs.push(IntersectingPair {
+                    i_tri,
+                    j_tri,
+                    p0,
+                    p1,
+                });
+            }
+        },
+        &BvhWithTransform {
+            bvhnodes: bvhnodes0,
+            bvhnode2aabb: aabbs0,
+            transform: None,
+        },
+        ibvh0,
+        &BvhWithTransform {
+            bvhnodes: bvhnodes1,
+            bvhnode2aabb: aabbs1,
+            transform: None,
+        },
+        ibvh1,
+        T::zero(),
+    );
+}
+
+fn intersection_of_triangles_in_two_meshes<T>(
+    tri2vtx0: &[usize],
+    vtx2xyz0: &[T],
+    i_tri: usize,
+    tri2vtx1: &[usize],
+    vtx2xyz1: &[T],
+    j_tri: usize,
+) -> Option<([T; 3], [T; 3])>
+where
+    T: num_traits::Float,
+{
+    use crate::vtx2xyz::to_vec3;
+    del_geo_core::tri3::intersection_against_tri3(
+        to_vec3(vtx2xyz0, tri2vtx0[i_tri * 3]),
+        to_vec3(vtx2xyz0, tri2vtx0[i_tri * 3 + 1]),
+        to_vec3(vtx2xyz0, tri2vtx0[i_tri * 3 + 2]),
+        to_vec3(vtx2xyz1, tri2vtx1[j_tri * 3]),
+        to_vec3(vtx2xyz1, tri2vtx1[j_tri * 3 + 1]),
+        to_vec3(vtx2xyz1, tri2vtx1[j_tri * 3 + 2]),
+    )
+}
+
+/// find intersecting triangle pairs between two different meshes by checking all the pairs
+pub fn search_between_two_meshes_brute_force<T>(
+    tri2vtx0: &[usize],
+    vtx2xyz0: &[T],
+    tri2vtx1: &[usize],
+    vtx2xyz1: &[T],
+) -> Vec<IntersectingPair<T>>
+where
+    T: num_traits::Float,
+{
+    let mut pairs: Vec<IntersectingPair<T>> = vec![];
+    for i_tri in 0..tri2vtx0.len() / 3 {
+        for j_tri in 0..tri2vtx1.len() / 3 {
+            if let Some((p0, p1)) = intersection_of_triangles_in_two_meshes(
+                tri2vtx0, vtx2xyz0, i_tri, tri2vtx1, vtx2xyz1, j_tri,
+            ) {
+                pairs.push(IntersectingPair {
+                    i_tri,
+                    j_tri,
+                    p0,
+                    p1,
+                });
+            }
+        }
+    }
+    pairs
+}
+
+/// intersection curve as a sequence of the segments of the intersecting pairs
+pub struct IntersectionPolyline<T> {
+    /// coordinates of the vertices of the polyline
+    pub vtx2xyz: Vec<T>,
+    /// triangle pair `[i_tri, j_tri]` of each segment.
+    /// The segment `i_seg` connects the vertices `i_seg` and `i_seg+1`
+    /// (the last segment of a closed polyline connects the last and the first vertex)
+    pub seg2pair: Vec<[usize; 2]>,
+    pub is_closed: bool,
+}
+
+/// chain the segments of the intersecting pairs into ordered polylines
+/// * `eps` - end points of segments closer than this distance are considered identical
+pub fn polylines_from_intersecting_pairs<T>(
+    pairs: &[IntersectingPair<T>],
+    eps: T,
+) -> Vec<IntersectionPolyline<T>>
+where
+    T: num_traits::Float,
+{
+    use del_geo_core::vec3::Vec3;
+    // merge the end points of the segments using a hash grid with the cell size `eps`.
+    // Points closer than `eps` are in the same or in adjacent cells.
+    let cell = |p: &[T; 3]| -> Option<[i64; 3]> {
+        Some([
+            (p[0] / eps).floor().to_i64()?,
+            (p[1] / eps).floor().to_i64()?,
+            (p[2] / eps).floor().to_i64()?,
+        ])
+    };
+    let mut cell2pnts = std::collections::HashMap::<[i64; 3], Vec<usize>>::new();
+    let mut pnt2xyz: Vec<[T; 3]> = vec![];
+    let mut seg2pnt: Vec<[usize; 2]> = Vec::with_capacity(pairs.len());
+    for pair in pairs {
+        let mut node2pnt = [0usize; 2];
+        for (i_node, p) in [pair.p0, pair.p1].iter().enumerate() {
+            let Some(c) = cell(p) else {
+                // `eps` is not positive or the point is not finite: no merging
+                pnt2xyz.push(*p);
+                node2pnt[i_node] = pnt2xyz.len() - 1;
+                continue;
+            };
+            // take the earliest point so that the result does not depend on the order of the cells
+            let i_pnt_near = (0..27)
+                .filter_map(|d| {
+                    cell2pnts.get(&[c[0] + d % 3 - 1, c[1] + (d / 3) % 3 - 1, c[2] + d / 9 - 1])
+                })
+                .flatten()
+                .filter(|&&j_pnt| pnt2xyz[j_pnt].sub(p).norm() < eps)
+                .min()
+                .copied();
+            node2pnt[i_node] = i_pnt_near.unwrap_or_else(|| {
+                pnt2xyz.push(*p);
+                cell2pnts.entry(c).or_default().push(pnt2xyz.len() - 1);
+                pnt2xyz.len() - 1
+            });
+        }
+        seg2pnt.push(node2pnt);
+    }
+    let mut pnt2seg: Vec<Vec<usize>> = vec![vec![]; pnt2xyz.len()];
+    for (i_seg, node2pnt) in seg2pnt.iter().enumerate() {
+        if node2pnt[0] == node2pnt[1] {
+            continue; // degenerate segment
+        }
+        pnt2seg[node2pnt[0]].push(i_seg);
+        pnt2seg[node2pnt[1]].push(i_seg);
+    }
+    let mut seg2flag: Vec<bool> = seg2pnt.iter().map(|v| v[0] == v[1]).collect();
+    // start from the open ends first, then from the remaining segments that form loops
+    let pnt_starts: Vec<usize> = (0..pnt2xyz.len())
+        .filter(|&i_pnt| pnt2seg[i_pnt].len() == 1)
+        .chain(0..pnt2xyz.len())
+        .collect();
+    let mut polylines = vec![];
+    for i_pnt_start in pnt_starts {
+        while let Some(&i_seg_start) = pnt2seg[i_pnt_start].iter().find(|&&i_seg| !seg2flag[i_seg])
+        {
+            let mut vtx2xyz: Vec<T> = pnt2xyz[i_pnt_start].to_vec();
+            let mut seg2pair: Vec<[usize; 2]> = vec![];
+            let mut i_pnt_cur = i_pnt_start;
+            let mut i_seg_cur = i_seg_start;
+            loop {
+                seg2flag[i_seg_cur] = true;
+                seg2pair.push([pairs[i_seg_cur].i_tri, pairs[i_seg_cur].j_tri]);
+                let node2pnt = seg2pnt[i_seg_cur];
+                i_pnt_cur = if node2pnt[0] == i_pnt_cur {
+                    node2pnt[1]
+                } else {
+                    node2pnt[0]
+                };
+                if i_pnt_cur == i_pnt_start {
+                    break;
+                }
+                vtx2xyz.extend_from_slice(&pnt2xyz[i_pnt_cur]);
+                let Some(&i_seg_next) = pnt2seg[i_pnt_cur].iter().find(|&&i_seg| !seg2flag[i_seg])
+                else {
+                    break;
+                };
+                i_seg_cur = i_seg_next;
+            }
+            let is_closed = i_pnt_cur == i_pnt_start;
+            polylines.push(IntersectionPolyline {
+                vtx2xyz,
+                seg2pair,
+                is_closed,
+            });
+        }
+    }
+    polylines
+}
+
 #[cfg(test)]
 mod tests {
     use crate::trimesh3_intersection::IntersectingPair;
@@ -239,4 +444,103 @@ mod tests {
         );
         assert_eq!(pairs.len(), 0);
     }
+
+    #[test]
+    fn test_polylines_merge_across_cells() {
+        let eps = 1.0e-3f64;
+        // corners of a square; the two copies of each corner lie on the opposite sides of a cell boundary
+        let corner = [[0., 0., 0.], [1., 0., 0.], [1., 1., 0.], [0., 1., 0.]];
+        let shift = |p: [f64; 3], s: f64| [p[0] + s, p[1] - s, p[2] + s];
+        let pairs: Vec<IntersectingPair<f64>> = (0..4)
+            .map(|i| IntersectingPair {
+                i_tri: i,
+                j_tri: i + 4,
+                p0: shift(corner[i], 0.2 * eps),
+                p1: shift(corner[(i + 1) % 4], -0.2 * eps),
+            })
+            .collect();
+        let polylines =
+            crate::trimesh3_intersection::polylines_from_intersecting_pairs(&pairs, eps);
+        assert_eq!(polylines.len(), 1);
+        assert!(polylines[0].is_closed);
+        assert_eq!(polylines[0].vtx2xyz.len(), 12);
+        // nothing is merged with a non-positive tolerance
+        let polylines = crate::trimesh3_intersection::polylines_from_intersecting_pairs(&pairs, 0.);
+        assert_eq!(polylines.len(), 4);
+        assert!(polylines.iter().all(|v| !v.is_closed));
+    }
+
+    #[test]
+    fn test_two_meshes() {
+        let (tri2vtx0, vtx2xyz0) = crate::trimesh3_primitive::sphere_yup::<usize, f32>(1.0, 16, 32);
+        let (tri2vtx1, vtx2xyz1) = crate::trimesh3_primitive::sphere_yup::<usize, f32>(0.8, 16, 32);
+        let trans = [0.93f32, 0.11, 0.07];
+        let vtx2xyz1: Vec<f32> = vtx2xyz1
+            .chunks(3)
+            .flat_map(|p| [p[0] + trans[0], p[1] + trans[1], p[2] + trans[2]])
+            .collect();
+        let bvhnodes0 = crate::bvhnodes_morton::from_triangle_mesh(&tri2vtx0, &vtx2xyz0, 3);
+        let aabbs0 = crate::bvhnode2aabb3::from_uniform_mesh_with_bvh::<usize, f32>(
+            0,
+            &bvhnodes0,
+            Some((&tri2vtx0, 3)),
+            &vtx2xyz0,
+            None,
+        );
+        let bvhnodes1 = crate::bvhnodes_morton::from_triangle_mesh(&tri2vtx1, &vtx2xyz1, 3);
+        let aabbs1 = crate::bvhnode2aabb3::from_uniform_mesh_with_bvh::<usize, f32>(
+            0,
+            &bvhnodes1,
+            Some((&tri2vtx1, 3)),
+            &vtx2xyz1,
+            None,
+        );
+        let mut pairs = Vec::<IntersectingPair<f32>>::new();
+        crate::trimesh3_intersection::search_with_two_bvhs(
+            &mut pairs,
+            (&tri2vtx0, &vtx2xyz0, &bvhnodes0, &aabbs0),
+            (&tri2vtx1, &vtx2xyz1, &bvhnodes1, &aabbs1),
+            0,
+            0,
+        );
+        let pairs_bf = crate::trimesh3_intersection::search_between_two_meshes_brute_force(
+            &tri2vtx0, &vtx2xyz0, &tri2vtx1, &vtx2xyz1,
+        );
+        assert!(!pairs.is_empty());
+        assert_eq!(pairs.len(), pairs_bf.len());
+        {
+            let mut a: Vec<_> = pairs.iter().map(|p| (p.i_tri, p.j_tri)).collect();
+            let mut b: Vec<_> = pairs_bf.iter().map(|p| (p.i_tri, p.j_tri)).collect();
+            a.sort();
+            b.sort();
+            assert_eq!(a, b);
+        }
+        let polylines =
+            crate::trimesh3_intersection::polylines_from_intersecting_pairs(&pairs, 1.0e-5);
+        // two spheres intersect in a single closed curve
+        assert_eq!(polylines.len(), 1);
+        let polyline = &polylines[0];
+        assert!(polyline.is_closed);
+        assert_eq!(polyline.seg2pair.len(), pairs.len());
+        assert_eq!(polyline.vtx2xyz.len() / 3, polyline.seg2pair.len());
+        for (i_seg, &[i_tri, j_tri]) in polyline.seg2pair.iter().enumerate() {
+            let pair = pairs
+                .iter()
+                .find(|p| p.i_tri == i_tri && p.j_tri == j_tri)
+                .unwrap();
+            let num_vtx = polyline.vtx2xyz.len() / 3;
+            let q0 = crate::vtx2xyz::to_vec3(&polyline.vtx2xyz, i_seg);
+            let q1 = crate::vtx2xyz::to_vec3(&polyline.vtx2xyz, (i_seg + 1) % num_vtx);
+            use del_geo_core::vec3::Vec3;
+            let d0 = pair.p0.sub(q0).norm() + pair.p1.sub(q1).norm();
+            let d1 = pair.p0.sub(q1).norm() + pair.p1.sub(q0).norm();
+            assert!(d0.min(d1) < 1.0e-4);
+        }
+        crate::io_obj::save_vtx2xyz_as_polyloop(
+            "../target/trimesh3_intersection_two_meshes.obj",
+            &polyline.vtx2xyz,
+            3,
+        )
+        .unwrap();
+    }
 }
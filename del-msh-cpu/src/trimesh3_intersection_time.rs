@@ -1,7 +1,88 @@
-use num_traits::AsPrimitive;
+//! continuous collision detection (CCD) of a triangle mesh moving from `vtx2xyz0` to `vtx2xyz1`
 
+/// time of impact between two edges. `None` if the edges share a vertex or do not collide
 #[allow(clippy::identity_op)]
+fn time_of_impact_edge_edge<T>(
+    edge2vtx: &[usize],
+    vtx2xyz0: &[T],
+    vtx2xyz1: &[T],
+    i_edge: usize,
+    j_edge: usize,
+    epsilon: T,
+) -> Option<T>
+where
+    T: num_traits::Float,
+{
+    let i0 = edge2vtx[i_edge * 2 + 0];
+    let i1 = edge2vtx[i_edge * 2 + 1];
+    let j0 = edge2vtx[j_edge * 2 + 0];
+    let j1 = edge2vtx[j_edge * 2 + 1];
+    if i0 == j0 || i0 == j1 || i1 == j0 || i1 == j1 {
+        return None;
+    };
+    use crate::vtx2xyz::to_vec3;
+    del_geo_core::ccd3::intersecting_time_ee(
+        del_geo_core::ccd3::EdgeEdge {
+            a0: to_vec3(vtx2xyz0, i0),
+            a1: to_vec3(vtx2xyz0, i1),
+            b0: to_vec3(vtx2xyz0, j0),
+            b1: to_vec3(vtx2xyz0, j1),
+        },
+        del_geo_core::ccd3::EdgeEdge {
+            a0: to_vec3(vtx2xyz1, i0),
+            a1: to_vec3(vtx2xyz1, i1),
+            b0: to_vec3(vtx2xyz1, j0),
+            b1: to_vec3(vtx2xyz1, j1),
+        },
+        epsilon,
+    )
+}
+
+/// time of impact between a triangle and a vertex. `None` if the vertex is a corner of the triangle
+/// or they do not collide
+#[allow(clippy::identity_op)]
+fn time_of_impact_face_vertex<T>(
+    tri2vtx: &[usize],
+    vtx2xyz0: &[T],
+    vtx2xyz1: &[T],
+    i_tri: usize,
+    j_vtx: usize,
+    epsilon: T,
+) -> Option<T>
+where
+    T: num_traits::Float,
+{
+    let i0 = tri2vtx[i_tri * 3 + 0];
+    let i1 = tri2vtx[i_tri * 3 + 1];
+    let i2 = tri2vtx[i_tri * 3 + 2];
+    if i0 == j_vtx || i1 == j_vtx || i2 == j_vtx {
+        return None;
+    };
+    use crate::vtx2xyz::to_vec3;
+    del_geo_core::ccd3::intersecting_time_fv(
+        del_geo_core::ccd3::FaceVertex {
+            f0: to_vec3(vtx2xyz0, i0),
+            f1: to_vec3(vtx2xyz0, i1),
+            f2: to_vec3(vtx2xyz0, i2),
+            v: to_vec3(vtx2xyz0, j_vtx),
+        },
+        del_geo_core::ccd3::FaceVertex {
+            f0: to_vec3(vtx2xyz1, i0),
+            f1: to_vec3(vtx2xyz1, i1),
+            f2: to_vec3(vtx2xyz1, i2),
+            v: to_vec3(vtx2xyz1, j_vtx),
+        },
+        epsilon,
+    )
+}
+
+/// edge-edge CCD between two branches of the BVH of edges.
+/// The AABBs need to enclose the edges swept from `vtx2xyz0` to `vtx2xyz1`.
+/// The colliding pair `[i_edge, j_edge, 0]` and its time of impact are appended
+#[allow(clippy::too_many_arguments)]
 pub fn edge_edge_between_bvh_branches<T>(
+    intersection_pair: &mut Vec<usize>,
+    intersection_time: &mut Vec<T>,
     edge2vtx: &[usize],
     vtx2xyz0: &[T],
     vtx2xyz1: &[T],
@@ -9,156 +90,49 @@ pub fn edge_edge_between_bvh_branches<T>(
     ibvh1: usize,
     bvhnodes: &[usize],
     aabbs: &[T],
+    epsilon: T,
 ) where
-    T: Copy + num_traits::Float + 'static + std::fmt::Debug + std::fmt::Display,
-    i64: AsPrimitive<T>,
-    f64: AsPrimitive<T>,
+    T: num_traits::Float,
 {
-    assert!(ibvh0 < aabbs.len() / 6);
-    assert!(ibvh1 < aabbs.len() / 6);
-    // trim branch
-    if !del_geo_core::aabb3::is_intersect(
-        arrayref::array_ref![aabbs, ibvh0 * 6, 6],
-        arrayref::array_ref![aabbs, ibvh1 * 6, 6],
-    ) {
-        return;
-    }
-    let ichild0_left = bvhnodes[ibvh0 * 3 + 1];
-    let ichild0_right = bvhnodes[ibvh0 * 3 + 2];
-    let ichild1_left = bvhnodes[ibvh1 * 3 + 1];
-    let ichild1_right = bvhnodes[ibvh1 * 3 + 2];
-    let is_leaf0 = ichild0_right == usize::MAX;
-    let is_leaf1 = ichild1_right == usize::MAX;
-    if !is_leaf0 && !is_leaf1 {
-        edge_edge_between_bvh_branches(
-            edge2vtx,
-            vtx2xyz0,
-            vtx2xyz1,
-            ichild0_left,
-            ichild1_left,
-            bvhnodes,
-            aabbs,
-        );
-        edge_edge_between_bvh_branches(
-            edge2vtx,
-            vtx2xyz0,
-            vtx2xyz1,
-            ichild0_right,
-            ichild1_left,
-            bvhnodes,
-            aabbs,
-        );
-        edge_edge_between_bvh_branches(
-            edge2vtx,
-            vtx2xyz0,
-            vtx2xyz1,
-            ichild0_left,
-            ichild1_right,
-            bvhnodes,
-            aabbs,
-        );
-        edge_edge_between_bvh_branches(
-            edge2vtx,
-            vtx2xyz0,
-            vtx2xyz1,
-            ichild0_right,
-            ichild1_right,
-            bvhnodes,
-            aabbs,
-        );
-    } else if !is_leaf0 && is_leaf1 {
-        edge_edge_between_bvh_branches(
-            edge2vtx,
-            vtx2xyz0,
-            vtx2xyz1,
-            ichild0_left,
-            ibvh1,
-            bvhnodes,
-            aabbs,
-        );
-        edge_edge_between_bvh_branches(
-            edge2vtx,
-            vtx2xyz0,
-            vtx2xyz1,
-            ichild0_right,
-            ibvh1,
-            bvhnodes,
-            aabbs,
-        );
-    } else if is_leaf0 && !is_leaf1 {
-        edge_edge_between_bvh_branches(
-            edge2vtx,
-            vtx2xyz0,
-            vtx2xyz1,
-            ibvh0,
-            ichild1_left,
-            bvhnodes,
-            aabbs,
-        );
-        edge_edge_between_bvh_branches(
-            edge2vtx,
-            vtx2xyz0,
-            vtx2xyz1,
-            ibvh0,
-            ichild1_right,
-            bvhnodes,
-            aabbs,
-        );
-    } else if is_leaf0 && is_leaf1 {
-        // check the primitive ccd
-        let i_edge = ichild0_left;
-        let j_edge = ichild1_left;
-        let i0 = edge2vtx[i_edge * 2 + 0];
-        let i1 = edge2vtx[i_edge * 2 + 1];
-        let j0 = edge2vtx[j_edge * 2 + 0];
-        let j1 = edge2vtx[j_edge * 2 + 1];
-        if i0 == j0 || i0 == j1 {
-            return;
-        };
-        if i1 == j0 || i1 == j1 {
-            return;
-        };
-        use crate::vtx2xyz::to_vec3;
-        let a0s = to_vec3(vtx2xyz0, i0);
-        let a1s = to_vec3(vtx2xyz0, i1);
-        let b0s = to_vec3(vtx2xyz0, j0);
-        let b1s = to_vec3(vtx2xyz0, j1);
-        let a0e = to_vec3(vtx2xyz1, i0);
-        let a1e = to_vec3(vtx2xyz1, i1);
-        let b0e = to_vec3(vtx2xyz1, j0);
-        let b1e = to_vec3(vtx2xyz1, j1);
-        let t = del_geo_core::ccd3::intersecting_time_ee(
-            del_geo_core::ccd3::EdgeEdge {
-                a0: a0s,
-                a1: a1s,
-                b0: b0s,
-                b1: b1s,
-            },
-            del_geo_core::ccd3::EdgeEdge {
-                a0: a0e,
-                a1: a1e,
-                b0: b0e,
-                b1: b1e,
-            },
-            1.0e-5f64.as_(),
-        );
-        if let Some(t) = t {
-            dbg!(t);
-        }
-    }
+    use crate::search_bvh3_pair::BvhWithTransform;
+    let bvh = BvhWithTransform {
+        bvhnodes,
+        bvhnode2aabb: aabbs,
+        transform: None,
+    };
+    crate::search_bvh3_pair::overlapping_leaves(
+        &mut |i_edge, j_edge| {
+            // same ordering as the brute force search
+            let (i_edge, j_edge) = (i_edge.min(j_edge), i_edge.max(j_edge));
+            if let Some(t) =
+                time_of_impact_edge_edge(edge2vtx, vtx2xyz0, vtx2xyz1, i_edge, j_edge, epsilon)
+            {
+                intersection_pair.extend([i_edge, j_edge, 0]);
+                intersection_time.push(t);
+            }
+        },
+        &bvh,
+        ibvh0,
+        &bvh,
+        ibvh1,
+        T::zero(),
+    );
 }
 
+/// edge-edge CCD for all the pairs of edges inside a branch of the BVH of edges
+#[allow(clippy::too_many_arguments)]
 pub fn edge_edge_inside_branch<T>(
+    intersection_pair: &mut Vec<usize>,
+    intersection_time: &mut Vec<T>,
     edge2vtx: &[usize],
     vtx2xyz0: &[T],
     vtx2xyz1: &[T],
     ibvh: usize,
     bvhnodes: &[usize],
     aabbs: &[T],
+    epsilon: T,
 ) where
-    T: Copy + num_traits::Float + 'static + std::fmt::Display + std::fmt::Debug,
-    i64: AsPrimitive<T>,
-    f64: AsPrimitive<T>,
+    T: num_traits::Float,
 {
     let ichild_left = bvhnodes[ibvh * 3 + 1];
     let ichild_right = bvhnodes[ibvh * 3 + 2];
@@ -166,6 +140,8 @@ pub fn edge_edge_inside_branch<T>(
         return;
     } // ibvh is a leaf node
     edge_edge_between_bvh_branches(
+        intersection_pair,
+        intersection_time,
         edge2vtx,
         vtx2xyz0,
         vtx2xyz1,
@@ -173,37 +149,148 @@ pub fn edge_edge_inside_branch<T>(
         ichild_right,
         bvhnodes,
         aabbs,
+        epsilon,
+    );
+    for ichild in [ichild_left, ichild_right] {
+        edge_edge_inside_branch(
+            intersection_pair,
+            intersection_time,
+            edge2vtx,
+            vtx2xyz0,
+            vtx2xyz1,
+            ichild,
+            bvhnodes,
+            aabbs,
+            epsilon,
+        );
+    }
+}
+
+/// vertex-triangle CCD between the BVH of triangles and the BVH of vertices.
+/// The AABBs need to enclose the triangles and vertices swept from `vtx2xyz0` to `vtx2xyz1`.
+/// The colliding pair `[i_tri, j_vtx, 1]` and its earliest time of impact are appended
+/// * `ibvh_tri` - root of the BVH of triangles
+/// * `ibvh_vtx` - root of the BVH of vertices
+#[allow(clippy::too_many_arguments)]
+pub fn face_vertex_between_bvh_branches<T>(
+    intersection_pair: &mut Vec<usize>,
+    intersection_time: &mut Vec<T>,
+    tri2vtx: &[usize],
+    vtx2xyz0: &[T],
+    vtx2xyz1: &[T],
+    ibvh_tri: usize,
+    ibvh_vtx: usize,
+    bvhnodes: &[usize],
+    aabbs: &[T],
+    epsilon: T,
+) where
+    T: num_traits::Float,
+{
+    use crate::search_bvh3_pair::BvhWithTransform;
+    let bvh = BvhWithTransform {
+        bvhnodes,
+        bvhnode2aabb: aabbs,
+        transform: None,
+    };
+    crate::search_bvh3_pair::overlapping_leaves(
+        &mut |i_tri, j_vtx| {
+            if let Some(t) =
+                time_of_impact_face_vertex(tri2vtx, vtx2xyz0, vtx2xyz1, i_tri, j_vtx, epsilon)
+            {
+                intersection_pair.extend([i_tri, j_vtx, 1]);
+                intersection_time.push(t);
+            }
+        },
+        &bvh,
+        ibvh_tri,
+        &bvh,
+        ibvh_vtx,
+        T::zero(),
     );
-    edge_edge_inside_branch(edge2vtx, vtx2xyz0, vtx2xyz1, ichild_left, bvhnodes, aabbs);
-    edge_edge_inside_branch(edge2vtx, vtx2xyz0, vtx2xyz1, ichild_right, bvhnodes, aabbs);
 }
 
+/// edge-edge and vertex-triangle CCD using BVH
+/// * `bvhnodes` - BVHs of vertices, edges and triangles concatenated
+/// * `aabbs` - AABBs enclosing the elements swept from `vtx2xyz0` to `vtx2xyz1`
+/// * `roots` - root node indices of the BVH of vertices, edges and triangles
+///
+/// returns the colliding pairs `[i, j, kind]` (0: edge-edge, 1: face-vertex) and their times of impact
+#[allow(clippy::too_many_arguments)]
 pub fn search_with_bvh<T>(
     edge2vtx: &[usize],
     tri2vtx: &[usize],
     vtx2xyz0: &[T],
     vtx2xyz1: &[T],
-    _bvhnodes: &[usize],
-    _aabbs: &[T],
+    bvhnodes: &[usize],
+    aabbs: &[T],
+    roots: &[usize; 3],
+    epsilon: T,
 ) -> (Vec<usize>, Vec<T>)
 where
-    T: num_traits::Float + 'static + std::fmt::Debug + std::fmt::Display,
-    i64: AsPrimitive<T>,
-    f64: AsPrimitive<T>,
+    T: num_traits::Float,
 {
-    /*
-    let mut intersection_pair = vec!(0usize; 0);
-    let mut intersection_times = vec!(T::zero(); 0);
     assert_eq!(vtx2xyz0.len(), vtx2xyz1.len());
-    intersection_pair.clear();
-    intersection_times.clear();
-    (intersection_pair, intersection_times)
-     */
-    // TODO: implement using BVH
-    search_brute_force(edge2vtx, tri2vtx, vtx2xyz0, vtx2xyz1, 1.0e-5.as_())
+    assert_eq!(bvhnodes.len() / 3, aabbs.len() / 6);
+    let mut intersection_pair = vec![0usize; 0];
+    let mut intersection_time: Vec<T> = vec![];
+    edge_edge_inside_branch(
+        &mut intersection_pair,
+        &mut intersection_time,
+        edge2vtx,
+        vtx2xyz0,
+        vtx2xyz1,
+        roots[1],
+        bvhnodes,
+        aabbs,
+        epsilon,
+    );
+    face_vertex_between_bvh_branches(
+        &mut intersection_pair,
+        &mut intersection_time,
+        tri2vtx,
+        vtx2xyz0,
+        vtx2xyz1,
+        roots[2],
+        roots[0],
+        bvhnodes,
+        aabbs,
+        epsilon,
+    );
+    (intersection_pair, intersection_time)
+}
+
+/// the earliest time of impact among all the colliding pairs in the step
+/// from `vtx2xyz0` to `vtx2xyz1` and the pair `[i, j, kind]` (0: edge-edge, 1: face-vertex).
+/// `None` if there is no collision. See [`search_with_bvh`] for the arguments
+#[allow(clippy::too_many_arguments)]
+pub fn earliest_time_of_impact_with_bvh<T>(
+    edge2vtx: &[usize],
+    tri2vtx: &[usize],
+    vtx2xyz0: &[T],
+    vtx2xyz1: &[T],
+    bvhnodes: &[usize],
+    aabbs: &[T],
+    roots: &[usize; 3],
+    epsilon: T,
+) -> Option<(T, [usize; 3])>
+where
+    T: num_traits::Float,
+{
+    let (intersection_pair, intersection_time) = search_with_bvh(
+        edge2vtx, tri2vtx, vtx2xyz0, vtx2xyz1, bvhnodes, aabbs, roots, epsilon,
+    );
+    intersection_time
+        .iter()
+        .enumerate()
+        .min_by(|a, b| a.1.partial_cmp(b.1).unwrap())
+        .map(|(i_pair, &t)| {
+            (
+                t,
+                arrayref::array_ref![intersection_pair, i_pair * 3, 3].to_owned(),
+            )
+        })
 }
 
-#[allow(clippy::identity_op)]
 pub fn search_brute_force<T>(
     edge2vtx: &[usize],
     tri2vtx: &[usize],
@@ -212,53 +299,17 @@ pub fn search_brute_force<T>(
     epsilon: T,
 ) -> (Vec<usize>, Vec<T>)
 where
-    T: num_traits::Float + 'static + std::fmt::Display + std::fmt::Debug,
-    i64: AsPrimitive<T>,
-    f64: AsPrimitive<T>,
+    T: num_traits::Float,
 {
     let mut intersection_pair = vec![0usize; 0];
     let mut intersection_times: Vec<T> = vec![];
     assert_eq!(vtx2xyz0.len(), vtx2xyz1.len());
-    intersection_pair.clear();
-    intersection_times.clear();
-    // edge_edge_inside_branch(edge2vtx, vtx2xyz0, vtx2xyz1, roots[1], bvhnodes, aabbs);
-    //
     let num_edge = edge2vtx.len() / 2;
     for i_edge in 0..num_edge {
         for j_edge in i_edge + 1..num_edge {
-            let i0 = edge2vtx[i_edge * 2 + 0];
-            let i1 = edge2vtx[i_edge * 2 + 1];
-            let j0 = edge2vtx[j_edge * 2 + 0];
-            let j1 = edge2vtx[j_edge * 2 + 1];
-            if i0 == j0 || i0 == j1 || i1 == j0 || i1 == j1 {
-                continue;
-            };
-            use crate::vtx2xyz::to_vec3;
-            let a0s = to_vec3(vtx2xyz0, i0);
-            let a1s = to_vec3(vtx2xyz0, i1);
-            let b0s = to_vec3(vtx2xyz0, j0);
-            let b1s = to_vec3(vtx2xyz0, j1);
-            let a0e = to_vec3(vtx2xyz1, i0);
-            let a1e = to_vec3(vtx2xyz1, i1);
-            let b0e = to_vec3(vtx2xyz1, j0);
-            let b1e = to_vec3(vtx2xyz1, j1);
-            let t = del_geo_core::ccd3::intersecting_time_ee(
-                del_geo_core::ccd3::EdgeEdge {
-                    a0: a0s,
-                    a1: a1s,
-                    b0: b0s,
-                    b1: b1s,
-                },
-                del_geo_core::ccd3::EdgeEdge {
-                    a0: a0e,
-                    a1: a1e,
-                    b0: b0e,
-                    b1: b1e,
-                },
-                epsilon,
-            );
-            if let Some(t) = t {
-                // println!("ee {} {} {}", t, i_edge, j_edge);
+            if let Some(t) =
+                time_of_impact_edge_edge(edge2vtx, vtx2xyz0, vtx2xyz1, i_edge, j_edge, epsilon)
+            {
                 intersection_pair.extend([i_edge, j_edge, 0]);
                 intersection_times.push(t);
             }
@@ -269,38 +320,9 @@ where
     let num_vtx = vtx2xyz0.len() / 3;
     for i_tri in 0..num_tri {
         for j_vtx in 0..num_vtx {
-            let i0 = tri2vtx[i_tri * 3 + 0];
-            let i1 = tri2vtx[i_tri * 3 + 1];
-            let i2 = tri2vtx[i_tri * 3 + 2];
-            if i0 == j_vtx || i1 == j_vtx || i2 == j_vtx {
-                continue;
-            };
-            use crate::vtx2xyz::to_vec3;
-            let f0s = to_vec3(vtx2xyz0, i0);
-            let f1s = to_vec3(vtx2xyz0, i1);
-            let f2s = to_vec3(vtx2xyz0, i2);
-            let v0s = to_vec3(vtx2xyz0, j_vtx);
-            let f0e = to_vec3(vtx2xyz1, i0);
-            let f1e = to_vec3(vtx2xyz1, i1);
-            let f2e = to_vec3(vtx2xyz1, i2);
-            let v0e = to_vec3(vtx2xyz1, j_vtx);
-            let t = del_geo_core::ccd3::intersecting_time_fv(
-                del_geo_core::ccd3::FaceVertex {
-                    f0: f0s,
-                    f1: f1s,
-                    f2: f2s,
-                    v: v0s,
-                },
-                del_geo_core::ccd3::FaceVertex {
-                    f0: f0e,
-                    f1: f1e,
-                    f2: f2e,
-                    v: v0e,
-                },
-                epsilon,
-            );
-            if let Some(t) = t {
-                // println!("fv {} {} {}", t, i_tri, j_vtx);
+            if let Some(t) =
+                time_of_impact_face_vertex(tri2vtx, vtx2xyz0, vtx2xyz1, i_tri, j_vtx, epsilon)
+            {
                 intersection_pair.extend([i_tri, j_vtx, 1]);
                 intersection_times.push(t);
             }
@@ -333,3 +355,83 @@ where
         }
     }
 }
+
+#[test]
+fn test_ccd() {
+    // two spheres approaching each other. The gap closes at around t=0.5
+    let (tri2vtx, vtx2xyz0, vtx2xyz1) = {
+        let (tri2vtx_s, vtx2xyz_s) =
+            crate::trimesh3_primitive::sphere_yup::<usize, f32>(0.5, 8, 16);
+        let mut tri2vtx = vec![];
+        let mut vtx2xyz0 = vec![];
+        crate::uniform_mesh::merge(&mut tri2vtx, &mut vtx2xyz0, &tri2vtx_s, &vtx2xyz_s, 3);
+        let vtx2xyz_t: Vec<f32> = vtx2xyz_s
+            .chunks(3)
+            .flat_map(|p| [p[0] + 1.3, p[1] + 0.03, p[2] + 0.02])
+            .collect();
+        crate::uniform_mesh::merge(&mut tri2vtx, &mut vtx2xyz0, &tri2vtx_s, &vtx2xyz_t, 3);
+        let num_vtx_s = vtx2xyz_s.len() / 3;
+        let vtx2xyz1: Vec<f32> = vtx2xyz0
+            .chunks(3)
+            .enumerate()
+            .flat_map(|(i_vtx, p)| {
+                let d = if i_vtx < num_vtx_s { 0.0 } else { -0.6 };
+                [p[0] + d, p[1], p[2]]
+            })
+            .collect();
+        (tri2vtx, vtx2xyz0, vtx2xyz1)
+    };
+    let num_vtx = vtx2xyz0.len() / 3;
+    let edge2vtx = crate::edge2vtx::from_triangle_mesh(&tri2vtx, num_vtx);
+    // concatenate the BVHs of the vertices, edges and triangles
+    let (bvhnodes, roots) = {
+        let edge2cntr = crate::elem2center::from_uniform_mesh_as_points(&edge2vtx, 2, &vtx2xyz0, 3);
+        let tri2cntr = crate::elem2center::from_uniform_mesh_as_points(&tri2vtx, 3, &vtx2xyz0, 3);
+        let (bvhnodes, roots) = crate::bvhnodes::concatenate(&[
+            &crate::bvhnodes_morton::from_vtx2xyz::<usize>(&vtx2xyz0, 3),
+            &crate::bvhnodes_morton::from_vtx2xyz::<usize>(&edge2cntr, 3),
+            &crate::bvhnodes_morton::from_vtx2xyz::<usize>(&tri2cntr, 3),
+        ]);
+        (bvhnodes, [roots[0], roots[1], roots[2]])
+    };
+    let mut aabbs = vec![0f32; bvhnodes.len() / 3 * 6];
+    for (i_root, elem2vtx) in [
+        None,
+        Some((edge2vtx.as_slice(), 2)),
+        Some((tri2vtx.as_slice(), 3)),
+    ]
+    .into_iter()
+    .enumerate()
+    {
+        crate::bvhnode2aabb3::update_for_uniform_mesh_with_bvh(
+            &mut aabbs,
+            roots[i_root],
+            &bvhnodes,
+            elem2vtx,
+            &vtx2xyz0,
+            Some(&vtx2xyz1),
+        );
+    }
+    let (pairs0, times0) = search_brute_force(&edge2vtx, &tri2vtx, &vtx2xyz0, &vtx2xyz1, 1.0e-5);
+    let (pairs1, times1) = search_with_bvh(
+        &edge2vtx, &tri2vtx, &vtx2xyz0, &vtx2xyz1, &bvhnodes, &aabbs, &roots, 1.0e-5,
+    );
+    assert!(pairs0.chunks(3).any(|v| v[2] == 0));
+    assert!(pairs0.chunks(3).any(|v| v[2] == 1));
+    {
+        let mut a: Vec<_> = pairs0.chunks(3).zip(times0.iter()).collect();
+        let mut b: Vec<_> = pairs1.chunks(3).zip(times1.iter()).collect();
+        a.sort_by(|x, y| x.0.cmp(y.0));
+        b.sort_by(|x, y| x.0.cmp(y.0));
+        assert_eq!(a, b);
+    }
+    let (t, pair) = earliest_time_of_impact_with_bvh(
+        &edge2vtx, &tri2vtx, &vtx2xyz0, &vtx2xyz1, &bvhnodes, &aabbs, &roots, 1.0e-5,
+    )
+    .unwrap();
+    assert!(t > 0.45 && t < 0.6, "{t}");
+    let t_min = times0.iter().fold(f32::MAX, |a, &b| a.min(b));
+    assert_eq!(t, t_min);
+    let i_pair = pairs1.chunks(3).position(|v| v == pair).unwrap();
+    assert_eq!(times1[i_pair], t);
+}
@@ -46,7 +46,6 @@ fn wdw_proximity<T>(
                 &q1.scale(prox_param[iprox * 4 + 3]),
             );
             let dist1 = pc.sub(&qc).norm();
-            println!("  proximity edge {dist1}");
             assert!(dist1 <= dist0);
             let eng1 = barrier(dist1);
             let deng1 = diff_barrier(dist1);
@@ -78,7 +77,6 @@ fn wdw_proximity<T>(
                 &p2.scale(prox_param[iprox * 4 + 2]),
             );
             let dist1 = pc.sub(q0).norm();
-            println!("  proximity face {dist1}");
             assert!(dist1 <= dist0);
             let eng1 = barrier(dist1);
             let deng1 = diff_barrier(dist1);
@@ -97,142 +95,536 @@ fn wdw_proximity<T>(
     }
 }
 
-fn wdw(
-    edge2vtx: &[usize],
-    tri2vtx: &[usize],
-    vtx2xyz: &[f64],
-    vtx2xyz_goal: &[f64],
-    dist0: f64,
-    k_contact: f64,
-    k_diff: f64,
-) -> (f64, Vec<f64>) {
-    let mut sum_eng = 0f64;
-    let mut res = vec![0f64; vtx2xyz.len()];
-    let (prox_idx, prox_param) =
-        crate::trimesh3_proximity::contacting_pair(tri2vtx, vtx2xyz, edge2vtx, dist0);
-    wdw_proximity(
-        &mut sum_eng,
-        &mut res,
-        &prox_idx,
-        &prox_param,
-        Mesh {
+/// parameters of [`deform_while_avoid_collision`]
+pub struct Params<T> {
+    /// stiffness of the energy pulling the vertices to the goal
+    pub k_diff: T,
+    /// stiffness of the contact barrier
+    pub k_contact: T,
+    /// distance below which the contact barrier is active
+    pub dist0: T,
+    /// step size of the gradient descent
+    pub alpha: T,
+    pub num_iter: usize,
+    /// the iteration stops when the largest gradient of the free vertices is below this value
+    pub tolerance: T,
+}
+
+/// static triangle mesh the deforming mesh should not penetrate
+pub struct Obstacle<'a, T> {
+    pub tri2vtx: &'a [usize],
+    pub vtx2xyz: &'a [T],
+}
+
+#[derive(Default)]
+pub struct Constraints<'a, T> {
+    /// weight of each vertex for the energy pulling it to the goal. `None` for the uniform weight
+    pub vtx2weight: Option<&'a [T]>,
+    /// vertices that do not move from the start position
+    pub vtx2isfix: Option<&'a [bool]>,
+    pub obstacles: &'a [Obstacle<'a, T>],
+}
+
+/// deforming mesh and the obstacles merged into one mesh with the BVHs of the vertices,
+/// edges and triangles. The obstacle vertices are appended after the deforming mesh's vertices
+struct Scene<T> {
+    tri2vtx: Vec<usize>,
+    edge2vtx: Vec<usize>,
+    vtx2goal: Vec<T>,
+    vtx2weight: Vec<T>,
+    vtx2isfix: Vec<bool>,
+    bvhnodes: Vec<usize>,
+    roots: [usize; 3],
+    aabbs: Vec<T>,
+}
+
+impl<T> Scene<T>
+where
+    T: num_traits::Float + 'static + std::fmt::Display + std::fmt::Debug,
+    f64: num_traits::AsPrimitive<T>,
+    T: num_traits::AsPrimitive<f32>,
+{
+    fn new(
+        tri2vtx: &[usize],
+        vtx2xyz_start: &[T],
+        vtx2xyz_goal: &[T],
+        constraints: &Constraints<T>,
+    ) -> (Self, Vec<T>) {
+        let num_vtx = vtx2xyz_start.len() / 3;
+        let mut tri2vtx = tri2vtx.to_vec();
+        let mut vtx2xyz = vtx2xyz_start.to_vec();
+        let mut vtx2goal = vtx2xyz_goal.to_vec();
+        let mut vtx2weight = match constraints.vtx2weight {
+            Some(vtx2weight) => vtx2weight.to_vec(),
+            None => vec![T::one(); num_vtx],
+        };
+        let mut vtx2isfix = match constraints.vtx2isfix {
+            Some(vtx2isfix) => vtx2isfix.to_vec(),
+            None => vec![false; num_vtx],
+        };
+        assert_eq!(vtx2weight.len(), num_vtx);
+        assert_eq!(vtx2isfix.len(), num_vtx);
+        for obstacle in constraints.obstacles {
+            crate::uniform_mesh::merge(
+                &mut tri2vtx,
+                &mut vtx2xyz,
+                obstacle.tri2vtx,
+                obstacle.vtx2xyz,
+                3,
+            );
+            vtx2goal.extend_from_slice(obstacle.vtx2xyz);
+            vtx2weight.resize(vtx2xyz.len() / 3, T::zero());
+            vtx2isfix.resize(vtx2xyz.len() / 3, true);
+        }
+        let edge2vtx = crate::edge2vtx::from_triangle_mesh(&tri2vtx, vtx2xyz.len() / 3);
+        let (bvhnodes, roots) = {
+            let vtx2xyz: Vec<f32> = vtx2xyz.iter().map(|&v| v.as_()).collect();
+            let edge2cntr =
+                crate::elem2center::from_uniform_mesh_as_points(&edge2vtx, 2, &vtx2xyz, 3);
+            let tri2cntr =
+                crate::elem2center::from_uniform_mesh_as_points(&tri2vtx, 3, &vtx2xyz, 3);
+            let (bvhnodes, roots) = crate::bvhnodes::concatenate(&[
+                &crate::bvhnodes_morton::from_vtx2xyz::<usize>(&vtx2xyz, 3),
+                &crate::bvhnodes_morton::from_vtx2xyz::<usize>(&edge2cntr, 3),
+                &crate::bvhnodes_morton::from_vtx2xyz::<usize>(&tri2cntr, 3),
+            ]);
+            (bvhnodes, [roots[0], roots[1], roots[2]])
+        };
+        let aabbs = vec![T::zero(); bvhnodes.len() / 3 * 6];
+        let scene = Scene {
             tri2vtx,
             edge2vtx,
-            vtx2xyz,
-        },
-        dist0,
-        k_contact,
-    );
-    for i in 0..vtx2xyz.len() {
-        let d: f64 = vtx2xyz[i] - vtx2xyz_goal[i];
-        sum_eng += 0.5 * d * d * k_diff;
-        res[i] += d * k_diff;
+            vtx2goal,
+            vtx2weight,
+            vtx2isfix,
+            bvhnodes,
+            roots,
+            aabbs,
+        };
+        (scene, vtx2xyz)
     }
-    (sum_eng, res)
-}
 
-pub struct Params {
-    pub k_diff: f64,
-    pub k_contact: f64,
-    pub dist0: f64,
-    pub alpha: f64,
-    pub num_iter: usize,
-}
+    /// if `vtx2xyz1` is `Some`, the AABBs enclose the elements swept from `vtx2xyz0` to `vtx2xyz1`
+    fn update_aabbs(&mut self, vtx2xyz0: &[T], vtx2xyz1: Option<&[T]>) {
+        for (i_root, elem2vtx) in [
+            None,
+            Some((self.edge2vtx.as_slice(), 2)),
+            Some((self.tri2vtx.as_slice(), 3)),
+        ]
+        .into_iter()
+        .enumerate()
+        {
+            crate::bvhnode2aabb3::update_for_uniform_mesh_with_bvh(
+                &mut self.aabbs,
+                self.roots[i_root],
+                &self.bvhnodes,
+                elem2vtx,
+                vtx2xyz0,
+                vtx2xyz1,
+            );
+        }
+    }
 
-pub fn match_vtx2xyz_while_avoid_collision(
-    tri2vtx: &[usize],
-    vtx2xyz_start: &[f64],
-    vtx2xyz_goal: &[f64],
-    params: Params,
-) -> Vec<f64> {
-    {
-        // there should be no self-intersection in the vtx2xyz_start mesh
-        let tripairs = crate::trimesh3_intersection::search_brute_force(tri2vtx, vtx2xyz_start);
-        for pair in tripairs.iter() {
-            dbg!(pair.i_tri, pair.j_tri);
+    /// energy and its gradient. The AABBs need to be updated for `vtx2xyz` beforehand
+    fn wdw(&self, vtx2xyz: &[T], params: &Params<T>) -> (T, Vec<T>) {
+        let mut sum_eng = T::zero();
+        let mut res = vec![T::zero(); vtx2xyz.len()];
+        let (prox_idx, prox_param) = {
+            let (prox_idx, prox_param) = crate::trimesh3_proximity::contacting_pair_with_bvh(
+                &self.tri2vtx,
+                vtx2xyz,
+                &self.edge2vtx,
+                params.dist0,
+                &self.bvhnodes,
+                &self.aabbs,
+                &self.roots,
+            );
+            // contacts between the fixed vertices do not change
+            let mut prox_idx1 = vec![];
+            let mut prox_param1 = vec![];
+            for (idxs, param) in prox_idx.chunks(3).zip(prox_param.chunks(4)) {
+                let (elem2vtx, num_node) = if idxs[2] == 0 {
+                    (&self.edge2vtx, 2)
+                } else {
+                    (&self.tri2vtx, 3)
+                };
+                let is_fix_i = elem2vtx[idxs[0] * num_node..(idxs[0] + 1) * num_node]
+                    .iter()
+                    .all(|&i_vtx| self.vtx2isfix[i_vtx]);
+                let is_fix_j = if idxs[2] == 0 {
+                    self.edge2vtx[idxs[1] * 2..idxs[1] * 2 + 2]
+                        .iter()
+                        .all(|&j_vtx| self.vtx2isfix[j_vtx])
+                } else {
+                    self.vtx2isfix[idxs[1]]
+                };
+                if is_fix_i && is_fix_j {
+                    continue;
+                }
+                prox_idx1.extend_from_slice(idxs);
+                prox_param1.extend_from_slice(param);
+            }
+            (prox_idx1, prox_param1)
+        };
+        wdw_proximity(
+            &mut sum_eng,
+            &mut res,
+            &prox_idx,
+            &prox_param,
+            Mesh {
+                tri2vtx: &self.tri2vtx,
+                edge2vtx: &self.edge2vtx,
+                vtx2xyz,
+            },
+            params.dist0,
+            params.k_contact,
+        );
+        let half = T::one() / (T::one() + T::one());
+        for i_vtx in 0..vtx2xyz.len() / 3 {
+            if self.vtx2isfix[i_vtx] {
+                res[i_vtx * 3..i_vtx * 3 + 3].fill(T::zero());
+                continue;
+            }
+            let k = params.k_diff * self.vtx2weight[i_vtx];
+            for i_dim in 0..3 {
+                let d = vtx2xyz[i_vtx * 3 + i_dim] - self.vtx2goal[i_vtx * 3 + i_dim];
+                sum_eng = sum_eng + half * d * d * k;
+                res[i_vtx * 3 + i_dim] = res[i_vtx * 3 + i_dim] + d * k;
+            }
         }
-        assert_eq!(tripairs.len(), 0, "there should be no intersections in start mesh but there are {:} intersecting try pairs", tripairs.len());
+        (sum_eng, res)
     }
-    let edge2vtx = crate::edge2vtx::from_triangle_mesh(tri2vtx, vtx2xyz_start.len() / 3);
+}
+
+/// move the vertices of a triangle mesh toward `vtx2xyz_goal` by gradient descent
+/// while avoiding the self-collision and the collision against the obstacles.
+/// The contact and the continuous collision detection use BVHs.
+/// * `vtx2xyz_start` - there should be no intersection in the start mesh
+/// * `callback` - called as `callback(i_iter, vtx2xyz, energy)` after every iteration
+///
+/// returns the vertex coordinates of the deformed mesh
+pub fn deform_while_avoid_collision<T, F>(
+    tri2vtx: &[usize],
+    vtx2xyz_start: &[T],
+    vtx2xyz_goal: &[T],
+    params: &Params<T>,
+    constraints: &Constraints<T>,
+    callback: &mut F,
+) -> Vec<T>
+where
+    T: num_traits::Float + 'static + std::fmt::Display + std::fmt::Debug,
+    f64: num_traits::AsPrimitive<T>,
+    T: num_traits::AsPrimitive<f32>,
+    F: FnMut(usize, &[T], T),
+{
+    use num_traits::AsPrimitive;
     assert_eq!(vtx2xyz_start.len(), vtx2xyz_goal.len());
-    //
-    let mut vtx2xyz = Vec::<f64>::from(vtx2xyz_start);
-    for _itr_outer in 0..params.num_iter {
-        // crate::io_obj::save_tri_mesh(format!("target/frame_{}.obj",_itr_outer), &tri2vtx, &vtx2xyz);
-        let (w0, dw0) = wdw(
-            &edge2vtx,
-            tri2vtx,
+    let num_vtx = vtx2xyz_start.len() / 3;
+    let (mut scene, mut vtx2xyz) = Scene::new(tri2vtx, vtx2xyz_start, vtx2xyz_goal, constraints);
+    scene.update_aabbs(&vtx2xyz, None);
+    {
+        // there should be no intersection in the start mesh
+        let mut tripairs = vec![];
+        crate::trimesh3_intersection::search_with_bvh_inside_branch(
+            &mut tripairs,
+            &scene.tri2vtx,
             &vtx2xyz,
-            vtx2xyz_goal,
-            params.dist0,
-            params.k_contact,
-            params.k_diff,
+            scene.roots[2],
+            &scene.bvhnodes,
+            &scene.aabbs,
         );
-        let step: Vec<_> = dw0.iter().map(|r| r * -params.alpha).collect();
-        let vtx2xyz_dist: Vec<f64> = vtx2xyz
+        assert_eq!(
+            tripairs.len(),
+            0,
+            "there should be no intersections in start mesh but there are {:} intersecting tri pairs",
+            tripairs.len()
+        );
+    }
+    for itr in 0..params.num_iter {
+        let (w0, dw0) = scene.wdw(&vtx2xyz, params);
+        let grad_max = dw0.iter().fold(T::zero(), |a, &b| a.max(b.abs()));
+        if grad_max < params.tolerance {
+            break;
+        }
+        let step: Vec<_> = dw0.iter().map(|&r| -r * params.alpha).collect();
+        let vtx2xyz_dist: Vec<T> = vtx2xyz
             .iter()
             .zip(step.iter())
-            .map(|(v, r)| v + r)
+            .map(|(&v, &r)| v + r)
             .collect();
-        //
-        let time_max = {
-            let (intersection_pair, intersection_time) =
-                crate::trimesh3_intersection_time::search_brute_force(
-                    &edge2vtx,
-                    tri2vtx,
-                    &vtx2xyz,
-                    &vtx2xyz_dist,
-                    f64::EPSILON,
-                );
-            assert_eq!(intersection_pair.len(), intersection_time.len() * 3);
-            dbg!(intersection_pair.len(), intersection_time.len());
-            let time_max = intersection_time
-                .iter()
-                .fold(f64::INFINITY, |a, &b| a.min(b));
-            time_max.clamp(0., 1.)
-        };
-
-        let mut coeff = 0.9;
+        scene.update_aabbs(&vtx2xyz, Some(&vtx2xyz_dist));
+        let time_max = crate::trimesh3_intersection_time::earliest_time_of_impact_with_bvh(
+            &scene.edge2vtx,
+            &scene.tri2vtx,
+            &vtx2xyz,
+            &vtx2xyz_dist,
+            &scene.bvhnodes,
+            &scene.aabbs,
+            &scene.roots,
+            T::epsilon(),
+        )
+        .map_or(T::one(), |(t, _)| t.min(T::one()).max(T::zero()));
+        let mut w = w0;
+        let mut coeff: T = 0.9.as_();
         for _itr in 0..10 {
             let vtx2xyz_cand: Vec<_> = vtx2xyz
                 .iter()
                 .zip(step.iter())
-                .map(|(v, r)| v + r * time_max * coeff)
+                .map(|(&v, &r)| v + r * time_max * coeff)
                 .collect();
-            {
-                let tripairs =
-                    crate::trimesh3_intersection::search_brute_force(tri2vtx, &vtx2xyz_cand);
-                println!("# of intersecting tripairs  {:}", tripairs.len());
-                if !tripairs.is_empty() {
-                    dbg!("something is wrong");
-                    crate::io_off::save_tri_mesh("target/cand0.off", tri2vtx, &vtx2xyz);
-                    crate::io_off::save_tri_mesh("target/cand1.off", tri2vtx, &vtx2xyz_cand);
-                    //panic!();
-                }
-                // assert_eq!(tripairs.len(),0);
-            }
-            let (w, _) = wdw(
-                &edge2vtx,
-                tri2vtx,
-                &vtx2xyz_cand,
-                vtx2xyz_goal,
-                params.dist0,
-                params.k_contact,
-                params.k_diff,
-            );
-            dbg!(w0, w);
-            if w < w0 {
-                vtx2xyz
-                    .iter_mut()
-                    .zip(vtx2xyz_cand.iter())
-                    .for_each(|(v, &c)| *v = c);
+            scene.update_aabbs(&vtx2xyz_cand, None);
+            let (w1, _) = scene.wdw(&vtx2xyz_cand, params);
+            if w1 < w0 {
+                vtx2xyz = vtx2xyz_cand;
+                w = w1;
                 break;
             }
-            coeff *= 0.5;
-            println!("divide coeff {coeff}");
+            coeff = coeff * 0.5.as_();
         }
+        scene.update_aabbs(&vtx2xyz, None);
+        callback(itr, &vtx2xyz[..num_vtx * 3], w);
     }
+    vtx2xyz.truncate(num_vtx * 3);
     vtx2xyz
 }
 
-// TODO: write some test
+pub fn match_vtx2xyz_while_avoid_collision<T>(
+    tri2vtx: &[usize],
+    vtx2xyz_start: &[T],
+    vtx2xyz_goal: &[T],
+    params: Params<T>,
+) -> Vec<T>
+where
+    T: num_traits::Float + 'static + std::fmt::Display + std::fmt::Debug,
+    f64: num_traits::AsPrimitive<T>,
+    T: num_traits::AsPrimitive<f32>,
+{
+    deform_while_avoid_collision(
+        tri2vtx,
+        vtx2xyz_start,
+        vtx2xyz_goal,
+        &params,
+        &Constraints {
+            vtx2weight: None,
+            vtx2isfix: None,
+            obstacles: &[],
+        },
+        &mut |_, _, _| {},
+    )
+}
+
+// ------------------------------------
+// untangle self-intersecting mesh
+
+/// state of the mesh when `untangle_self_intersection` gives up
+#[derive(Debug)]
+pub struct UntangleReport {
+    pub num_iter: usize,
+    /// number of intersecting triangle pairs left
+    pub num_intersecting_pair: usize,
+    /// total length of the intersection contours left
+    pub contour_length: f64,
+}
+
+/// displacement of the vertices that pushes each intersecting triangle pair apart.
+/// The vertices of a triangle behind the plane of the other triangle are moved to its front
+/// side, assuming the triangles are consistently oriented
+#[allow(clippy::identity_op)]
+fn vtx2disp_separating_intersection(
+    tri2vtx: &[usize],
+    vtx2xyz: &[f64],
+    pairs: &[crate::trimesh3_intersection::IntersectingPair<f64>],
+    margin: f64,
+) -> Vec<f64> {
+    use crate::vtx2xyz::to_vec3;
+    use del_geo_core::vec3::Vec3;
+    let num_vtx = vtx2xyz.len() / 3;
+    let mut vtx2disp = vec![0f64; num_vtx * 3];
+    let mut vtx2cnt = vec![0usize; num_vtx];
+    let tri2unorm = |i_tri: usize| {
+        del_geo_core::tri3::normal(
+            to_vec3(vtx2xyz, tri2vtx[i_tri * 3 + 0]),
+            to_vec3(vtx2xyz, tri2vtx[i_tri * 3 + 1]),
+            to_vec3(vtx2xyz, tri2vtx[i_tri * 3 + 2]),
+        )
+        .normalize()
+    };
+    for pair in pairs {
+        let n_i = tri2unorm(pair.i_tri);
+        let n_j = tri2unorm(pair.j_tri);
+        // (moving triangle, normal of the other triangle)
+        for (i_tri, n) in [(pair.i_tri, n_j), (pair.j_tri, n_i)] {
+            for &i_vtx in &tri2vtx[i_tri * 3..i_tri * 3 + 3] {
+                let dist = to_vec3(vtx2xyz, i_vtx).sub(&pair.p0).dot(&n);
+                if dist > margin {
+                    continue;
+                }
+                // each triangle covers the half of the penetration
+                let d = n.scale(0.5 * (margin - dist));
+                vtx2disp[i_vtx * 3 + 0] += d[0];
+                vtx2disp[i_vtx * 3 + 1] += d[1];
+                vtx2disp[i_vtx * 3 + 2] += d[2];
+                vtx2cnt[i_vtx] += 1;
+            }
+        }
+    }
+    for i_vtx in 0..num_vtx {
+        if vtx2cnt[i_vtx] == 0 {
+            continue;
+        }
+        let s = 1f64 / vtx2cnt[i_vtx] as f64;
+        vtx2disp[i_vtx * 3..i_vtx * 3 + 3]
+            .iter_mut()
+            .for_each(|v| *v *= s);
+    }
+    vtx2disp
+}
+
+/// iteratively move the vertices of a self-intersecting mesh until there is no intersection
+/// * `margin` - distance the vertices are pushed beyond the other triangle's plane
+/// * `step` - relaxation factor of the displacement in (0,1]
+///
+/// returns the number of iterations used, or the report of the remaining intersections
+/// if the mesh is not clean after `num_iter` iterations
+pub fn untangle_self_intersection(
+    tri2vtx: &[usize],
+    vtx2xyz: &mut [f64],
+    margin: f64,
+    step: f64,
+    num_iter: usize,
+) -> Result<usize, UntangleReport> {
+    use del_geo_core::vec3::Vec3;
+    let bvhnodes = crate::bvhnodes_topdown_trimesh3::from_triangle_mesh(tri2vtx, vtx2xyz);
+    let mut bvhnode2aabb = vec![0f64; bvhnodes.len() / 3 * 6];
+    let mut pairs = vec![];
+    for itr in 0..=num_iter {
+        crate::bvhnode2aabb3::update_for_uniform_mesh_with_bvh::<usize, f64>(
+            &mut bvhnode2aabb,
+            0,
+            &bvhnodes,
+            Some((tri2vtx, 3)),
+            vtx2xyz,
+            None,
+        );
+        pairs.clear();
+        crate::trimesh3_intersection::search_with_bvh_inside_branch(
+            &mut pairs,
+            tri2vtx,
+            vtx2xyz,
+            0,
+            &bvhnodes,
+            &bvhnode2aabb,
+        );
+        if pairs.is_empty() {
+            return Ok(itr);
+        }
+        if itr == num_iter {
+            break;
+        }
+        let vtx2disp = vtx2disp_separating_intersection(tri2vtx, vtx2xyz, &pairs, margin);
+        vtx2xyz
+            .iter_mut()
+            .zip(vtx2disp.iter())
+            .for_each(|(v, &d)| *v += d * step);
+    }
+    Err(UntangleReport {
+        num_iter,
+        num_intersecting_pair: pairs.len(),
+        contour_length: pairs.iter().map(|pair| pair.p0.sub(&pair.p1).norm()).sum(),
+    })
+}
+
+#[test]
+fn test_untangle_self_intersection() {
+    let (tri2vtx0, vtx2xyz0) = crate::trimesh3_primitive::sphere_yup::<usize, f64>(1.0, 16, 16);
+    let mut vtx2xyz1 = vec![0f64; vtx2xyz0.len()];
+    crate::vtx2xyz::translate_then_scale(&mut vtx2xyz1, &vtx2xyz0, &[1.7, 0.1, 0.05], 1.);
+    let mut tri2vtx = vec![];
+    let mut vtx2xyz = vec![];
+    crate::uniform_mesh::merge(&mut tri2vtx, &mut vtx2xyz, &tri2vtx0, &vtx2xyz0, 3);
+    crate::uniform_mesh::merge(&mut tri2vtx, &mut vtx2xyz, &tri2vtx0, &vtx2xyz1, 3);
+    let num_pair_ini = crate::trimesh3_intersection::search_brute_force(&tri2vtx, &vtx2xyz).len();
+    assert!(num_pair_ini > 0);
+    // too few iterations
+    {
+        let mut vtx2xyz = vtx2xyz.clone();
+        let report = untangle_self_intersection(&tri2vtx, &mut vtx2xyz, 1.0e-3, 0.5, 1);
+        let report = report.expect_err("should not converge in one iteration");
+        assert_eq!(report.num_iter, 1);
+        assert!(report.num_intersecting_pair > 0);
+        assert!(report.contour_length > 0.);
+    }
+    let num_iter = untangle_self_intersection(&tri2vtx, &mut vtx2xyz, 1.0e-3, 0.5, 100).unwrap();
+    assert!(num_iter > 0);
+    crate::io_obj::save_tri2vtx_vtx2xyz("../target/untangle.obj", &tri2vtx, &vtx2xyz, 3).unwrap();
+    assert!(crate::trimesh3_intersection::search_brute_force(&tri2vtx, &vtx2xyz).is_empty());
+}
+
+#[test]
+fn test_deform_while_avoid_collision() {
+    // a small sphere pulled into a large sphere obstacle
+    let (tri2vtx, vtx2xyz_start) = crate::trimesh3_primitive::sphere_yup::<usize, f32>(0.3, 6, 12);
+    let vtx2xyz_start: Vec<f32> = vtx2xyz_start
+        .chunks(3)
+        .flat_map(|p| [p[0] - 1.0, p[1] + 0.02, p[2] + 0.01])
+        .collect();
+    let vtx2xyz_goal: Vec<f32> = vtx2xyz_start
+        .chunks(3)
+        .flat_map(|p| [p[0] + 1.0, p[1], p[2]])
+        .collect();
+    let (tri2vtx_obs, vtx2xyz_obs) =
+        crate::trimesh3_primitive::sphere_yup::<usize, f32>(0.5, 8, 16);
+    let num_vtx = vtx2xyz_start.len() / 3;
+    let vtx2isfix: Vec<bool> = (0..num_vtx).map(|i_vtx| i_vtx == 0).collect();
+    let vtx2weight: Vec<f32> = (0..num_vtx)
+        .map(|i_vtx| if i_vtx % 2 == 0 { 1.0 } else { 0.5 })
+        .collect();
+    let params = Params {
+        k_diff: 1.0,
+        k_contact: 10.0,
+        dist0: 0.03,
+        alpha: 0.02,
+        num_iter: 50,
+        tolerance: 1.0e-3,
+    };
+    let mut itr2eng = vec![];
+    let vtx2xyz = deform_while_avoid_collision(
+        &tri2vtx,
+        &vtx2xyz_start,
+        &vtx2xyz_goal,
+        &params,
+        &Constraints {
+            vtx2weight: Some(&vtx2weight),
+            vtx2isfix: Some(&vtx2isfix),
+            obstacles: &[Obstacle {
+                tri2vtx: &tri2vtx_obs,
+                vtx2xyz: &vtx2xyz_obs,
+            }],
+        },
+        &mut |_itr, _vtx2xyz, eng| itr2eng.push(eng),
+    );
+    crate::io_obj::save_tri2vtx_vtx2xyz(
+        "../target/deform_avoid_collision.obj",
+        &tri2vtx,
+        &vtx2xyz,
+        3,
+    )
+    .unwrap();
+    assert_eq!(vtx2xyz.len(), vtx2xyz_start.len());
+    assert!(!itr2eng.is_empty());
+    assert!(itr2eng.windows(2).all(|w| w[1] <= w[0]));
+    assert_eq!(vtx2xyz[0..3], vtx2xyz_start[0..3]);
+    // moved toward the goal but stopped in front of the obstacle
+    let cntr_x = vtx2xyz.chunks(3).map(|p| p[0]).sum::<f32>() / num_vtx as f32;
+    assert!(cntr_x > -0.95, "{cntr_x}");
+    let pairs = crate::trimesh3_intersection::search_between_two_meshes_brute_force(
+        &tri2vtx,
+        &vtx2xyz,
+        &tri2vtx_obs,
+        &vtx2xyz_obs,
+    );
+    assert!(pairs.is_empty());
+    assert!(vtx2xyz
+        .chunks(3)
+        .all(|p| del_geo_core::vec3::norm(arrayref::array_ref![p, 0, 3]) > 0.45));
+}
@@ -1,6 +1,79 @@
+//! search of the contacting (proximate) pairs of elements in a triangle mesh
+
 use num_traits::AsPrimitive;
 
+/// barycentric coordinates `[ra0, ra1, rb0, rb1]` of the nearest points of two edges
+/// if they are closer than `threshold`. `None` if the edges share a vertex
 #[allow(clippy::identity_op)]
+fn contacting_edge_edge<T>(
+    edge2vtx: &[usize],
+    vtx2xyz: &[T],
+    i_edge: usize,
+    j_edge: usize,
+    threshold: T,
+) -> Option<[T; 4]>
+where
+    T: num_traits::Float,
+{
+    let i0 = edge2vtx[i_edge * 2 + 0];
+    let i1 = edge2vtx[i_edge * 2 + 1];
+    let j0 = edge2vtx[j_edge * 2 + 0];
+    let j1 = edge2vtx[j_edge * 2 + 1];
+    if i0 == j0 || i0 == j1 || i1 == j0 || i1 == j1 {
+        return None;
+    };
+    use crate::vtx2xyz::to_vec3;
+    let a0 = to_vec3(vtx2xyz, i0);
+    let a1 = to_vec3(vtx2xyz, i1);
+    let b0 = to_vec3(vtx2xyz, j0);
+    let b1 = to_vec3(vtx2xyz, j1);
+    let (dist, ra1, rb1) = del_geo_core::edge3::nearest_to_edge3(a0, a1, b0, b1);
+    if dist > threshold {
+        return None;
+    }
+    let (ra0, rb0) = (T::one() - ra1, T::one() - rb1);
+    Some([ra0, ra1, rb0, rb1])
+}
+
+/// barycentric coordinates `[rf0, rf1, rf2, 1]` of the point on the triangle nearest to the vertex
+/// if they are closer than `threshold`. `None` if the vertex is a corner of the triangle
+#[allow(clippy::identity_op)]
+fn contacting_face_vertex<T>(
+    tri2vtx: &[usize],
+    vtx2xyz: &[T],
+    i_tri: usize,
+    j_vtx: usize,
+    threshold: T,
+) -> Option<[T; 4]>
+where
+    T: num_traits::Float,
+{
+    use del_geo_core::vec3::Vec3;
+    let i0 = tri2vtx[i_tri * 3 + 0];
+    let i1 = tri2vtx[i_tri * 3 + 1];
+    let i2 = tri2vtx[i_tri * 3 + 2];
+    if i0 == j_vtx || i1 == j_vtx || i2 == j_vtx {
+        return None;
+    };
+    use crate::vtx2xyz::to_vec3;
+    let f0 = to_vec3(vtx2xyz, i0);
+    let f1 = to_vec3(vtx2xyz, i1);
+    let f2 = to_vec3(vtx2xyz, i2);
+    let v0 = to_vec3(vtx2xyz, j_vtx);
+    let (_p, rf0, rf1) = del_geo_core::tri3::nearest_to_point3(f0, f1, f2, v0);
+    let rf2 = T::one() - rf0 - rf1;
+    let p0 = del_geo_core::vec3::add_three(&f0.scale(rf0), &f1.scale(rf1), &f2.scale(rf2));
+    let dist = p0.sub(v0).norm();
+    if dist > threshold {
+        return None;
+    }
+    Some([rf0, rf1, rf2, T::one()])
+}
+
+/// contacting pairs closer than `threshold` by checking all the pairs
+///
+/// returns the pairs `[i, j, kind]` (0: edge-edge, 1: triangle-vertex)
+/// and the four barycentric coordinates of each pair
 pub fn contacting_pair<T>(
     tri2vtx: &[usize],
     vtx2xyz: &[T],
@@ -11,32 +84,18 @@ where
     T: Copy + num_traits::Float + 'static + std::fmt::Debug,
     f64: AsPrimitive<T>,
 {
-    use del_geo_core::vec3::Vec3;
     let mut contacting_pair = vec![0usize; 0];
     let mut contacting_coord: Vec<T> = vec![];
     // edge-edge
     let num_edge = edge2vtx.len() / 2;
     for i_edge in 0..num_edge {
         for j_edge in i_edge + 1..num_edge {
-            let i0 = edge2vtx[i_edge * 2 + 0];
-            let i1 = edge2vtx[i_edge * 2 + 1];
-            let j0 = edge2vtx[j_edge * 2 + 0];
-            let j1 = edge2vtx[j_edge * 2 + 1];
-            if i0 == j0 || i0 == j1 || i1 == j0 || i1 == j1 {
+            let Some(coord) = contacting_edge_edge(edge2vtx, vtx2xyz, i_edge, j_edge, threshold)
+            else {
                 continue;
             };
-            use crate::vtx2xyz::to_vec3;
-            let a0 = to_vec3(vtx2xyz, i0);
-            let a1 = to_vec3(vtx2xyz, i1);
-            let b0 = to_vec3(vtx2xyz, j0);
-            let b1 = to_vec3(vtx2xyz, j1);
-            let (dist, ra1, rb1) = del_geo_core::edge3::nearest_to_edge3(a0, a1, b0, b1);
-            if dist > threshold {
-                continue;
-            }
-            let (ra0, rb0) = (T::one() - ra1, T::one() - rb1);
             contacting_pair.extend([i_edge, j_edge, 0]);
-            contacting_coord.extend([ra0, ra1, rb0, rb1]);
+            contacting_coord.extend(coord);
         }
     }
     // tri-vtx
@@ -44,27 +103,134 @@ where
     let num_vtx = vtx2xyz.len() / 3;
     for i_tri in 0..num_tri {
         for j_vtx in 0..num_vtx {
-            let i0 = tri2vtx[i_tri * 3 + 0];
-            let i1 = tri2vtx[i_tri * 3 + 1];
-            let i2 = tri2vtx[i_tri * 3 + 2];
-            if i0 == j_vtx || i1 == j_vtx || i2 == j_vtx {
+            let Some(coord) = contacting_face_vertex(tri2vtx, vtx2xyz, i_tri, j_vtx, threshold)
+            else {
                 continue;
             };
-            use crate::vtx2xyz::to_vec3;
-            let f0 = to_vec3(vtx2xyz, i0);
-            let f1 = to_vec3(vtx2xyz, i1);
-            let f2 = to_vec3(vtx2xyz, i2);
-            let v0 = to_vec3(vtx2xyz, j_vtx);
-            let (_p, rf0, rf1) = del_geo_core::tri3::nearest_to_point3(f0, f1, f2, v0);
-            let rf2 = T::one() - rf0 - rf1;
-            let p0 = del_geo_core::vec3::add_three(&f0.scale(rf0), &f1.scale(rf1), &f2.scale(rf2));
-            let dist = p0.sub(v0).norm();
-            if dist > threshold {
-                continue;
-            }
             contacting_pair.extend([i_tri, j_vtx, 1]);
-            contacting_coord.extend([rf0, rf1, rf2, T::one()]);
+            contacting_coord.extend(coord);
         }
     }
     (contacting_pair, contacting_coord)
 }
+
+/// contacting pairs closer than `threshold` using BVH.
+/// The output is the same as [`contacting_pair`] except for the order of the pairs.
+/// * `bvhnodes` - BVHs of vertices, edges and triangles concatenated (see [`crate::bvhnodes::concatenate`])
+/// * `aabbs` - AABBs of the BVH nodes. They are inflated by `threshold` during the search
+/// * `roots` - root node indices of the BVH of vertices, edges and triangles
+#[allow(clippy::too_many_arguments)]
+pub fn contacting_pair_with_bvh<T>(
+    tri2vtx: &[usize],
+    vtx2xyz: &[T],
+    edge2vtx: &[usize],
+    threshold: T,
+    bvhnodes: &[usize],
+    aabbs: &[T],
+    roots: &[usize; 3],
+) -> (Vec<usize>, Vec<T>)
+where
+    T: num_traits::Float,
+{
+    use crate::search_bvh3_pair::BvhWithTransform;
+    let bvh = BvhWithTransform {
+        bvhnodes,
+        bvhnode2aabb: aabbs,
+        transform: None,
+    };
+    let mut contacting_pair = vec![0usize; 0];
+    let mut contacting_coord: Vec<T> = vec![];
+    // edge-edge
+    crate::search_bvh3_pair::overlapping_leaves_inside_branch(
+        &mut |i_edge, j_edge| {
+            let (i_edge, j_edge) = (i_edge.min(j_edge), i_edge.max(j_edge));
+            let Some(coord) = contacting_edge_edge(edge2vtx, vtx2xyz, i_edge, j_edge, threshold)
+            else {
+                return;
+            };
+            contacting_pair.extend([i_edge, j_edge, 0]);
+            contacting_coord.extend(coord);
+        },
+        &bvh,
+        roots[1],
+        threshold,
+    );
+    // tri-vtx
+    crate::search_bvh3_pair::overlapping_leaves(
+        &mut |i_tri, j_vtx| {
+            let Some(coord) = contacting_face_vertex(tri2vtx, vtx2xyz, i_tri, j_vtx, threshold)
+            else {
+                return;
+            };
+            contacting_pair.extend([i_tri, j_vtx, 1]);
+            contacting_coord.extend(coord);
+        },
+        &bvh,
+        roots[2],
+        &bvh,
+        roots[0],
+        threshold,
+    );
+    (contacting_pair, contacting_coord)
+}
+
+#[test]
+fn test_contacting_pair() {
+    // a torus and a sphere slightly overlapping
+    let (tri2vtx, vtx2xyz) = {
+        let (tri2vtx0, vtx2xyz0) =
+            crate::trimesh3_primitive::torus_zup::<usize, f32>(0.6, 0.2, 32, 12);
+        let (tri2vtx1, vtx2xyz1) = crate::trimesh3_primitive::sphere_yup::<usize, f32>(0.3, 12, 24);
+        let vtx2xyz1: Vec<f32> = vtx2xyz1
+            .chunks(3)
+            .flat_map(|p| [p[0] + 0.6, p[1] + 0.02, p[2] + 0.51])
+            .collect();
+        let mut tri2vtx = vec![];
+        let mut vtx2xyz = vec![];
+        crate::uniform_mesh::merge(&mut tri2vtx, &mut vtx2xyz, &tri2vtx0, &vtx2xyz0, 3);
+        crate::uniform_mesh::merge(&mut tri2vtx, &mut vtx2xyz, &tri2vtx1, &vtx2xyz1, 3);
+        (tri2vtx, vtx2xyz)
+    };
+    let num_vtx = vtx2xyz.len() / 3;
+    let edge2vtx = crate::edge2vtx::from_triangle_mesh(&tri2vtx, num_vtx);
+    let (bvhnodes, roots) = {
+        let edge2cntr = crate::elem2center::from_uniform_mesh_as_points(&edge2vtx, 2, &vtx2xyz, 3);
+        let tri2cntr = crate::elem2center::from_uniform_mesh_as_points(&tri2vtx, 3, &vtx2xyz, 3);
+        let (bvhnodes, roots) = crate::bvhnodes::concatenate(&[
+            &crate::bvhnodes_morton::from_vtx2xyz::<usize>(&vtx2xyz, 3),
+            &crate::bvhnodes_morton::from_vtx2xyz::<usize>(&edge2cntr, 3),
+            &crate::bvhnodes_morton::from_vtx2xyz::<usize>(&tri2cntr, 3),
+        ]);
+        (bvhnodes, [roots[0], roots[1], roots[2]])
+    };
+    let mut aabbs = vec![0f32; bvhnodes.len() / 3 * 6];
+    for (i_root, elem2vtx) in [
+        None,
+        Some((edge2vtx.as_slice(), 2)),
+        Some((tri2vtx.as_slice(), 3)),
+    ]
+    .into_iter()
+    .enumerate()
+    {
+        crate::bvhnode2aabb3::update_for_uniform_mesh_with_bvh(
+            &mut aabbs,
+            roots[i_root],
+            &bvhnodes,
+            elem2vtx,
+            &vtx2xyz,
+            None,
+        );
+    }
+    let threshold = 0.03f32;
+    let (pairs0, coords0) = contacting_pair(&tri2vtx, &vtx2xyz, &edge2vtx, threshold);
+    let (pairs1, coords1) = contacting_pair_with_bvh(
+        &tri2vtx, &vtx2xyz, &edge2vtx, threshold, &bvhnodes, &aabbs, &roots,
+    );
+    assert!(pairs0.chunks(3).any(|v| v[2] == 0));
+    assert!(pairs0.chunks(3).any(|v| v[2] == 1));
+    let mut a: Vec<_> = pairs0.chunks(3).zip(coords0.chunks(4)).collect();
+    let mut b: Vec<_> = pairs1.chunks(3).zip(coords1.chunks(4)).collect();
+    a.sort_by(|x, y| x.0.cmp(y.0));
+    b.sort_by(|x, y| x.0.cmp(y.0));
+    assert_eq!(a, b);
+}
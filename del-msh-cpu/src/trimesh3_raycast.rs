@@ -47,6 +47,144 @@ pub fn update_pix2tri<Index>(
         .for_each(|(i_pix, i_tri)| *i_tri = tri_for_pix(i_pix));
 }
 
+/// same as `update_pix2tri` but the rays of 4x2 pixel tiles are traced together as a packet
+/// (see `search_bvh3_packet`)
+pub fn update_pix2tri_with_packet<Index>(
+    pix2tri: &mut [Index],
+    tri2vtx: &[Index],
+    vtx2xyz: &[f32],
+    bvhnodes: &[Index],
+    bvhnode2aabb: &[f32],
+    img_shape: (usize, usize), // (width, height)
+    transform_ndc2world: &[f32; 16],
+) where
+    Index: num_traits::PrimInt + AsPrimitive<usize> + Sync + Send,
+    usize: AsPrimitive<Index>,
+{
+    use rayon::prelude::*;
+    const TILE_W: usize = 4;
+    const TILE_H: usize = crate::search_bvh3_packet::PACKET_SIZE / TILE_W;
+    assert_eq!(pix2tri.len(), img_shape.0 * img_shape.1);
+    let num_tile_w = img_shape.0.div_ceil(TILE_W);
+    let num_tile_h = img_shape.1.div_ceil(TILE_H);
+    let num_ray = num_tile_w * num_tile_h * TILE_W * TILE_H;
+    // pixel coordinate of the ray. The rays in a tile are consecutive
+    let ray2pix = |i_ray: usize| -> (usize, usize) {
+        let i_tile = i_ray / (TILE_W * TILE_H);
+        let k = i_ray % (TILE_W * TILE_H);
+        let (i_tile_h, i_tile_w) = (i_tile / num_tile_w, i_tile % num_tile_w);
+        (
+            i_tile_w * TILE_W + k % TILE_W,
+            i_tile_h * TILE_H + k / TILE_W,
+        )
+    };
+    let mut ray2org = vec![0f32; num_ray * 3];
+    let mut ray2dir = vec![0f32; num_ray * 3];
+    ray2org
+        .par_chunks_mut(3)
+        .zip(ray2dir.par_chunks_mut(3))
+        .enumerate()
+        .for_each(|(i_ray, (org, dir))| {
+            let (i_w, i_h) = ray2pix(i_ray);
+            let (ray_org, ray_dir) =
+                del_geo_core::mat4_col_major::ray_from_transform_ndc2world_and_pixel_coordinate(
+                    (i_w as f32, i_h as f32),
+                    &(img_shape.0 as f32, img_shape.1 as f32),
+                    transform_ndc2world,
+                );
+            org.copy_from_slice(&ray_org);
+            dir.copy_from_slice(&ray_dir);
+        });
+    let mut ray2tri = vec![Index::max_value(); num_ray];
+    let mut ray2t = vec![0f32; num_ray];
+    crate::search_bvh3_packet::first_intersection_rays(
+        &mut ray2tri,
+        &mut ray2t,
+        &ray2org,
+        &ray2dir,
+        &crate::search_bvh3::TriMeshWithBvh {
+            tri2vtx,
+            vtx2xyz,
+            bvhnodes,
+            bvhnode2aabb,
+        },
+        0,
+    );
+    for (i_ray, &i_tri) in ray2tri.iter().enumerate() {
+        let (i_w, i_h) = ray2pix(i_ray);
+        if i_w < img_shape.0 && i_h < img_shape.1 {
+            pix2tri[i_h * img_shape.0 + i_w] = i_tri;
+        }
+    }
+}
+
+#[test]
+fn test_update_pix2tri_with_packet() {
+    let (tri2vtx, vtx2xyz) = crate::trimesh3_primitive::torus_zup::<usize, f32>(0.5, 0.2, 32, 32);
+    let bvhnodes = crate::bvhnodes_morton::from_triangle_mesh(&tri2vtx, &vtx2xyz, 3);
+    let bvhnode2aabb = crate::bvhnode2aabb3::from_uniform_mesh_with_bvh(
+        0,
+        &bvhnodes,
+        Some((&tri2vtx, 3)),
+        &vtx2xyz,
+        None,
+    );
+    let img_shape = (61, 43);
+    let transform_ndc2world = del_geo_core::mat4_col_major::from_identity::<f32>();
+    let mut pix2tri0 = vec![0usize; img_shape.0 * img_shape.1];
+    update_pix2tri(
+        &mut pix2tri0,
+        &tri2vtx,
+        &vtx2xyz,
+        &bvhnodes,
+        &bvhnode2aabb,
+        img_shape,
+        &transform_ndc2world,
+    );
+    let mut pix2tri1 = vec![0usize; img_shape.0 * img_shape.1];
+    update_pix2tri_with_packet(
+        &mut pix2tri1,
+        &tri2vtx,
+        &vtx2xyz,
+        &bvhnodes,
+        &bvhnode2aabb,
+        img_shape,
+        &transform_ndc2world,
+    );
+    // Both use the same triangle routine, so the hit of the same triangle has the same depth.
+    // They may differ at the ties (e.g., a shared edge) where the order of the visits decides the triangle,
+    // and at the rare pixels where the different AABB tests disagree at the boundary of a box
+    let mut num_miss_one_side = 0;
+    for (i_pix, (&i_tri0, &i_tri1)) in pix2tri0.iter().zip(pix2tri1.iter()).enumerate() {
+        if i_tri0 == i_tri1 {
+            continue;
+        }
+        if i_tri0 == usize::MAX || i_tri1 == usize::MAX {
+            num_miss_one_side += 1;
+            continue;
+        }
+        let (ray_org, ray_dir) =
+            del_geo_core::mat4_col_major::ray_from_transform_ndc2world_and_pixel_coordinate(
+                ((i_pix % img_shape.0) as f32, (i_pix / img_shape.0) as f32),
+                &(img_shape.0 as f32, img_shape.1 as f32),
+                &transform_ndc2world,
+            );
+        let t = |i_tri: usize| {
+            crate::trimesh3::to_tri3(&tri2vtx, &vtx2xyz, i_tri)
+                .intersection_against_ray(&ray_org, &ray_dir)
+        };
+        let (Some(t0), Some(t1)) = (t(i_tri0), t(i_tri1)) else {
+            panic!("the triangle found is not hit by the ray at {i_pix}");
+        };
+        assert!((t0 - t1).abs() < 1.0e-5, "{i_pix} {t0} {t1}");
+    }
+    assert!(
+        num_miss_one_side * 100 < pix2tri0.len(),
+        "{num_miss_one_side}"
+    );
+    assert!(pix2tri1.iter().any(|&i_tri| i_tri != usize::MAX));
+}
+
 pub fn render_depth_bvh(
     image_size: (usize, usize),
     img_data: &mut [f32],
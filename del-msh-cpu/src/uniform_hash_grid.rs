@@ -0,0 +1,344 @@
+//! uniform grid with spatial hashing for the neighbour search of N-dimensional points
+
+/// Uniform grid owning the coordinates of the points.
+/// The points are sorted by the hash of the cell they belong to (parallel counting sort),
+/// and the coordinates and the cells are stored in that order so that each bucket is contiguous in memory
+/// * `hash2idx` - the points in the cells with hash `i` are `idx2vtx[hash2idx[i]..hash2idx[i+1]]`
+/// * `idx2vecn` - coordinates of the point `idx2vtx[idx]`
+/// * `idx2cell` - integer coordinates of the cell of the point `idx2vtx[idx]`
+pub struct UniformHashGrid<Real, const N: usize> {
+    pub cell_size: Real,
+    pub hash2idx: Vec<usize>,
+    pub idx2vtx: Vec<usize>,
+    pub idx2vecn: Vec<Real>,
+    pub idx2cell: Vec<[i64; N]>,
+    /// bounding box of the cells of all the points
+    cell_min: [i64; N],
+    cell_max: [i64; N],
+}
+
+fn hash_of_cell<const N: usize>(cell: &[i64; N], num_hash: usize) -> usize {
+    const PRIMES: [u64; 4] = [73856093, 19349663, 83492791, 2654435761];
+    let h = cell.iter().enumerate().fold(0u64, |h, (i_dim, &c)| {
+        h ^ (c as u64).wrapping_mul(PRIMES[i_dim % 4])
+    });
+    (h % num_hash as u64) as usize
+}
+
+impl<Real, const N: usize> UniformHashGrid<Real, N>
+where
+    Real: num_traits::Float + num_traits::AsPrimitive<i64> + Send + Sync,
+{
+    /// * `cell_size` - edge length of the cell. Typically the search radius
+    pub fn from_vtx2vecn(vtx2vecn: &[Real], cell_size: Real) -> Self {
+        use rayon::prelude::*;
+        assert_eq!(vtx2vecn.len() % N, 0);
+        assert!(cell_size > Real::zero());
+        let num_vtx = vtx2vecn.len() / N;
+        let num_hash = (num_vtx * 2).next_power_of_two();
+        let vtx2cell: Vec<[i64; N]> = vtx2vecn
+            .par_chunks(N)
+            .map(|p| std::array::from_fn(|i_dim| (p[i_dim] / cell_size).floor().as_()))
+            .collect();
+        let vtx2hash: Vec<usize> = vtx2cell
+            .par_iter()
+            .map(|cell| hash_of_cell(cell, num_hash))
+            .collect();
+        // parallel counting sort. The range of the hash is divided into blocks.
+        // The points are distributed to the blocks chunk by chunk, and each block is sorted independently.
+        // The sort is stable, so the points in a cell are in the ascending order as in the serial one
+        let num_block = rayon::current_num_threads().clamp(1, num_hash);
+        let chunk_size = num_vtx.div_ceil(num_block).max(1);
+        let chunk2block2vtxs: Vec<Vec<Vec<usize>>> = vtx2hash
+            .par_chunks(chunk_size)
+            .enumerate()
+            .map(|(i_chunk, hashes)| {
+                let mut block2vtxs = vec![vec![]; num_block];
+                for (i, &i_hash) in hashes.iter().enumerate() {
+                    block2vtxs[i_hash * num_block / num_hash].push(i_chunk * chunk_size + i);
+                }
+                block2vtxs
+            })
+            .collect();
+        // start of each hash in the block and the sorted points of the block
+        let block2sorted: Vec<(Vec<usize>, Vec<usize>)> = (0..num_block)
+            .into_par_iter()
+            .map(|i_block| {
+                let hash_begin = (i_block * num_hash).div_ceil(num_block);
+                let hash_end = ((i_block + 1) * num_hash).div_ceil(num_block);
+                let vtxs = chunk2block2vtxs
+                    .iter()
+                    .flat_map(|block2vtxs| block2vtxs[i_block].iter());
+                let mut hash2start = vec![0usize; hash_end - hash_begin + 1];
+                for &i_vtx in vtxs.clone() {
+                    hash2start[vtx2hash[i_vtx] - hash_begin + 1] += 1;
+                }
+                for i in 0..hash_end - hash_begin {
+                    hash2start[i + 1] += hash2start[i];
+                }
+                let mut idx2vtx = vec![0usize; hash2start[hash_end - hash_begin]];
+                let mut hash2cnt = hash2start.clone();
+                for &i_vtx in vtxs {
+                    let i = vtx2hash[i_vtx] - hash_begin;
+                    idx2vtx[hash2cnt[i]] = i_vtx;
+                    hash2cnt[i] += 1;
+                }
+                hash2start.pop();
+                (hash2start, idx2vtx)
+            })
+            .collect();
+        let block2offset: Vec<usize> = block2sorted
+            .iter()
+            .scan(0, |offset, (_, idx2vtx)| {
+                let o = *offset;
+                *offset += idx2vtx.len();
+                Some(o)
+            })
+            .collect();
+        let hash2idx: Vec<usize> = block2sorted
+            .par_iter()
+            .zip(block2offset.par_iter())
+            .flat_map_iter(|((hash2start, _), &offset)| hash2start.iter().map(move |&i| i + offset))
+            .chain(rayon::iter::once(num_vtx))
+            .collect();
+        let idx2vtx: Vec<usize> = block2sorted
+            .into_par_iter()
+            .flat_map_iter(|(_, idx2vtx)| idx2vtx)
+            .collect();
+        let idx2vecn: Vec<Real> = idx2vtx
+            .par_iter()
+            .flat_map_iter(|&i_vtx| vtx2vecn[i_vtx * N..(i_vtx + 1) * N].iter().copied())
+            .collect();
+        let idx2cell: Vec<[i64; N]> = idx2vtx.par_iter().map(|&i_vtx| vtx2cell[i_vtx]).collect();
+        let (cell_min, cell_max) = vtx2cell
+            .par_iter()
+            .fold(
+                || ([i64::MAX; N], [i64::MIN; N]),
+                |(min, max), c| {
+                    (
+                        std::array::from_fn(|i_dim| min[i_dim].min(c[i_dim])),
+                        std::array::from_fn(|i_dim| max[i_dim].max(c[i_dim])),
+                    )
+                },
+            )
+            .reduce(
+                || ([i64::MAX; N], [i64::MIN; N]),
+                |(min0, max0), (min1, max1)| {
+                    (
+                        std::array::from_fn(|i_dim| min0[i_dim].min(min1[i_dim])),
+                        std::array::from_fn(|i_dim| max0[i_dim].max(max1[i_dim])),
+                    )
+                },
+            );
+        UniformHashGrid {
+            cell_size,
+            hash2idx,
+            idx2vtx,
+            idx2vecn,
+            idx2cell,
+            cell_min,
+            cell_max,
+        }
+    }
+
+    pub fn num_vtx(&self) -> usize {
+        self.idx2vtx.len()
+    }
+
+    fn cell_of_pos(&self, pos: &[Real; N]) -> [i64; N] {
+        std::array::from_fn(|i_dim| (pos[i_dim] / self.cell_size).floor().as_())
+    }
+
+    /// distance between the point at `idx` in the sorted order and `pos`
+    fn dist_idx(&self, idx: usize, pos: &[Real; N]) -> Real {
+        self.idx2vecn[idx * N..(idx + 1) * N]
+            .iter()
+            .zip(pos.iter())
+            .map(|(&a, &b)| (a - b) * (a - b))
+            .fold(Real::zero(), |a, b| a + b)
+            .sqrt()
+    }
+
+    /// call `f(i_vtx, dist)` for all the points closer than `rad` from `pos`
+    pub fn for_each_neighbour<F>(&self, pos: &[Real; N], rad: Real, mut f: F)
+    where
+        F: FnMut(usize, Real),
+    {
+        if self.idx2vtx.is_empty() {
+            return;
+        }
+        let num_hash = self.hash2idx.len() - 1;
+        // the cells touching the ball, clipped by the cells of the points
+        let cell_min = self.cell_of_pos(&std::array::from_fn(|i_dim| pos[i_dim] - rad));
+        let cell_max = self.cell_of_pos(&std::array::from_fn(|i_dim| pos[i_dim] + rad));
+        let cell_min: [i64; N] =
+            std::array::from_fn(|i_dim| cell_min[i_dim].max(self.cell_min[i_dim]));
+        let cell_max: [i64; N] =
+            std::array::from_fn(|i_dim| cell_max[i_dim].min(self.cell_max[i_dim]));
+        if (0..N).any(|i_dim| cell_min[i_dim] > cell_max[i_dim]) {
+            return;
+        }
+        let num_cell = (0..N).fold(1u128, |n, i_dim| {
+            n.saturating_mul((cell_max[i_dim] as i128 - cell_min[i_dim] as i128 + 1) as u128)
+        });
+        if num_cell > num_hash as u128 {
+            // visiting the cells is more expensive than visiting all the points
+            for idx in 0..self.idx2vtx.len() {
+                let dist = self.dist_idx(idx, pos);
+                if dist < rad {
+                    f(self.idx2vtx[idx], dist);
+                }
+            }
+            return;
+        }
+        let mut cell = cell_min;
+        loop {
+            let i_hash = hash_of_cell(&cell, num_hash);
+            for idx in self.hash2idx[i_hash]..self.hash2idx[i_hash + 1] {
+                if self.idx2cell[idx] != cell {
+                    continue; // different cell with the same hash
+                }
+                let dist = self.dist_idx(idx, pos);
+                if dist < rad {
+                    f(self.idx2vtx[idx], dist);
+                }
+            }
+            // next cell in the box `cell_min..=cell_max`
+            let mut i_dim = 0;
+            while i_dim < N {
+                if cell[i_dim] < cell_max[i_dim] {
+                    cell[i_dim] += 1;
+                    break;
+                }
+                cell[i_dim] = cell_min[i_dim];
+                i_dim += 1;
+            }
+            if i_dim == N {
+                break;
+            }
+        }
+    }
+
+    /// indices of the points closer than `rad` from `pos` (in no particular order)
+    pub fn neighbours(&self, pos: &[Real; N], rad: Real) -> Vec<usize> {
+        let mut idx2vtx = vec![];
+        self.for_each_neighbour(pos, rad, |i_vtx, _| idx2vtx.push(i_vtx));
+        idx2vtx
+    }
+
+    /// all the pairs of the points closer than `rad` computed in parallel.
+    /// returns the flat array of the pairs `[i_vtx, j_vtx]` with `i_vtx < j_vtx` sorted by `i_vtx`
+    pub fn pairs_within(&self, rad: Real) -> Vec<usize> {
+        use rayon::prelude::*;
+        let idx2pairs: Vec<Vec<usize>> = (0..self.num_vtx())
+            .into_par_iter()
+            .map(|idx| {
+                let i_vtx = self.idx2vtx[idx];
+                let pos: &[Real; N] = self.idx2vecn[idx * N..(idx + 1) * N].try_into().unwrap();
+                let mut pairs = vec![];
+                self.for_each_neighbour(pos, rad, |j_vtx, _| {
+                    if i_vtx < j_vtx {
+                        pairs.extend([i_vtx, j_vtx]);
+                    }
+                });
+                pairs
+            })
+            .collect();
+        let mut vtx2pairs = vec![vec![]; self.num_vtx()];
+        for (idx, pairs) in idx2pairs.into_iter().enumerate() {
+            vtx2pairs[self.idx2vtx[idx]] = pairs;
+        }
+        vtx2pairs.concat()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::UniformHashGrid;
+
+    fn check<const N: usize>(cell_size: f64, rad: f64) {
+        use rand::Rng;
+        use rand::SeedableRng;
+        let mut reng = rand_chacha::ChaChaRng::seed_from_u64(0);
+        let num_vtx = 1000;
+        let vtx2vecn: Vec<f64> = (0..num_vtx * N)
+            .map(|_| reng.random::<f64>() * 2.0 - 1.0)
+            .collect();
+        let grid = UniformHashGrid::<f64, N>::from_vtx2vecn(&vtx2vecn, cell_size);
+        {
+            let mut idx2vtx = grid.idx2vtx.clone();
+            idx2vtx.sort();
+            assert_eq!(idx2vtx, (0..num_vtx).collect::<Vec<_>>());
+            // the points of each hash are in the ascending order
+            let num_hash = grid.hash2idx.len() - 1;
+            assert_eq!(grid.hash2idx[num_hash], num_vtx);
+            for i_hash in 0..num_hash {
+                let vtxs = &grid.idx2vtx[grid.hash2idx[i_hash]..grid.hash2idx[i_hash + 1]];
+                assert!(vtxs.windows(2).all(|v| v[0] < v[1]));
+                for idx in grid.hash2idx[i_hash]..grid.hash2idx[i_hash + 1] {
+                    let i_vtx = grid.idx2vtx[idx];
+                    assert_eq!(
+                        grid.idx2vecn[idx * N..(idx + 1) * N],
+                        vtx2vecn[i_vtx * N..(i_vtx + 1) * N]
+                    );
+                    let cell =
+                        grid.cell_of_pos(&std::array::from_fn(|i_dim| vtx2vecn[i_vtx * N + i_dim]));
+                    assert_eq!(grid.idx2cell[idx], cell);
+                    assert_eq!(super::hash_of_cell(&cell, num_hash), i_hash);
+                }
+            }
+        }
+        let dist = |i_vtx: usize, pos: &[f64]| {
+            (0..N)
+                .map(|i_dim| (vtx2vecn[i_vtx * N + i_dim] - pos[i_dim]).powi(2))
+                .sum::<f64>()
+                .sqrt()
+        };
+        for _itr in 0..100 {
+            let pos: [f64; N] = std::array::from_fn(|_| reng.random::<f64>() * 2.0 - 1.0);
+            let mut vtxs0 = grid.neighbours(&pos, rad);
+            vtxs0.sort();
+            let vtxs1: Vec<usize> = (0..num_vtx).filter(|&i| dist(i, &pos) < rad).collect();
+            assert_eq!(vtxs0, vtxs1);
+        }
+        let pairs0 = grid.pairs_within(rad);
+        let mut pairs1 = vec![];
+        for i_vtx in 0..num_vtx {
+            for j_vtx in i_vtx + 1..num_vtx {
+                if dist(i_vtx, &vtx2vecn[j_vtx * N..(j_vtx + 1) * N]) < rad {
+                    pairs1.extend([i_vtx, j_vtx]);
+                }
+            }
+        }
+        let mut pairs0: Vec<_> = pairs0.chunks(2).collect();
+        pairs0.sort();
+        let pairs1: Vec<_> = pairs1.chunks(2).collect();
+        assert_eq!(pairs0, pairs1);
+    }
+
+    #[test]
+    fn test_neighbours_and_pairs() {
+        check::<2>(0.1, 0.1);
+        check::<3>(0.2, 0.2);
+        // radius larger than the cell
+        check::<2>(0.05, 0.12);
+        check::<3>(0.1, 0.25);
+        // radius much larger than the cell
+        check::<2>(0.01, 3.0);
+        check::<3>(0.02, 3.0);
+        {
+            let vtx2xyz = [0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 1.0];
+            let grid = UniformHashGrid::<f64, 3>::from_vtx2vecn(&vtx2xyz, 1.0e-3);
+            let mut vtxs = grid.neighbours(&[0.5; 3], f64::INFINITY);
+            vtxs.sort();
+            assert_eq!(vtxs, vec![0, 1, 2]);
+            assert!(grid.neighbours(&[1.0e10; 3], 1.0).is_empty());
+            let mut vtxs = grid.neighbours(&[-1.0e10, 0., 0.], 2.0e10);
+            vtxs.sort();
+            assert_eq!(vtxs, vec![0, 1, 2]);
+        }
+        let grid = UniformHashGrid::<f64, 3>::from_vtx2vecn(&[], 0.1);
+        assert_eq!(grid.hash2idx, vec![0, 0]);
+        assert!(grid.neighbours(&[0.; 3], 1.).is_empty());
+    }
+}
@@ -28,7 +28,10 @@ __device__
 int device_Delta(int i, int j, const unsigned int* sortedMC, int nMC)
 {
   if ( j<0 || j >= nMC ){ return -1; }
-  return __clz(sortedMC[i] ^ sortedMC[j]);
+  const uint32_t mci = sortedMC[i];
+  const uint32_t mcj = sortedMC[j];
+  if( mci == mcj ){ return 32 + __clz(i ^ j); } // duplicated morton code: the index is used as a tie-breaker
+  return __clz(mci ^ mcj);
 }
 
 __device__
@@ -39,19 +42,6 @@ int2 device_MortonCode_DeterminRange(
 {
   if( imc == 0 ){ return make_int2(0,nMC-1); }
   // ----------------------
-  const std::uint32_t mc0 = sortedMC[imc-1];
-  const std::uint32_t mc1 = sortedMC[imc+0];
-  const std::uint32_t mc2 = sortedMC[imc+1];
-  if( mc0 == mc1 && mc1 == mc2 ){ // for hash value collision
-    int jmc=imc+1;
-    while (jmc<nMC-1){
-      jmc += 1;
-      if( sortedMC[jmc] != mc1 ) {
-        return make_int2(imc,jmc-1);
-      }
-    }
-    return make_int2(imc,jmc);
-  }
   int d = device_Delta(imc, imc + 1, sortedMC, nMC) - device_Delta(imc, imc - 1, sortedMC, nMC);
   d = d > 0 ? 1 : -1;
 
@@ -91,15 +81,13 @@ int device_MortonCode_FindSplit(
   if (iMC_start == iMC_last) { return -1; }
 
   // ------------------------------
-  const int common_prefix = __clz(sortedMC[iMC_start] ^ sortedMC[iMC_last]);
-
-  //handle duplicated morton code
-  if (common_prefix == 32 ){ return iMC_start; } // sizeof(std::uint32_t)*8
+  // duplicated morton codes are handled in device_Delta
+  const int nMC = iMC_last + 1;
+  const int common_prefix = device_Delta(iMC_start, iMC_last, sortedMC, nMC);
 
   // Use binary search to find where the next bit differs.
   // Specifically, we are looking for the highest object that
   // shares more than commonPrefix bits with the first one.
-  const std::uint32_t mcStart = sortedMC[iMC_start];
   int iMC_split = iMC_start; // initial guess
   int step = iMC_last - iMC_start;
   do
@@ -107,8 +95,7 @@ int device_MortonCode_FindSplit(
     step = (step + 1) >> 1; // exponential decrease
     const int newSplit = iMC_split + step; // proposed new position
     if (newSplit < iMC_last){
-      const unsigned int splitCode = sortedMC[newSplit];
-      int splitPrefix = __clz(mcStart ^ splitCode);
+      int splitPrefix = device_Delta(iMC_start, newSplit, sortedMC, nMC);
       if (splitPrefix > common_prefix){
         iMC_split = newSplit; // accept proposal
       }
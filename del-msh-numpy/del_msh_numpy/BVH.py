@@ -53,3 +53,32 @@ def aabb_uniform_mesh(
     else:
         pass
     return aabbs
+
+
+def save(
+        path_file: str,
+        bvhnodes: numpy.typing.NDArray,
+        aabbs: numpy.typing.NDArray,
+        elem2vtx: numpy.typing.NDArray,
+        vtx2xyz: numpy.typing.NDArray):
+    """ save BVH to a binary file with the hash of the mesh
+    :param path_file: path of the file
+    :param bvhnodes: BVH tree structure
+    :param aabbs: AABBs of the BVH nodes (float32)
+    :param elem2vtx: elements of the mesh used to build the BVH
+    :param vtx2xyz: vertex coordinates of the mesh (float32)
+    """
+    from .del_msh_numpy import save_bvh
+    save_bvh(path_file, bvhnodes, aabbs, elem2vtx, vtx2xyz)
+
+
+def load(
+        path_file: str,
+        elem2vtx: numpy.typing.NDArray,
+        vtx2xyz: numpy.typing.NDArray):
+    """ load BVH saved by `save`. Raise `ValueError` if the mesh is modified after saving
+    or the file is broken, and `IOError` if the file cannot be read
+    :return: bvhnodes and aabbs
+    """
+    from .del_msh_numpy import load_bvh
+    return load_bvh(path_file, elem2vtx, vtx2xyz)
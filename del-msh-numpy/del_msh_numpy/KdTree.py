@@ -6,6 +6,9 @@ def build_topology(
     if vtx2xy.shape[1] == 2:
         from del_msh_numpy.del_msh_numpy import kdtree_build_2d
         return kdtree_build_2d(vtx2xy)
+    elif vtx2xy.shape[1] == 3:
+        from del_msh_numpy.del_msh_numpy import kdtree_build_3d
+        return kdtree_build_3d(vtx2xy)
     else:
         assert False
 
@@ -20,3 +23,25 @@ def build_edge(
         return kdtree_edge_2d(tree, vtx2xy, vmin, vmax)
     else:
         assert False
+
+
+def k_nearest(
+        tree: numpy.typing.NDArray,
+        vtx2xyz: numpy.typing.NDArray,
+        qry2xyz: numpy.typing.NDArray,
+        k: int):
+    """indices and distances of the k-nearest points for each query (padded with the max index and inf)"""
+    assert vtx2xyz.shape[1] == 3
+    from del_msh_numpy.del_msh_numpy import kdtree_k_nearest_3d
+    return kdtree_k_nearest_3d(tree, vtx2xyz, qry2xyz, k)
+
+
+def inside_radius(
+        tree: numpy.typing.NDArray,
+        vtx2xyz: numpy.typing.NDArray,
+        qry2xyz: numpy.typing.NDArray,
+        rad: float):
+    """points closer than `rad` for each query in the jagged array format (qry2idx, idx2vtx)"""
+    assert vtx2xyz.shape[1] == 3
+    from del_msh_numpy.del_msh_numpy import kdtree_inside_radius_3d
+    return kdtree_inside_radius_3d(tree, vtx2xyz, qry2xyz, rad)
@@ -342,11 +342,11 @@ def aabb_vtxedgetri(
     # edge
     aabbs = aabb_uniform_mesh(
         edge2vtx, vtx2xyz0, bvhnodes,
-        aabbs=aabbs, root=roots[0], vtx2xyz1=vtx2xyz1)
+        aabbs=aabbs, root=roots[1], vtx2xyz1=vtx2xyz1)
     # triangle
     aabbs = aabb_uniform_mesh(
         tri2vtx, vtx2xyz0, bvhnodes,
-        aabbs=aabbs, root=roots[0], vtx2xyz1=vtx2xyz1)
+        aabbs=aabbs, root=roots[2], vtx2xyz1=vtx2xyz1)
     return aabbs
 
 
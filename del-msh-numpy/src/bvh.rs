@@ -19,6 +19,9 @@ pub fn add_functions(_py: pyo3::Python, m: &Bound<pyo3::types::PyModule>) -> pyo
         build_bvh_geometry_aabb_uniformmesh_f64,
         m
     )?)?;
+    // io
+    m.add_function(wrap_pyfunction!(save_bvh, m)?)?;
+    m.add_function(wrap_pyfunction!(load_bvh, m)?)?;
     Ok(())
 }
 
@@ -189,3 +192,71 @@ fn build_bvh_geometry_aabb_uniformmesh_f64<'a>(
         vtx2xyz1,
     );
 }
+
+/// save BVH with the hash of the mesh `elem2vtx` and `vtx2xyz`
+#[pyo3::pyfunction]
+fn save_bvh<'a>(
+    _py: pyo3::Python<'a>,
+    path_file: String,
+    bvhnodes: numpy::PyReadonlyArray2<'a, usize>,
+    aabbs: numpy::PyReadonlyArray2<'a, f32>,
+    elem2vtx: numpy::PyReadonlyArray2<'a, usize>,
+    vtx2xyz: numpy::PyReadonlyArray2<'a, f32>,
+) -> pyo3::PyResult<()> {
+    assert!(bvhnodes.is_c_contiguous());
+    assert!(aabbs.is_c_contiguous());
+    assert!(elem2vtx.is_c_contiguous());
+    assert!(vtx2xyz.is_c_contiguous());
+    let hash = del_msh_cpu::io_bvh::hash_of_mesh(
+        elem2vtx.as_slice().unwrap(),
+        vtx2xyz.as_slice().unwrap(),
+    );
+    del_msh_cpu::io_bvh::save(
+        &path_file,
+        bvhnodes.as_slice().unwrap(),
+        aabbs.as_slice().unwrap(),
+        hash,
+    )
+    .map_err(|e| pyo3::exceptions::PyIOError::new_err(e.to_string()))
+}
+
+/// load BVH saved by `save_bvh`. Raise `ValueError` if the mesh is different from the saved one
+/// or the file is broken, and `IOError` if the file cannot be read
+#[pyo3::pyfunction]
+#[allow(clippy::type_complexity)]
+fn load_bvh<'a>(
+    _py: pyo3::Python<'a>,
+    path_file: String,
+    elem2vtx: numpy::PyReadonlyArray2<'a, usize>,
+    vtx2xyz: numpy::PyReadonlyArray2<'a, f32>,
+) -> pyo3::PyResult<(
+    Bound<'a, numpy::PyArray2<usize>>,
+    Bound<'a, numpy::PyArray2<f32>>,
+)> {
+    use numpy::IntoPyArray;
+    assert!(elem2vtx.is_c_contiguous());
+    assert!(vtx2xyz.is_c_contiguous());
+    let hash = del_msh_cpu::io_bvh::hash_of_mesh(
+        elem2vtx.as_slice().unwrap(),
+        vtx2xyz.as_slice().unwrap(),
+    );
+    let (bvhnodes, aabbs) =
+        del_msh_cpu::io_bvh::load::<_, usize>(&path_file, hash).map_err(|e| {
+            if e.downcast_ref::<std::io::Error>().is_some() {
+                pyo3::exceptions::PyIOError::new_err(e.to_string())
+            } else {
+                pyo3::exceptions::PyValueError::new_err(e.to_string())
+            }
+        })?;
+    let num_bvhnode = bvhnodes.len() / 3;
+    // the empty BVH is saved with zero values per AABB
+    let num_aabb_value = aabbs.len().checked_div(num_bvhnode).unwrap_or(0);
+    Ok((
+        numpy::ndarray::Array2::from_shape_vec((num_bvhnode, 3), bvhnodes)
+            .unwrap()
+            .into_pyarray(_py),
+        numpy::ndarray::Array2::from_shape_vec((num_bvhnode, num_aabb_value), aabbs)
+            .unwrap()
+            .into_pyarray(_py),
+    ))
+}
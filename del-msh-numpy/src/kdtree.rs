@@ -8,6 +8,9 @@ pub fn add_functions(_py: pyo3::Python, m: &Bound<pyo3::types::PyModule>) -> pyo
     use pyo3::wrap_pyfunction;
     m.add_function(wrap_pyfunction!(kdtree_build_2d, m)?)?;
     m.add_function(wrap_pyfunction!(kdtree_edge_2d, m)?)?;
+    m.add_function(wrap_pyfunction!(kdtree_build_3d, m)?)?;
+    m.add_function(wrap_pyfunction!(kdtree_k_nearest_3d, m)?)?;
+    m.add_function(wrap_pyfunction!(kdtree_inside_radius_3d, m)?)?;
     Ok(())
 }
 
@@ -67,3 +70,65 @@ fn kdtree_edge_2d<'a>(
         .unwrap()
         .into_pyarray(_py)
 }
+
+#[pyo3::pyfunction]
+pub fn kdtree_build_3d<'a>(
+    _py: pyo3::Python<'a>,
+    vtx2xyz: numpy::PyReadonlyArray2<'a, f64>,
+) -> Bound<'a, numpy::PyArray2<usize>> {
+    assert_eq!(vtx2xyz.shape()[1], 3);
+    let vtx2xyz = vtx2xyz.as_slice().unwrap();
+    let tree = del_msh_cpu::kdtree::KdTree::<f64, 3>::from_vtx2vecn(vtx2xyz).nodes;
+    numpy::ndarray::Array2::from_shape_vec((tree.len() / 3, 3), tree)
+        .unwrap()
+        .into_pyarray(_py)
+}
+
+#[pyo3::pyfunction]
+fn kdtree_k_nearest_3d<'a>(
+    _py: pyo3::Python<'a>,
+    tree: numpy::PyReadonlyArray2<'a, usize>,
+    vtx2xyz: numpy::PyReadonlyArray2<'a, f64>,
+    qry2xyz: numpy::PyReadonlyArray2<'a, f64>,
+    k: usize,
+) -> (
+    Bound<'a, numpy::PyArray2<usize>>,
+    Bound<'a, numpy::PyArray2<f64>>,
+) {
+    let num_qry = qry2xyz.shape()[0];
+    let tree = del_msh_cpu::kdtree::KdTree::<f64, 3> {
+        nodes: tree.as_slice().unwrap().to_vec(),
+        vtx2vecn: vtx2xyz.as_slice().unwrap().to_vec(),
+    };
+    let (qry2knn, qry2dist) = tree.k_nearest_for_points(qry2xyz.as_slice().unwrap(), k);
+    (
+        numpy::ndarray::Array2::from_shape_vec((num_qry, k), qry2knn)
+            .unwrap()
+            .into_pyarray(_py),
+        numpy::ndarray::Array2::from_shape_vec((num_qry, k), qry2dist)
+            .unwrap()
+            .into_pyarray(_py),
+    )
+}
+
+#[pyo3::pyfunction]
+fn kdtree_inside_radius_3d<'a>(
+    _py: pyo3::Python<'a>,
+    tree: numpy::PyReadonlyArray2<'a, usize>,
+    vtx2xyz: numpy::PyReadonlyArray2<'a, f64>,
+    qry2xyz: numpy::PyReadonlyArray2<'a, f64>,
+    rad: f64,
+) -> (
+    Bound<'a, numpy::PyArray1<usize>>,
+    Bound<'a, numpy::PyArray1<usize>>,
+) {
+    let tree = del_msh_cpu::kdtree::KdTree::<f64, 3> {
+        nodes: tree.as_slice().unwrap().to_vec(),
+        vtx2vecn: vtx2xyz.as_slice().unwrap().to_vec(),
+    };
+    let (qry2idx, idx2vtx) = tree.inside_radius_for_points(qry2xyz.as_slice().unwrap(), rad);
+    (
+        numpy::ndarray::Array1::from_vec(qry2idx).into_pyarray(_py),
+        numpy::ndarray::Array1::from_vec(idx2vtx).into_pyarray(_py),
+    )
+}
@@ -90,7 +90,14 @@ fn ccd_intersection_time<'a>(
         assert_eq!(bvhnodes.len() * 2, aabbs.len());
         assert_eq!(roots.len(), 3);
         del_msh_cpu::trimesh3_intersection_time::search_with_bvh(
-            edge2vtx, tri2vtx, vtx2xyz0, vtx2xyz1, bvhnodes, aabbs,
+            edge2vtx,
+            tri2vtx,
+            vtx2xyz0,
+            vtx2xyz1,
+            bvhnodes,
+            aabbs,
+            &[roots[0], roots[1], roots[2]],
+            1.0e-8f32,
         )
     };
     (
@@ -1,6 +1,7 @@
 import math
 #
 import numpy
+import pytest
 #
 from del_msh_numpy import TriMesh, BVH
 
@@ -45,4 +46,28 @@ def test_ccd():
     edge2node2xyz0, edge2tri0 = TriMesh.self_intersection(tri2vtx, vtx2xyz1)
     edge2node2xyz, edge2tri = TriMesh.self_intersection(tri2vtx, vtx2xyz1, bvhnodes, aabbs, roots[2])
     assert edge2node2xyz0.shape == edge2node2xyz.shape
-    assert edge2node2xyz.shape[0] != 0
\ No newline at end of file
+    assert edge2node2xyz.shape[0] != 0
+
+def test_save_load(tmp_path):
+    tri2vtx, vtx2xyz = TriMesh.sphere(1., 8, 4)
+    bvhnodes = TriMesh.bvhnodes_tri(tri2vtx, vtx2xyz)
+    aabbs = BVH.aabb_uniform_mesh(tri2vtx, vtx2xyz, bvhnodes, aabbs=None, vtx2xyz1=None)
+    path_file = str(tmp_path / "bvh.bin")
+    BVH.save(path_file, bvhnodes, aabbs, tri2vtx, vtx2xyz)
+    bvhnodes1, aabbs1 = BVH.load(path_file, tri2vtx, vtx2xyz)
+    assert numpy.array_equal(bvhnodes, bvhnodes1)
+    assert numpy.array_equal(aabbs, aabbs1)
+    vtx2xyz[0, 0] += 0.1
+    with pytest.raises(ValueError):
+        BVH.load(path_file, tri2vtx, vtx2xyz)
+    with pytest.raises(IOError):
+        BVH.load(str(tmp_path / "not_exist.bin"), tri2vtx, vtx2xyz)
+    # empty BVH
+    tri2vtx = numpy.zeros((0, 3), dtype=numpy.uint64)
+    vtx2xyz = numpy.zeros((0, 3), dtype=numpy.float32)
+    bvhnodes = numpy.zeros((0, 3), dtype=numpy.uint64)
+    aabbs = numpy.zeros((0, 6), dtype=numpy.float32)
+    BVH.save(path_file, bvhnodes, aabbs, tri2vtx, vtx2xyz)
+    bvhnodes1, aabbs1 = BVH.load(path_file, tri2vtx, vtx2xyz)
+    assert bvhnodes1.shape[0] == 0
+    assert aabbs1.shape[0] == 0
@@ -9,4 +9,20 @@ def test_01():
     from del_msh_numpy.del_msh_numpy import kdtree_edge_2d
     vmin = vtx2xy.min(axis=0)
     vmax = vtx2xy.max(axis=0)
-    edge2node2xy = kdtree_edge_2d(tree, vtx2xy, vmin, vmax)
\ No newline at end of file
+    edge2node2xy = kdtree_edge_2d(tree, vtx2xy, vmin, vmax)
+
+
+def test_02():
+    vtx2xyz = numpy.random.rand(300, 3)
+    qry2xyz = numpy.random.rand(20, 3)
+    from del_msh_numpy import KdTree
+    tree = KdTree.build_topology(vtx2xyz)
+    assert tree.shape == (300, 3)
+    qry2knn, qry2dist = KdTree.k_nearest(tree, vtx2xyz, qry2xyz, 4)
+    qry2idx, idx2vtx = KdTree.inside_radius(tree, vtx2xyz, qry2xyz, 0.2)
+    for i_qry in range(qry2xyz.shape[0]):
+        dist = numpy.linalg.norm(vtx2xyz - qry2xyz[i_qry], axis=1)
+        assert numpy.allclose(qry2dist[i_qry], numpy.sort(dist)[:4])
+        assert numpy.allclose(dist[qry2knn[i_qry]], qry2dist[i_qry])
+        vtxs = numpy.sort(idx2vtx[qry2idx[i_qry]:qry2idx[i_qry + 1]])
+        assert numpy.array_equal(vtxs, numpy.where(dist < 0.2)[0])